@@ -2,6 +2,8 @@ use std::fmt::Debug;
 
 use rustpython_parser::ast::{ExprName, Identifier};
 
+use crate::ast::{complex::Complex, number::Number};
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Ident {
     pub inner: String,
@@ -20,11 +22,16 @@ impl From<Identifier> for Ident {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub enum Value {
     // Decimal Value for Char
     Char(i32),
-    Number(f32),
+    Number(Number),
+    // Only with a non-zero imaginary part, a real result is a Number
+    Complex(Complex),
+    // What comparisons and conditions come out as
+    Bool(bool),
+    List(Vec<Value>),
 }
 
 impl Debug for Value {
@@ -39,6 +46,9 @@ impl Debug for Value {
                 ))
                 .finish(),
             Self::Number(arg0) => f.debug_tuple("Number").field(arg0).finish(),
+            Self::Complex(arg0) => f.debug_tuple("Complex").field(arg0).finish(),
+            Self::Bool(arg0) => f.debug_tuple("Bool").field(arg0).finish(),
+            Self::List(arg0) => f.debug_tuple("List").field(arg0).finish(),
         }
     }
 }
@@ -48,15 +58,53 @@ impl From<char> for Value {
         Value::Char((value as u32) as i32)
     }
 }
+impl From<Number> for Value {
+    fn from(value: Number) -> Value {
+        Value::Number(value)
+    }
+}
+
+impl From<Complex> for Value {
+    fn from(value: Complex) -> Value {
+        if value.is_real() {
+            Value::Number(value.re)
+        } else {
+            Value::Complex(value)
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Value {
+        Value::Number(value.into())
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Value {
+        Value::Number(value.into())
+    }
+}
+
 impl From<f32> for Value {
     fn from(value: f32) -> Value {
-        Value::Number(value)
+        Value::Number(value.into())
     }
 }
 
 impl From<f64> for Value {
     fn from(value: f64) -> Value {
-        Value::Number(value as f32)
+        Value::Number(value.into())
+    }
+}
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Bool(value)
+    }
+}
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Value {
+        Value::List(value)
     }
 }
 #[cfg(test)]
@@ -0,0 +1,248 @@
+use std::{
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use crate::ast::number::Number;
+
+// a + bi with exact parts where the numbers allow it, and the letter the
+// document writes the imaginary unit with (i, or j for engineers)
+#[derive(Clone, Debug)]
+pub struct Complex {
+    pub re: Number,
+    pub im: Number,
+    pub unit: char,
+}
+
+// Float noise below this (relative to the size) is dropped, e^(i pi) is -1 rather than -1 + 1.2e-16i
+const NOISE: f64 = 1e-12;
+
+impl Complex {
+    pub fn new(re: Number, im: Number, unit: char) -> Complex {
+        Complex { re, im, unit }
+    }
+
+    // The imaginary unit itself
+    pub fn i(unit: char) -> Complex {
+        Complex::new(Number::Integer(0), Number::Integer(1), unit)
+    }
+
+    pub fn real(re: Number, unit: char) -> Complex {
+        Complex::new(re, Number::Integer(0), unit)
+    }
+
+    fn polar(r: f64, theta: f64, unit: char) -> Complex {
+        let clean = |x: f64| if x.abs() < NOISE * r { 0.0 } else { x };
+        Complex::new(
+            Number::Float(clean(r * theta.cos())),
+            Number::Float(clean(r * theta.sin())),
+            unit,
+        )
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.im.is_zero()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.im.is_zero()
+    }
+
+    pub fn conj(&self) -> Complex {
+        Complex::new(self.re.clone(), -self.im.clone(), self.unit)
+    }
+
+    // |z|, exact when re^2 + im^2 is a square (|3 + 4i| = 5)
+    pub fn abs(&self) -> Number {
+        if self.is_real() {
+            return self.re.abs();
+        }
+        let norm = self.re.clone() * self.re.clone() + self.im.clone() * self.im.clone();
+        norm.root(2)
+            .unwrap_or_else(|| Number::Float(self.re.to_f64().hypot(self.im.to_f64())))
+    }
+
+    // The principal argument, in (-pi, pi]
+    pub fn arg(&self) -> Number {
+        if self.is_real() && self.re >= 0.0 {
+            return Number::Integer(0);
+        }
+        Number::Float(self.im.to_f64().atan2(self.re.to_f64()))
+    }
+
+    // Principal branch: whole powers by multiplying (exact), square roots of negative
+    // numbers exact where the real root is, everything else through exp(w ln z)
+    pub fn pow(&self, exponent: &Complex) -> Complex {
+        let unit = self.unit;
+        if exponent.is_real() {
+            if let Some(n) = exponent.re.as_integer() {
+                let (mut base, mut k) = (self.clone(), n.unsigned_abs());
+                let mut result = Complex::real(Number::Integer(1), unit);
+                while k > 0 {
+                    if k % 2 == 1 {
+                        result = result * base.clone();
+                    }
+                    base = base.clone() * base;
+                    k /= 2;
+                }
+                return if n < 0 {
+                    Complex::real(Number::Integer(1), unit) / result
+                } else {
+                    result
+                };
+            }
+            // (-4)^(1/2) = 2i, (-4)^(3/2) = -8i
+            if let Number::Rational(p, 2) = exponent.re
+                && self.is_real()
+                && self.re < 0.0
+            {
+                let size = (-self.re.clone()).pow(&exponent.re);
+                return match p.rem_euclid(4) {
+                    1 => Complex::new(Number::Integer(0), size, unit),
+                    _ => Complex::new(Number::Integer(0), -size, unit),
+                };
+            }
+        }
+
+        if self.is_zero() {
+            return Complex::real(Number::Integer(0), unit);
+        }
+        let (r, theta) = (self.abs().to_f64(), self.arg().to_f64());
+        let (c, d) = (exponent.re.to_f64(), exponent.im.to_f64());
+        Complex::polar((c * r.ln() - d * theta).exp(), d * r.ln() + c * theta, unit)
+    }
+
+    // The principal k-th root
+    pub fn root(&self, k: &Number) -> Complex {
+        self.pow(&Complex::real(Number::Integer(1) / k.clone(), self.unit))
+    }
+
+    // re + bi in a writer's notation, the real part is left out when it is 0 and b when it is 1
+    pub fn render(
+        &self,
+        number: impl Fn(Number) -> String,
+        unit: &str,
+        plus: &str,
+        minus: &str,
+    ) -> String {
+        let size = self.im.abs();
+        let im = if size == 1.0 {
+            unit.to_string()
+        } else {
+            format!("{}{}", number(size), unit)
+        };
+        match (self.re.is_zero(), self.im < 0.0) {
+            (true, false) => im,
+            (true, true) => format!("{}{}", minus.trim(), im),
+            (false, false) => format!("{}{}{}", number(self.re.clone()), plus, im),
+            (false, true) => format!("{}{}{}", number(self.re.clone()), minus, im),
+        }
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im, self.unit)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im, self.unit)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        let (a, b, c, d) = (self.re, self.im, other.re, other.im);
+        Complex::new(
+            a.clone() * c.clone() - b.clone() * d.clone(),
+            a * d + b * c,
+            self.unit,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, other: Complex) -> Complex {
+        let (a, b, c, d) = (self.re, self.im, other.re, other.im);
+        let norm = c.clone() * c.clone() + d.clone() * d.clone();
+        Complex::new(
+            (a.clone() * c.clone() + b.clone() * d.clone()) / norm.clone(),
+            (b * c - a * d) / norm,
+            self.unit,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im, self.unit)
+    }
+}
+
+// By value, the letter doesn't matter
+impl PartialEq for Complex {
+    fn eq(&self, other: &Complex) -> bool {
+        self.re == other.re && self.im == other.im
+    }
+}
+
+// 3 + 4i, 1 - i, -2i and (1/3)i, so a fraction isn't read as 1/(3i)
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let number = |n: Number| match n.fraction() {
+            Some(_) => format!("({})", n),
+            None => n.to_string(),
+        };
+        write!(
+            f,
+            "{}",
+            self.render(number, &self.unit.to_string(), " + ", " - ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    fn z(re: i64, im: i64) -> Complex {
+        Complex::new(Number::Integer(re), Number::Integer(im), 'i')
+    }
+
+    #[test]
+    fn arithmetic_stays_exact() {
+        assert_eq!(z(1, 2) * z(3, -1), z(5, 5));
+        assert_eq!((z(1, 1) / z(1, -1)).to_string(), "i");
+        assert_eq!((z(1, 0) / z(0, 3)).to_string(), "-(1/3)i");
+        assert_eq!(z(3, 4).abs(), Number::Integer(5));
+        assert_eq!(z(3, -4).conj().to_string(), "3 + 4i");
+    }
+
+    #[test]
+    fn principal_powers() {
+        assert_eq!(z(0, 1).pow(&z(2, 0)), z(-1, 0));
+        assert_eq!(z(1, 1).pow(&z(-2, 0)).to_string(), "-(1/2)i");
+        assert_eq!(z(-4, 0).root(&Number::Integer(2)), z(0, 2));
+        assert_eq!(
+            z(-4, 0).pow(&Complex::real(Number::Rational(3, 2), 'i')),
+            z(0, -8)
+        );
+        // e^(i pi) = -1
+        let euler = Complex::real(Number::Float(std::f64::consts::E), 'i');
+        let turn = Complex::new(Number::Integer(0), Number::Float(std::f64::consts::PI), 'i');
+        assert_eq!(euler.pow(&turn), z(-1, 0));
+    }
+}
@@ -1,12 +1,12 @@
 use crate::{
     ast::{
         base::{Ident, Value},
-        opperations::Opperation,
+        opperations::{Opperation, Precedence},
         system_func::System,
     },
     symbols::Symbols,
 };
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 #[derive(Debug)]
 pub enum Expression {
@@ -26,3 +26,76 @@ pub enum Expression {
     Constant(Value),
     Empty, // 0
 }
+
+impl Expression {
+    // Replace variables by expressions (function parameters by their arguments)
+    pub fn substitute(&self, bindings: &HashMap<String, Expression>) -> Expression {
+        match self {
+            Expression::VariableRef(ident) => bindings
+                .get(&ident.inner)
+                .cloned()
+                .unwrap_or_else(|| self.clone()),
+            // The index of a series is bound inside it
+            Expression::Opperations(opperation) => match opperation.binds() {
+                Some(index) if bindings.contains_key(&index.inner) => {
+                    let mut inner = bindings.clone();
+                    inner.remove(&index.inner);
+                    Expression::Opperations(opperation.map(&mut |e| e.substitute(&inner)))
+                }
+                _ => Expression::Opperations(opperation.map(&mut |e| e.substitute(bindings))),
+            },
+            Expression::List(items) => {
+                Expression::List(items.iter().map(|i| i.substitute(bindings)).collect())
+            }
+            Expression::FunctionCall(ident, args) => Expression::FunctionCall(
+                ident.clone(),
+                args.iter().map(|a| a.substitute(bindings)).collect(),
+            ),
+            Expression::System(system) => Expression::System(Box::new(match system.as_ref() {
+                System::Print(expression) => System::Print(expression.substitute(bindings)),
+                System::Simplify(expression) => System::Simplify(expression.substitute(bindings)),
+                // The variable of a derivative is bound inside it
+                System::Diff(expression, x) => {
+                    let mut inner = bindings.clone();
+                    inner.remove(&x.inner);
+                    System::Diff(expression.substitute(&inner), x.clone())
+                }
+                system => system.clone(),
+            })),
+            Expression::Constant(_) | Expression::Empty => self.clone(),
+        }
+    }
+
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            Expression::Opperations(opperation) => opperation.precedence(),
+            // A negative literal reads like a negation
+            Expression::Constant(Value::Number(n)) if *n < 0.0 => Precedence::Prefix,
+            // So does a fraction like 1/3
+            Expression::Constant(Value::Number(n)) if n.fraction().is_some() => Precedence::Product,
+            // 3 + 4i reads like a sum, -i like a negation and 4i like a product
+            Expression::Constant(Value::Complex(z)) if !z.re.is_zero() => Precedence::Sum,
+            Expression::Constant(Value::Complex(z)) if z.im < 0.0 => Precedence::Prefix,
+            Expression::Constant(Value::Complex(z)) if z.im != 1.0 => Precedence::Product,
+            _ => Precedence::Atom,
+        }
+    }
+}
+
+impl Clone for Expression {
+    fn clone(&self) -> Self {
+        match self {
+            Expression::Opperations(opperation) => {
+                Expression::Opperations(opperation.map(&mut |e| e.clone()))
+            }
+            Expression::List(items) => Expression::List(items.clone()),
+            Expression::FunctionCall(ident, args) => {
+                Expression::FunctionCall(ident.clone(), args.clone())
+            }
+            Expression::System(system) => Expression::System(system.clone()),
+            Expression::VariableRef(ident) => Expression::VariableRef(ident.clone()),
+            Expression::Constant(value) => Expression::Constant(value.clone()),
+            Expression::Empty => Expression::Empty,
+        }
+    }
+}
@@ -1,6 +1,8 @@
 pub mod base;
+pub mod complex;
 pub mod expressions;
 pub mod module;
+pub mod number;
 pub mod opperations;
 pub mod system_func;
 
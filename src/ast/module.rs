@@ -1,11 +1,13 @@
 use crate::ast::Statement;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Default)]
 pub struct Metadata {
     pub author: Option<String>,
     pub author_email: Option<String>,
     pub url: Option<String>,
     pub title: Option<String>,
+    // Complex mode, the letter for the imaginary unit (i or j)
+    pub imaginary: Option<char>,
 }
 
 pub enum ParseMode {
@@ -34,3 +36,46 @@ pub enum Module {
         statements: Vec<Statement>,
     },
 }
+
+impl Module {
+    pub fn metadata(&self) -> &Metadata {
+        match self {
+            Module::Entry { metadata, .. }
+            | Module::Module { metadata, .. }
+            | Module::Frame { metadata, .. } => metadata,
+        }
+    }
+
+    pub fn statements(&self) -> &Vec<Statement> {
+        match self {
+            Module::Entry { statements, .. }
+            | Module::Module { statements, .. }
+            | Module::Frame { statements, .. } => statements,
+        }
+    }
+
+    pub fn statements_mut(&mut self) -> &mut Vec<Statement> {
+        match self {
+            Module::Entry { statements, .. }
+            | Module::Module { statements, .. }
+            | Module::Frame { statements, .. } => statements,
+        }
+    }
+
+    pub fn into_parts(self) -> (Metadata, Vec<Statement>) {
+        match self {
+            Module::Entry {
+                metadata,
+                statements,
+            }
+            | Module::Module {
+                metadata,
+                statements,
+            }
+            | Module::Frame {
+                metadata,
+                statements,
+            } => (metadata, statements),
+        }
+    }
+}
@@ -0,0 +1,416 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+#[cfg(feature = "decimal")]
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive};
+
+// Numbers stay exact as long as they can: integers, fractions of integers and,
+// with the `decimal` feature, decimals of any size once a fraction outgrows i64.
+// Floats take over for the rest (sin(x), 2^0.5) and anything touching a float is a float.
+#[derive(Clone, Debug)]
+pub enum Number {
+    Integer(i64),
+    // Lowest terms, the denominator is above 1
+    Rational(i64, i64),
+    #[cfg(feature = "decimal")]
+    Decimal(BigDecimal),
+    Float(f64),
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+// Integer k-th root of n when there is one
+fn exact_root(n: i128, k: u32) -> Option<i128> {
+    if n < 0 {
+        return (k % 2 == 1).then(|| exact_root(-n, k).map(|r| -r))?;
+    }
+    let guess = (n as f64).powf(1.0 / k as f64).round() as i128;
+    (guess - 1..=guess + 1).find(|r| *r >= 0 && r.checked_pow(k) == Some(n))
+}
+
+impl Number {
+    // n / d in lowest terms, what doesn't fit in i64 goes further up the tower
+    fn ratio(n: i128, d: i128) -> Number {
+        if d == 0 {
+            return Number::Float(n as f64 / 0.0);
+        }
+        let g = gcd(n, d);
+        let (n, d) = if d < 0 {
+            (-n / g, -d / g)
+        } else {
+            (n / g, d / g)
+        };
+        match (i64::try_from(n), i64::try_from(d)) {
+            (Ok(n), Ok(1)) => Number::Integer(n),
+            (Ok(n), Ok(d)) => Number::Rational(n, d),
+            _ => Number::overflow(n, d),
+        }
+    }
+
+    #[cfg(feature = "decimal")]
+    fn overflow(n: i128, d: i128) -> Number {
+        Number::Decimal(BigDecimal::from(n) / BigDecimal::from(d))
+    }
+
+    #[cfg(not(feature = "decimal"))]
+    fn overflow(n: i128, d: i128) -> Number {
+        Number::Float(n as f64 / d as f64)
+    }
+
+    // A literal like 12, 0.25 or 1.5e3 as the narrowest exact number that holds it
+    pub fn parse(text: &str) -> Option<Number> {
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+            None => (unsigned, 0),
+        };
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{}{}", whole, fraction);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let exact = || {
+            let digits = digits.parse::<i128>().ok()?;
+            let digits = if negative { -digits } else { digits };
+            let scale = fraction.len() as i32 - exponent;
+            let power = 10i128.checked_pow(scale.unsigned_abs())?;
+            if scale >= 0 {
+                Some(Number::ratio(digits, power))
+            } else {
+                Some(Number::ratio(digits.checked_mul(power)?, 1))
+            }
+        };
+        exact().or_else(|| Number::long(text))
+    }
+
+    #[cfg(feature = "decimal")]
+    fn long(text: &str) -> Option<Number> {
+        text.parse::<BigDecimal>().ok().map(Number::Decimal)
+    }
+
+    #[cfg(not(feature = "decimal"))]
+    fn long(text: &str) -> Option<Number> {
+        text.parse::<f64>().ok().map(Number::Float)
+    }
+
+    // Numerator and denominator of an integer or fraction
+    pub(crate) fn parts(&self) -> Option<(i128, i128)> {
+        match self {
+            Number::Integer(n) => Some((*n as i128, 1)),
+            Number::Rational(n, d) => Some((*n as i128, *d as i128)),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "decimal")]
+    fn decimal(&self) -> Option<BigDecimal> {
+        match self {
+            Number::Integer(n) => Some(BigDecimal::from(*n)),
+            Number::Rational(n, d) => Some(BigDecimal::from(*n) / BigDecimal::from(*d)),
+            Number::Decimal(d) => Some(d.clone()),
+            Number::Float(_) => None,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Integer(n) => *n as f64,
+            Number::Rational(n, d) => *n as f64 / *d as f64,
+            #[cfg(feature = "decimal")]
+            Number::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+            Number::Float(f) => *f,
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        !matches!(self, Number::Float(_))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == 0.0
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.to_f64().is_finite()
+    }
+
+    // The value as an i64 when it is a whole number
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Number::Integer(n) => Some(*n),
+            Number::Rational(..) => None,
+            #[cfg(feature = "decimal")]
+            Number::Decimal(d) if d.is_integer() => d.to_i64(),
+            #[cfg(feature = "decimal")]
+            Number::Decimal(_) => None,
+            Number::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Some(*f as i64),
+            Number::Float(_) => None,
+        }
+    }
+
+    // The numerator and denominator of a fraction, writers show it as one (1/2, not 0.5)
+    pub fn fraction(&self) -> Option<(i64, i64)> {
+        match self {
+            Number::Rational(n, d) => Some((*n, *d)),
+            _ => None,
+        }
+    }
+
+    pub fn abs(&self) -> Number {
+        if *self < 0.0 {
+            -self.clone()
+        } else {
+            self.clone()
+        }
+    }
+
+    pub fn floor(&self) -> Number {
+        match self {
+            Number::Integer(_) => self.clone(),
+            Number::Rational(n, d) => Number::Integer(n.div_euclid(*d)),
+            #[cfg(feature = "decimal")]
+            Number::Decimal(d) => Number::Decimal(d.with_scale_round(0, RoundingMode::Floor)),
+            Number::Float(f) => Number::Float(f.floor()),
+        }
+    }
+
+    pub fn ceil(&self) -> Number {
+        -(-self.clone()).floor()
+    }
+
+    // Exact k-th root when the numerator and denominator both have one (root(3, 8/27) = 2/3)
+    pub fn root(&self, k: i64) -> Option<Number> {
+        let (n, d) = self.parts()?;
+        let k = u32::try_from(k).ok().filter(|k| *k > 0)?;
+        Some(Number::ratio(exact_root(n, k)?, exact_root(d, k)?))
+    }
+
+    // Exact for integer exponents and roots that come out even, a float otherwise
+    pub fn pow(&self, exponent: &Number) -> Number {
+        match (self.parts(), exponent) {
+            (Some((n, d)), Number::Integer(k)) => {
+                let power = u32::try_from(k.unsigned_abs()).ok();
+                let raised = power.and_then(|p| Some((n.checked_pow(p)?, d.checked_pow(p)?)));
+                match raised {
+                    Some((n, d)) if *k >= 0 => return Number::ratio(n, d),
+                    Some((n, d)) if n != 0 => return Number::ratio(d, n),
+                    _ => {}
+                }
+            }
+            (Some(_), Number::Rational(p, q)) => {
+                if let Some(root) = self.root(*q) {
+                    return root.pow(&Number::Integer(*p));
+                }
+            }
+            _ => {}
+        }
+        Number::Float(self.to_f64().powf(exponent.to_f64()))
+    }
+}
+
+// Either side isn't an integer or fraction: decimals when both are exact, floats otherwise
+macro_rules! inexact {
+    ($a:expr, $b:expr, $op:tt) => {{
+        #[cfg(feature = "decimal")]
+        if let (Some(a), Some(b)) = ($a.decimal(), $b.decimal()) {
+            return Number::Decimal(a $op b);
+        }
+        Number::Float($a.to_f64() $op $b.to_f64())
+    }};
+}
+
+impl Add for Number {
+    type Output = Number;
+
+    fn add(self, other: Number) -> Number {
+        match (self.parts(), other.parts()) {
+            (Some((a, b)), Some((c, d))) => Number::ratio(a * d + c * b, b * d),
+            _ => inexact!(self, other, +),
+        }
+    }
+}
+
+impl Sub for Number {
+    type Output = Number;
+
+    fn sub(self, other: Number) -> Number {
+        match (self.parts(), other.parts()) {
+            (Some((a, b)), Some((c, d))) => Number::ratio(a * d - c * b, b * d),
+            _ => inexact!(self, other, -),
+        }
+    }
+}
+
+impl Mul for Number {
+    type Output = Number;
+
+    fn mul(self, other: Number) -> Number {
+        match (self.parts(), other.parts()) {
+            (Some((a, b)), Some((c, d))) => Number::ratio(a * c, b * d),
+            _ => inexact!(self, other, *),
+        }
+    }
+}
+
+// Dividing by an exact zero gives an infinite (or NaN) float, the evaluator reports it first
+impl Div for Number {
+    type Output = Number;
+
+    fn div(self, other: Number) -> Number {
+        if other.is_zero() {
+            return Number::Float(self.to_f64() / 0.0);
+        }
+        match (self.parts(), other.parts()) {
+            (Some((a, b)), Some((c, d))) => Number::ratio(a * d, b * c),
+            _ => inexact!(self, other, /),
+        }
+    }
+}
+
+impl Neg for Number {
+    type Output = Number;
+
+    fn neg(self) -> Number {
+        match self {
+            Number::Integer(n) => Number::ratio(-(n as i128), 1),
+            Number::Rational(n, d) => Number::ratio(-(n as i128), d as i128),
+            #[cfg(feature = "decimal")]
+            Number::Decimal(d) => Number::Decimal(-d),
+            Number::Float(f) => Number::Float(-f),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        match (self.parts(), other.parts()) {
+            // Denominators are positive
+            (Some((a, b)), Some((c, d))) => Some((a * d).cmp(&(c * b))),
+            _ => {
+                #[cfg(feature = "decimal")]
+                if let (Some(a), Some(b)) = (self.decimal(), other.decimal()) {
+                    return a.partial_cmp(&b);
+                }
+                self.to_f64().partial_cmp(&other.to_f64())
+            }
+        }
+    }
+}
+
+// By value, 2 == 2.0 and 1/2 == 0.5
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialEq<f64> for Number {
+    fn eq(&self, other: &f64) -> bool {
+        self.to_f64() == *other
+    }
+}
+
+impl PartialOrd<f64> for Number {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        self.to_f64().partial_cmp(other)
+    }
+}
+
+// Text form, fractions without a short decimal form are written n/d
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Integer(n) => write!(f, "{}", n),
+            Number::Rational(n, d) => write!(f, "{}/{}", n, d),
+            #[cfg(feature = "decimal")]
+            Number::Decimal(d) => write!(f, "{}", d.normalized().to_plain_string()),
+            Number::Float(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(value: i64) -> Number {
+        Number::Integer(value)
+    }
+}
+
+impl From<i32> for Number {
+    fn from(value: i32) -> Number {
+        Number::Integer(value as i64)
+    }
+}
+
+impl From<f64> for Number {
+    fn from(value: f64) -> Number {
+        Number::Float(value)
+    }
+}
+
+impl From<f32> for Number {
+    fn from(value: f32) -> Number {
+        Number::Float(value as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    fn n(text: &str) -> Number {
+        Number::parse(text).expect("should parse")
+    }
+
+    #[test]
+    fn parse_narrowest_exact() {
+        assert!(matches!(n("12"), Number::Integer(12)));
+        assert!(matches!(n("12.0"), Number::Integer(12)));
+        assert!(matches!(n("0.25"), Number::Rational(1, 4)));
+        assert!(matches!(n("1.5e3"), Number::Integer(1500)));
+        assert!(matches!(n("-2e-1"), Number::Rational(-1, 5)));
+        assert_eq!(Number::parse("1.2.3"), None);
+    }
+
+    #[test]
+    fn arithmetic_stays_exact() {
+        assert!(matches!(n("0.1") + n("0.2"), Number::Rational(3, 10)));
+        assert_eq!((n("0.1") + n("0.2")).to_string(), "3/10");
+        assert_eq!((n("1") / n("3")).to_string(), "1/3");
+        assert!(matches!(n("1") / n("3") * n("3"), Number::Integer(1)));
+        assert!(matches!(n("2").pow(&n("-2")), Number::Rational(1, 4)));
+        assert!(matches!(n("4").pow(&(n("1") / n("2"))), Number::Integer(2)));
+        assert!(matches!(n("2").pow(&n("0.5")), Number::Float(_)));
+        // Floats are contagious
+        assert!(matches!(n("1") + Number::Float(0.5), Number::Float(_)));
+    }
+
+    #[test]
+    fn overflow_moves_up() {
+        let big = Number::Integer(i64::MAX) + Number::Integer(1);
+        assert!(!matches!(big, Number::Integer(_)));
+        assert_eq!(big, 9223372036854775808.0);
+    }
+
+    #[test]
+    fn compare_and_round() {
+        assert_eq!(n("2"), Number::Float(2.0));
+        assert!(n("1") / n("3") < n("0.34"));
+        assert!(matches!(n("-7") / n("2"), Number::Rational(-7, 2)));
+        assert_eq!((n("-7") / n("2")).floor().to_string(), "-4");
+        assert_eq!((n("-7") / n("2")).ceil().to_string(), "-3");
+        assert_eq!(n("0.375").to_string(), "3/8");
+    }
+}
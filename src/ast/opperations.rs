@@ -2,16 +2,49 @@
 use std::fmt::Debug;
 use crate::{
     ast::{base::{Ident, Value}, expressions::Expression},
+    calculus::{Differentiate, simplify::Simplify},
+    eval::Evaluate,
     symbols::Symbols,
+    writer::{display::mathml::Mathml, latex::Latex, python::Python, typst::Typst},
 };
 
+// How tightly an opperation binds, writers parenthesize operands that bind looser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    Or,
+    And,
+    Not,
+    Compare,
+    Sum,
+    Product,
+    Prefix,
+    Power,
+    Atom,
+}
+
 // Mathematical Functions
 pub trait Opperation
 where
-    Self: Debug,
+    Self: Debug + Evaluate + Differentiate + Simplify + Latex + Typst + Mathml + Python,
 {
     fn stringify(&self) -> String;
     fn ty(&self) -> &str;
+    fn precedence(&self) -> Precedence;
+    // Same opperation with every operand replaced by `f(operand)`
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation>;
+    // Variable bound inside the opperation (the index of a sum), outer values don't reach it
+    fn binds(&self) -> Option<&Ident> {
+        None
+    }
+}
+
+// Text form of an operand, in parentheses when it binds looser than `min`
+fn group(expression: &Expression, min: Precedence) -> String {
+    if expression.precedence() < min {
+        format!("({})", expression.to_string())
+    } else {
+        expression.to_string()
+    }
 }
 
 // Hold the Values
@@ -60,12 +93,53 @@ pub struct Index {
     pub index: Expression
 }
 
+// Prefix negation (-x)
+#[derive(Debug)]
+pub struct Neg {
+    pub value: Expression,
+}
+
+// sum(i, 1, n, body) or prod(i, 1, n, body), `index` runs over the integers from..=to
+#[derive(Debug)]
+pub struct Series {
+    pub symbol: Symbols,
+    pub index: Ident,
+    pub from: Expression,
+    pub to: Expression,
+    pub body: Expression,
+}
+
+// a < b, a <= b, a = b, a != b (> and >= too), chained like 0 < x <= 1
+#[derive(Debug)]
+pub struct Compare {
+    pub first: Expression,
+    pub rest: Vec<(Symbols, Expression)>,
+}
+
+// a and b, a or b
+#[derive(Debug)]
+pub struct Logic {
+    pub symbol: Symbols,
+    pub terms: Vec<Expression>,
+}
+
+#[derive(Debug)]
+pub struct Not {
+    pub value: Expression,
+}
+
+// { value if condition; ...; value otherwise }, the first case that holds is taken
+#[derive(Debug)]
+pub struct Piecewise {
+    pub cases: Vec<(Expression, Expression)>,
+    pub otherwise: Option<Expression>,
+}
 
 impl Opperation for Add {
     fn stringify(&self) -> String {
         self.terms
             .iter()
-            .map(|v| v.to_string())
+            .map(|v| group(v, Precedence::Sum))
             .collect::<Vec<_>>()
             .join(format!(" {} ", Symbols::Addition.as_str()).as_str())
     }
@@ -73,45 +147,304 @@ impl Opperation for Add {
     fn ty(&self) -> &str {
         "add"
     }
+    fn precedence(&self) -> Precedence {
+        Precedence::Sum
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Add {
+            terms: self.terms.iter().map(f).collect(),
+        })
+    }
 }
 
 impl Opperation for Multi {
     fn stringify(&self) -> String {
         self.terms
             .iter()
-            .map(|v| v.to_string())
+            .map(|v| group(v, Precedence::Product))
             .collect::<Vec<_>>()
             .join(format!(" {} ", Symbols::Multiplication.as_str()).as_str())
     }
     fn ty(&self) -> &str {
         "multi"
     }
+    fn precedence(&self) -> Precedence {
+        Precedence::Product
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Multi {
+            terms: self.terms.iter().map(f).collect(),
+        })
+    }
+}
+
+impl Opperation for Sub {
+    // Left-associative, so a grouped right side stays grouped: a - (b - c)
+    fn stringify(&self) -> String {
+        format!(
+            "{} {} {}",
+            group(&self.first, Precedence::Sum),
+            Symbols::Subtraction.as_str(),
+            group(&self.second, Precedence::Product)
+        )
+    }
+    fn ty(&self) -> &str {
+        "sub"
+    }
+    fn precedence(&self) -> Precedence {
+        Precedence::Sum
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Sub {
+            first: f(&self.first),
+            second: f(&self.second),
+        })
+    }
+}
+
+impl Opperation for Div {
+    fn stringify(&self) -> String {
+        format!(
+            "{} {} {}",
+            group(&self.first, Precedence::Product),
+            Symbols::Division.as_str(),
+            group(&self.second, Precedence::Prefix)
+        )
+    }
+    fn ty(&self) -> &str {
+        "div"
+    }
+    fn precedence(&self) -> Precedence {
+        Precedence::Product
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Div {
+            first: f(&self.first),
+            second: f(&self.second),
+        })
+    }
+}
+
+impl Opperation for Neg {
+    fn stringify(&self) -> String {
+        format!(
+            "{}{}",
+            Symbols::Subtraction.as_str(),
+            group(&self.value, Precedence::Prefix)
+        )
+    }
+    fn ty(&self) -> &str {
+        "neg"
+    }
+    fn precedence(&self) -> Precedence {
+        Precedence::Prefix
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Neg {
+            value: f(&self.value),
+        })
+    }
 }
 
 impl Opperation for Root {
     fn stringify(&self) -> String {
-       todo!()
+        match &self.degree {
+            Expression::Constant(Value::Number(n)) if *n == 2.0 => {
+                format!("sqrt({})", self.radicand.to_string())
+            }
+            degree => format!(
+                "root({}, {})",
+                degree.to_string(),
+                self.radicand.to_string()
+            ),
+        }
     }
     fn ty(&self) -> &str {
         "root"
     }
+    fn precedence(&self) -> Precedence {
+        Precedence::Atom
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Root {
+            degree: f(&self.degree),
+            radicand: f(&self.radicand),
+        })
+    }
 }
 
 impl Opperation for Index {
     fn stringify(&self) -> String {
-       format!("{}_{}", self.base.to_string(), self.index.to_string() )
+       format!("{}_{}", self.base.to_string(), group(&self.index, Precedence::Atom))
     }
     fn ty(&self) -> &str {
         "index"
     }
+    fn precedence(&self) -> Precedence {
+        Precedence::Atom
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Index {
+            base: f(&self.base),
+            index: f(&self.index),
+        })
+    }
 }
 
 impl Opperation for Exp {
     fn stringify(&self) -> String {
-        todo!()
+        // Right-associative: 2^3^4 = 2^(3^4), (2^3)^4 keeps its parentheses
+        format!(
+            "{}^{}",
+            group(&self.base, Precedence::Atom),
+            group(&self.exponent, Precedence::Power)
+        )
     }
     fn ty(&self) -> &str {
         "exp"
     }
+    fn precedence(&self) -> Precedence {
+        Precedence::Power
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Exp {
+            base: f(&self.base),
+            exponent: f(&self.exponent),
+        })
+    }
 }
 // Root
+
+impl Opperation for Series {
+    fn stringify(&self) -> String {
+        format!(
+            "{}({}, {}, {}, {})",
+            self.symbol.as_str(),
+            self.index.inner,
+            self.from.to_string(),
+            self.to.to_string(),
+            self.body.to_string()
+        )
+    }
+    fn ty(&self) -> &str {
+        self.symbol.as_str()
+    }
+    fn precedence(&self) -> Precedence {
+        Precedence::Atom
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Series {
+            symbol: self.symbol.clone(),
+            index: self.index.clone(),
+            from: f(&self.from),
+            to: f(&self.to),
+            body: f(&self.body),
+        })
+    }
+    fn binds(&self) -> Option<&Ident> {
+        Some(&self.index)
+    }
+}
+
+impl Opperation for Compare {
+    fn stringify(&self) -> String {
+        self.rest
+            .iter()
+            .fold(group(&self.first, Precedence::Sum), |acc, (symbol, e)| {
+                format!("{} {} {}", acc, symbol.as_str(), group(e, Precedence::Sum))
+            })
+    }
+    fn ty(&self) -> &str {
+        "compare"
+    }
+    fn precedence(&self) -> Precedence {
+        Precedence::Compare
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Compare {
+            first: f(&self.first),
+            rest: self
+                .rest
+                .iter()
+                .map(|(symbol, e)| (symbol.clone(), f(e)))
+                .collect(),
+        })
+    }
+}
+
+impl Opperation for Logic {
+    // An or inside an and keeps its parentheses
+    fn stringify(&self) -> String {
+        self.terms
+            .iter()
+            .map(|v| group(v, self.precedence()))
+            .collect::<Vec<_>>()
+            .join(format!(" {} ", self.symbol.as_str()).as_str())
+    }
+    fn ty(&self) -> &str {
+        self.symbol.as_str()
+    }
+    fn precedence(&self) -> Precedence {
+        match self.symbol {
+            Symbols::Or => Precedence::Or,
+            _ => Precedence::And,
+        }
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Logic {
+            symbol: self.symbol.clone(),
+            terms: self.terms.iter().map(f).collect(),
+        })
+    }
+}
+
+impl Opperation for Not {
+    fn stringify(&self) -> String {
+        format!(
+            "{} {}",
+            Symbols::Not.as_str(),
+            group(&self.value, Precedence::Not)
+        )
+    }
+    fn ty(&self) -> &str {
+        "not"
+    }
+    fn precedence(&self) -> Precedence {
+        Precedence::Not
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Not {
+            value: f(&self.value),
+        })
+    }
+}
+
+impl Opperation for Piecewise {
+    fn stringify(&self) -> String {
+        let mut cases = self
+            .cases
+            .iter()
+            .map(|(value, condition)| format!("{} if {}", value.to_string(), condition.to_string()))
+            .collect::<Vec<_>>();
+        if let Some(otherwise) = &self.otherwise {
+            cases.push(format!("{} otherwise", otherwise.to_string()));
+        }
+        format!("{{ {} }}", cases.join("; "))
+    }
+    fn ty(&self) -> &str {
+        "piecewise"
+    }
+    fn precedence(&self) -> Precedence {
+        Precedence::Atom
+    }
+    fn map(&self, f: &mut dyn FnMut(&Expression) -> Expression) -> Box<dyn Opperation> {
+        Box::new(Piecewise {
+            cases: self
+                .cases
+                .iter()
+                .map(|(value, condition)| (f(value), f(condition)))
+                .collect(),
+            otherwise: self.otherwise.as_ref().map(f),
+        })
+    }
+}
@@ -1,8 +1,8 @@
 use std::{fmt::Debug, path::Path};
 
-use crate::ast::expressions::Expression;
+use crate::ast::{base::Ident, expressions::Expression};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum System {
     Print(Expression),
 
@@ -11,4 +11,19 @@ pub enum System {
 
     // Import all of its content (frames)
     Frame(String),
+
+    // Derivative of the expression with respect to the variable
+    Diff(Expression, Ident),
+
+    // Shown in its simplified form
+    Simplify(Expression),
+
+    // A CSV file as a list of rows, or one of its columns (data("temps.csv").celsius)
+    Data(String, Option<Ident>),
+
+    // A value in a JSON file, at a "path.to.field" when given
+    Json(String, Option<String>),
+
+    // Line chart of the expression (or list of them) for x from a to b: plot(f, x, a, b)
+    Plot(Expression, Ident, Expression, Expression),
 }
@@ -0,0 +1,88 @@
+use std::f64::consts::{E, PI};
+
+use crate::ast::number::Number;
+
+// Math functions every document can call without defining them,
+// a definition with the same name takes their place
+
+#[derive(Debug, Clone)]
+pub(crate) struct Builtin {
+    pub(crate) name: &'static str,
+    pub(crate) arity: usize,
+    // Operator name in LaTeX (\sin), empty when the function has its own notation (|x|, n!, conj)
+    pub(crate) latex: &'static str,
+    // Python call with `import math` / `import numpy as np`, empty when written another way
+    pub(crate) math: &'static str,
+    pub(crate) numpy: &'static str,
+}
+
+const fn builtin(
+    name: &'static str,
+    arity: usize,
+    latex: &'static str,
+    math: &'static str,
+    numpy: &'static str,
+) -> Builtin {
+    Builtin {
+        name,
+        arity,
+        latex,
+        math,
+        numpy,
+    }
+}
+
+pub(crate) static BUILTINS: [Builtin; 22] = [
+    builtin("sin", 1, "\\sin", "math.sin", "np.sin"),
+    builtin("cos", 1, "\\cos", "math.cos", "np.cos"),
+    builtin("tan", 1, "\\tan", "math.tan", "np.tan"),
+    builtin("asin", 1, "\\arcsin", "math.asin", "np.arcsin"),
+    builtin("acos", 1, "\\arccos", "math.acos", "np.arccos"),
+    builtin("atan", 1, "\\arctan", "math.atan", "np.arctan"),
+    // log is the natural logarithm, like math.log
+    builtin("ln", 1, "\\ln", "math.log", "np.log"),
+    builtin("log", 1, "\\log", "math.log", "np.log"),
+    builtin("exp", 1, "\\exp", "math.exp", "np.exp"),
+    builtin("abs", 1, "", "abs", "np.abs"),
+    builtin("floor", 1, "", "math.floor", "np.floor"),
+    builtin("ceil", 1, "", "math.ceil", "np.ceil"),
+    builtin("min", 2, "\\min", "min", "np.minimum"),
+    builtin("max", 2, "\\max", "max", "np.maximum"),
+    builtin("gcd", 2, "\\gcd", "math.gcd", "np.gcd"),
+    builtin("factorial", 1, "", "math.factorial", ""),
+    // Complex numbers, plain Python has them as methods and attributes (z.real)
+    builtin("arg", 1, "\\arg", "", "np.angle"),
+    builtin("conj", 1, "", "", "np.conj"),
+    builtin("re", 1, "\\Re", "", "np.real"),
+    builtin("im", 1, "\\Im", "", "np.imag"),
+    // Over a whole list, sum(k, 1, n, k) with four arguments is a series
+    builtin("sum", 1, "\\sum", "sum", "np.sum"),
+    builtin("mean", 1, "", "", "np.mean"),
+];
+
+// Predefined constants, also shadowed by a definition
+pub(crate) const CONSTANTS: [(&str, f64); 2] = [("pi", PI), ("e", E)];
+
+pub(crate) fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|b| b.name == name)
+}
+
+pub(crate) fn constant(name: &str) -> Option<Number> {
+    CONSTANTS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, v)| Number::Float(*v))
+}
+
+pub fn is_builtin(name: &str) -> bool {
+    lookup(name).is_some()
+}
+
+// The built-in a Python call refers to (math.asin, np.arcsin, abs)
+pub(crate) fn from_python(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|b| {
+        [b.math, b.numpy]
+            .iter()
+            .any(|call| call.rsplit('.').next() == Some(name))
+    })
+}
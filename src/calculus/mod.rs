@@ -0,0 +1,632 @@
+pub mod simplify;
+
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        Definition,
+        base::{Ident, Value},
+        expressions::Expression,
+        number::Number,
+        opperations::{
+            Add, Compare, Div, Exp, Index, Logic, Multi, Neg, Not, Opperation, Piecewise, Root,
+            Series, Sub,
+        },
+        system_func::System,
+    },
+    builtins,
+    eval::{Binding, Environment, EvalError},
+    symbols::Symbols,
+};
+
+pub use simplify::simplify;
+
+// Symbolic derivative, definitions in `env` are expanded
+pub trait Differentiate {
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError>;
+}
+
+// d/dx of an expression on its own, calls to unknown functions stay as diff(f(..), x)
+pub fn diff(expression: &Expression, x: &Ident) -> Expression {
+    diff_in(expression, x, &Environment::new())
+        .unwrap_or_else(|_| derivative(expression.clone(), x))
+}
+
+// d/dx with the definitions in scope expanded (f(x) = x^2 gives diff(f(x), x) = 2x)
+pub fn diff_in(
+    expression: &Expression,
+    x: &Ident,
+    env: &Environment,
+) -> Result<Expression, EvalError> {
+    expression.diff(x, env)
+}
+
+// Unevaluated diff(expression, x)
+fn derivative(expression: Expression, x: &Ident) -> Expression {
+    Expression::System(Box::new(System::Diff(expression, x.clone())))
+}
+
+// The constructors below fold the zeros and ones the rules produce,
+// so d/dx 3x is 3 and not 0 * x + 3 * 1
+
+fn number(n: i64) -> Expression {
+    constant(Number::Integer(n))
+}
+
+fn constant(n: Number) -> Expression {
+    Expression::Constant(Value::Number(n))
+}
+
+fn as_number(expression: &Expression) -> Option<Number> {
+    match expression {
+        Expression::Constant(Value::Number(n)) => Some(n.clone()),
+        _ => None,
+    }
+}
+
+fn is_zero(expression: &Expression) -> bool {
+    as_number(expression).is_some_and(|n| n.is_zero())
+}
+
+fn call(name: &str, args: Vec<Expression>) -> Expression {
+    Expression::FunctionCall(Ident::from(name.to_string()), args)
+}
+
+fn sum(terms: Vec<Expression>) -> Expression {
+    let mut terms = terms
+        .into_iter()
+        .filter(|t| !is_zero(t))
+        .collect::<Vec<_>>();
+    match terms.len() {
+        0 => number(0),
+        1 => terms.remove(0),
+        _ => Expression::Opperations(Box::new(Add { terms })),
+    }
+}
+
+fn product(factors: Vec<Expression>) -> Expression {
+    let mut coefficient = Number::Integer(1);
+    let mut terms = Vec::new();
+    for factor in factors {
+        match as_number(&factor) {
+            Some(n) => coefficient = coefficient * n,
+            None => terms.push(factor),
+        }
+    }
+
+    if coefficient.is_zero() {
+        return number(0);
+    }
+    if coefficient != 1.0 || terms.is_empty() {
+        terms.insert(0, constant(coefficient));
+    }
+    match terms.len() {
+        1 => terms.remove(0),
+        _ => Expression::Opperations(Box::new(Multi { terms })),
+    }
+}
+
+fn difference(first: Expression, second: Expression) -> Expression {
+    match (as_number(&first), as_number(&second)) {
+        (_, Some(b)) if b.is_zero() => first,
+        (Some(a), _) if a.is_zero() => negate(second),
+        (Some(a), Some(b)) => constant(a - b),
+        _ => Expression::Opperations(Box::new(Sub { first, second })),
+    }
+}
+
+fn quotient(first: Expression, second: Expression) -> Expression {
+    match (as_number(&first), as_number(&second)) {
+        (Some(a), _) if a.is_zero() => number(0),
+        (_, Some(b)) if b == 1.0 => first,
+        _ => Expression::Opperations(Box::new(Div { first, second })),
+    }
+}
+
+fn negate(value: Expression) -> Expression {
+    match as_number(&value) {
+        Some(n) => constant(-n),
+        None => Expression::Opperations(Box::new(Neg { value })),
+    }
+}
+
+fn power(base: Expression, exponent: Expression) -> Expression {
+    match as_number(&exponent) {
+        Some(n) if n.is_zero() => number(1),
+        Some(n) if n == 1.0 => base,
+        _ => Expression::Opperations(Box::new(Exp { base, exponent })),
+    }
+}
+
+// Chain rule for the built-in functions, `u` is the argument and `du` its derivative
+fn builtin(name: &str, u: &Expression, du: Expression) -> Option<Expression> {
+    let outer = match name {
+        // Linear, the derivative of each item
+        "sum" | "mean" => return Some(call(name, vec![du])),
+        "sin" => call("cos", vec![u.clone()]),
+        "cos" => negate(call("sin", vec![u.clone()])),
+        "tan" => quotient(
+            number(1),
+            power(call("cos", vec![u.clone()]), number(2)),
+        ),
+        // 1 / sqrt(1 - u^2)
+        "asin" | "acos" => {
+            let outer = quotient(
+                number(1),
+                Expression::Opperations(Box::new(Root {
+                    degree: number(2),
+                    radicand: difference(number(1), power(u.clone(), number(2))),
+                })),
+            );
+            if name == "acos" {
+                negate(outer)
+            } else {
+                outer
+            }
+        }
+        "atan" => quotient(
+            number(1),
+            sum(vec![number(1), power(u.clone(), number(2))]),
+        ),
+        "exp" => call("exp", vec![u.clone()]),
+        "ln" | "log" => quotient(number(1), u.clone()),
+        // The sign of u, everywhere but 0
+        "abs" => quotient(u.clone(), call("abs", vec![u.clone()])),
+        // Flat between the jumps
+        "floor" | "ceil" => number(0),
+        _ => return None,
+    };
+    Some(product(vec![outer, du]))
+}
+
+impl Differentiate for Expression {
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        match self {
+            Expression::Opperations(opperation) => opperation.diff(x, env),
+            Expression::List(items) => items
+                .iter()
+                .map(|item| item.diff(x, env))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Expression::List),
+            Expression::FunctionCall(ident, args) => diff_call(self, ident, args, x, env),
+            Expression::System(system) => system.diff(x, env),
+            Expression::VariableRef(ident) if ident == x => Ok(number(1)),
+            // Constants defined in terms of x are expanded, anything else doesn't depend on x
+            Expression::VariableRef(ident) => match env.lookup(&ident.inner) {
+                Some(Binding::Definition(owner, Definition::Constant(_, expression))) => {
+                    expression.diff(x, &env.scope(owner, &ident.inner)?)
+                }
+                _ => Ok(number(0)),
+            },
+            Expression::Constant(_) | Expression::Empty => Ok(number(0)),
+        }
+    }
+}
+
+fn diff_call(
+    expression: &Expression,
+    ident: &Ident,
+    args: &[Expression],
+    x: &Ident,
+    env: &Environment,
+) -> Result<Expression, EvalError> {
+    match env.lookup(&ident.inner) {
+        // Known function: differentiate its body with the arguments put in
+        Some(Binding::Definition(owner, Definition::Function(_, params, body))) => {
+            if params.len() != args.len() {
+                return Err(EvalError::Arity {
+                    name: ident.inner.clone(),
+                    expected: params.len(),
+                    found: args.len(),
+                });
+            }
+            let bindings = params
+                .iter()
+                .map(|p| p.inner.clone())
+                .zip(args.iter().cloned())
+                .collect::<HashMap<_, _>>();
+            body.substitute(&bindings)
+                .diff(x, &env.scope(owner, &ident.inner)?)
+        }
+        // x(y+1) when x isn't a function is implicit multiplication
+        Some(_) if args.len() == 1 => product_rule(
+            &[Expression::VariableRef(ident.clone()), args[0].clone()],
+            x,
+            env,
+        ),
+        // Same for x(y+1) and pi(y+1)
+        _ if args.len() == 1 && (ident == x || builtins::constant(&ident.inner).is_some()) => {
+            product_rule(
+                &[Expression::VariableRef(ident.clone()), args[0].clone()],
+                x,
+                env,
+            )
+        }
+        // min, max, gcd and factorial have no rule, but are constant when their arguments are
+        _ if builtins::is_builtin(&ident.inner) => {
+            let derivatives = args
+                .iter()
+                .map(|u| u.diff(x, env))
+                .collect::<Result<Vec<_>, _>>()?;
+            if derivatives.iter().all(|du| is_zero(&simplify(du))) {
+                return Ok(number(0));
+            }
+            let rule = match (args, derivatives.as_slice()) {
+                ([u], [du]) => builtin(&ident.inner, u, du.clone()),
+                _ => None,
+            };
+            rule.ok_or_else(|| {
+                EvalError::Unsupported(derivative(expression.clone(), x).to_string())
+            })
+        }
+        _ => Ok(derivative(expression.clone(), x)),
+    }
+}
+
+fn product_rule(
+    factors: &[Expression],
+    x: &Ident,
+    env: &Environment,
+) -> Result<Expression, EvalError> {
+    let mut terms = Vec::new();
+    for (i, factor) in factors.iter().enumerate() {
+        let mut term = factors.to_vec();
+        term[i] = factor.diff(x, env)?;
+        terms.push(product(term));
+    }
+    Ok(sum(terms))
+}
+
+impl Differentiate for System {
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        match self {
+            System::Print(expression) | System::Simplify(expression) => expression.diff(x, env),
+            System::Diff(expression, inner) => diff_in(expression, inner, env)?.diff(x, env),
+            System::Import(_) | System::Frame(_) | System::Data(..) | System::Json(..) => {
+                Ok(number(0))
+            }
+            System::Plot(..) => Err(EvalError::Unsupported(self.to_string())),
+        }
+    }
+}
+
+impl Differentiate for Add {
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        self.terms
+            .iter()
+            .map(|t| t.diff(x, env))
+            .collect::<Result<Vec<_>, _>>()
+            .map(sum)
+    }
+}
+
+impl Differentiate for Sub {
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        Ok(difference(
+            self.first.diff(x, env)?,
+            self.second.diff(x, env)?,
+        ))
+    }
+}
+
+impl Differentiate for Multi {
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        product_rule(&self.terms, x, env)
+    }
+}
+
+impl Differentiate for Div {
+    // (f/g)' = (f'g - fg') / g^2
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        let (f, g) = (&self.first, &self.second);
+        let dg = g.diff(x, env)?;
+        if is_zero(&dg) {
+            return Ok(quotient(f.diff(x, env)?, g.clone()));
+        }
+        Ok(quotient(
+            difference(
+                product(vec![f.diff(x, env)?, g.clone()]),
+                product(vec![f.clone(), dg]),
+            ),
+            power(g.clone(), number(2)),
+        ))
+    }
+}
+
+impl Differentiate for Neg {
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        Ok(negate(self.value.diff(x, env)?))
+    }
+}
+
+impl Differentiate for Exp {
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        let (base, exponent) = (&self.base, &self.exponent);
+        let d_base = base.diff(x, env)?;
+        let d_exponent = exponent.diff(x, env)?;
+
+        if is_zero(&d_exponent) {
+            // Power rule: (u^n)' = n u^(n-1) u'
+            let lowered = match as_number(exponent) {
+                Some(n) => constant(n - Number::Integer(1)),
+                None => difference(exponent.clone(), number(1)),
+            };
+            return Ok(product(vec![
+                exponent.clone(),
+                power(base.clone(), lowered),
+                d_base,
+            ]));
+        }
+
+        let ln_base = call("ln", vec![base.clone()]);
+        if is_zero(&d_base) {
+            // (a^v)' = a^v ln(a) v'
+            return Ok(product(vec![
+                power(base.clone(), exponent.clone()),
+                ln_base,
+                d_exponent,
+            ]));
+        }
+
+        // (u^v)' = u^v (v' ln(u) + v u' / u)
+        Ok(product(vec![
+            power(base.clone(), exponent.clone()),
+            sum(vec![
+                product(vec![d_exponent, ln_base]),
+                quotient(product(vec![exponent.clone(), d_base]), base.clone()),
+            ]),
+        ]))
+    }
+}
+
+impl Differentiate for Root {
+    // (root(n, u))' = u' / (n root(n, u)^(n-1))
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        if !is_zero(&self.degree.diff(x, env)?) {
+            // The degree depends on x too, go through u^(1/n)
+            let exp = Exp {
+                base: self.radicand.clone(),
+                exponent: quotient(number(1), self.degree.clone()),
+            };
+            return exp.diff(x, env);
+        }
+
+        let lowered = match as_number(&self.degree) {
+            Some(n) => constant(n - Number::Integer(1)),
+            None => difference(self.degree.clone(), number(1)),
+        };
+        Ok(quotient(
+            self.radicand.diff(x, env)?,
+            product(vec![
+                self.degree.clone(),
+                power(
+                    Expression::Opperations(Box::new(Root {
+                        degree: self.degree.clone(),
+                        radicand: self.radicand.clone(),
+                    })),
+                    lowered,
+                ),
+            ]),
+        ))
+    }
+}
+
+impl Differentiate for Series {
+    // (sum(i, a, b, u))' = sum(i, a, b, u'), the bounds can't depend on x
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        // Nothing outside depends on the index
+        if self.index == *x {
+            return Ok(number(0));
+        }
+        if !is_zero(&self.from.diff(x, env)?) || !is_zero(&self.to.diff(x, env)?) {
+            return Err(EvalError::Unsupported(format!(
+                "diff({}, {})",
+                self.stringify(),
+                x.inner
+            )));
+        }
+
+        // The index shadows any definition with its name
+        let mut scope = env.child();
+        scope.bind(&self.index, Value::from(0));
+        let body = self.body.diff(x, &scope)?;
+        if is_zero(&body) {
+            return Ok(number(0));
+        }
+        let series = |body| {
+            Expression::Opperations(Box::new(Series {
+                symbol: self.symbol.clone(),
+                index: self.index.clone(),
+                from: self.from.clone(),
+                to: self.to.clone(),
+                body,
+            }))
+        };
+        Ok(match self.symbol {
+            // (prod u)' = prod u * sum u' / u
+            Symbols::Product => product(vec![
+                series(self.body.clone()),
+                Expression::Opperations(Box::new(Series {
+                    symbol: Symbols::Summation,
+                    index: self.index.clone(),
+                    from: self.from.clone(),
+                    to: self.to.clone(),
+                    body: quotient(body, self.body.clone()),
+                })),
+            ]),
+            _ => series(body),
+        })
+    }
+}
+
+// A condition is true or false, it has no derivative
+fn condition(opperation: &dyn Opperation, x: &Ident) -> Result<Expression, EvalError> {
+    Err(EvalError::Unsupported(format!(
+        "diff({}, {})",
+        opperation.stringify(),
+        x.inner
+    )))
+}
+
+impl Differentiate for Compare {
+    fn diff(&self, x: &Ident, _: &Environment) -> Result<Expression, EvalError> {
+        condition(self, x)
+    }
+}
+
+impl Differentiate for Logic {
+    fn diff(&self, x: &Ident, _: &Environment) -> Result<Expression, EvalError> {
+        condition(self, x)
+    }
+}
+
+impl Differentiate for Not {
+    fn diff(&self, x: &Ident, _: &Environment) -> Result<Expression, EvalError> {
+        condition(self, x)
+    }
+}
+
+impl Differentiate for Piecewise {
+    // Case by case under the same conditions (the points where the cases meet are left alone)
+    fn diff(&self, x: &Ident, env: &Environment) -> Result<Expression, EvalError> {
+        Ok(Expression::Opperations(Box::new(Piecewise {
+            cases: self
+                .cases
+                .iter()
+                .map(|(value, condition)| Ok((value.diff(x, env)?, condition.clone())))
+                .collect::<Result<Vec<_>, EvalError>>()?,
+            otherwise: self
+                .otherwise
+                .as_ref()
+                .map(|otherwise| otherwise.diff(x, env))
+                .transpose()?,
+        })))
+    }
+}
+
+impl Differentiate for Index {
+    // x_i is its own variable
+    fn diff(&self, _: &Ident, _: &Environment) -> Result<Expression, EvalError> {
+        Ok(number(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{
+        ast::{Statement, module::ParseMode},
+        parser::math::{expression::pexp, module::parse_module},
+    };
+
+    fn x() -> Ident {
+        Ident::from("x".to_string())
+    }
+
+    fn parse(input: &str) -> Expression {
+        let (rest, expression) = pexp(input).expect("should parse");
+        assert!(rest.is_empty(), "unparsed: {:?}", rest);
+        expression
+    }
+
+    fn d(input: &str) -> String {
+        diff(&parse(input), &x()).to_string()
+    }
+
+    // d/dx of `expression` evaluated at x = `at`, with `source` in scope
+    fn slope(source: &str, expression: &str, at: f64) -> Value {
+        let statements: Vec<Statement> = match source {
+            "" => Vec::new(),
+            source => {
+                parse_module(source, false, ParseMode::Module)
+                    .expect("source parses")
+                    .1
+                    .into_parts()
+                    .1
+            }
+        };
+        let env = Environment::from(statements.as_slice());
+        let derivative = diff_in(&parse(expression), &x(), &env).expect("should differentiate");
+
+        let mut point = env.child();
+        point.bind(&x(), Value::from(at));
+        point.evaluate(&derivative).expect("should evaluate")
+    }
+
+    #[test]
+    fn diff_polynomial() {
+        assert_eq!(d("x^2"), "2 * x");
+        assert_eq!(d("3x + 1"), "3");
+        assert_eq!(d("x^3 - 2x"), "3 * x^2 - 2");
+    }
+
+    #[test]
+    fn diff_piecewise() {
+        assert_eq!(
+            d("{ x^2 if x > 0; 3x otherwise }"),
+            "{ 2 * x if x > 0; 3 otherwise }"
+        );
+        assert_eq!(
+            slope("f(x) = { x^3 if x < 1; x otherwise }", "f(x)", 2.0),
+            Value::from(1)
+        );
+        // A condition has no derivative
+        assert_eq!(d("x > 1"), "diff(x > 1, x)");
+    }
+
+    #[test]
+    fn diff_product_and_quotient() {
+        assert_eq!(slope("", "x^2(x + 1)", 2.0), Value::from(16));
+        assert_eq!(slope("", "1 / x", 2.0), Value::from(-0.25));
+    }
+
+    #[test]
+    fn diff_roots() {
+        assert_eq!(d("sqrt(x)"), "1 / (2 * sqrt(x))");
+        assert_eq!(
+            slope("", "root(3, x)", 8.0),
+            Value::from(Number::Rational(1, 12))
+        );
+    }
+
+    #[test]
+    fn diff_chain_rule() {
+        assert_eq!(d("sin(x^2)"), "cos(x^2) * 2 * x");
+        assert_eq!(slope("", "(2x + 1)^3", 0.0), Value::from(6));
+    }
+
+    #[test]
+    fn diff_series() {
+        assert_eq!(d("sum(i, 1, n, i * x^2)"), "sum(i, 1, n, i * 2 * x)");
+        assert_eq!(d("sum(x, 1, 3, x^2)"), "0");
+        assert_eq!(slope("", "prod(i, 1, 3, x + i)", 0.0), Value::from(11));
+        assert!(diff_in(&parse("sum(i, 1, x, i)"), &x(), &Environment::new()).is_err());
+    }
+
+    #[test]
+    fn diff_builtins() {
+        assert_eq!(d("atan(x)"), "1 / (1 + x^2)");
+        assert_eq!(d("abs(x)"), "x / abs(x)");
+        assert_eq!(d("floor(x)"), "0");
+        assert_eq!(slope("", "mean([x, x^2])", 1.0), Value::from(1.5));
+        assert_eq!(slope("", "asin(x)", 0.0), Value::from(1));
+        assert_eq!(slope("", "acos(x)", 0.0), Value::from(-1));
+        assert_eq!(slope("", "pi(x + 1)", 0.0), Value::from(std::f64::consts::PI));
+
+        // No rule is needed when the arguments don't depend on x
+        assert_eq!(slope("", "factorial(3) * x", 0.0), Value::from(6));
+        assert_eq!(d("max(2, 3) + x"), "1");
+        assert!(diff_in(&parse("max(x, 3)"), &x(), &Environment::new()).is_err());
+    }
+
+    #[test]
+    fn diff_user_functions() {
+        assert_eq!(
+            slope("f(t) = t^3\ng(t) = f(2t) + a\na = 5", "g(x)", 1.0),
+            Value::from(24)
+        );
+        // Without a definition the derivative stays symbolic
+        assert_eq!(d("f(x)"), "diff(f(x), x)");
+    }
+}
@@ -0,0 +1,775 @@
+use std::cmp::Ordering;
+
+use crate::{
+    ast::{
+        base::{Ident, Value},
+        expressions::Expression,
+        number::Number,
+        opperations::{
+            Add, Compare, Div, Exp, Index, Logic, Multi, Neg, Not, Piecewise, Root, Series, Sub,
+        },
+        system_func::System,
+    },
+    calculus::diff,
+    eval::{Environment, Evaluate},
+    symbols::Symbols,
+};
+
+// Expressions are brought into a sum of products, like terms and powers are
+// collected, then the sum is written back with - and / where they read better.
+// Running it again on its own output gives the same expression.
+// x / x and x^0 are 1 without an x != 0 condition, as in most algebra systems:
+// the rules below only keep the domain where a merge would change the values
+// where both sides are defined (x^(1/2) * x^(1/2) is not x for x < 0).
+
+// coefficient * base^exponent * ...
+#[derive(Clone, Debug)]
+pub struct Term {
+    coefficient: Number,
+    factors: Vec<(Expression, Expression)>,
+}
+
+// Opperations as a sum of terms, operands are simplified first
+pub trait Simplify {
+    fn terms(&self) -> Vec<Term>;
+}
+
+pub fn simplify(expression: &Expression) -> Expression {
+    rebuild(terms(expression))
+}
+
+fn number(n: i64) -> Expression {
+    constant(Number::Integer(n))
+}
+
+fn constant(n: Number) -> Expression {
+    Expression::Constant(Value::Number(n))
+}
+
+fn as_number(expression: &Expression) -> Option<Number> {
+    match expression {
+        Expression::Constant(Value::Number(n)) => Some(n.clone()),
+        _ => None,
+    }
+}
+
+fn boolean(b: bool) -> Expression {
+    Expression::Constant(Value::Bool(b))
+}
+
+fn as_bool(expression: &Expression) -> Option<bool> {
+    match expression {
+        Expression::Constant(Value::Bool(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+fn is_integer(n: &Number) -> bool {
+    n.as_integer().is_some()
+}
+
+// Constant powers are folded when nothing is lost: 2^3, 2^-1, 4^0.5 but not 2^0.5
+fn fold_power(base: &Number, exponent: &Number) -> Option<Number> {
+    let result = base.pow(exponent);
+    (result.is_finite() && (is_integer(exponent) || result.is_exact())).then_some(result)
+}
+
+impl Term {
+    fn constant(coefficient: Number) -> Self {
+        Term {
+            coefficient,
+            factors: Vec::new(),
+        }
+    }
+
+    fn atom(expression: Expression) -> Self {
+        Term {
+            coefficient: Number::Integer(1),
+            factors: vec![(expression, number(1))],
+        }
+    }
+
+    fn is_constant(&self) -> bool {
+        self.factors.is_empty()
+    }
+
+    fn scale(self, by: Number) -> Self {
+        Term {
+            coefficient: self.coefficient * by,
+            factors: self.factors,
+        }
+    }
+
+    // Same base multiplies by adding exponents, constant factors move to the coefficient
+    fn multiply(self, other: Term) -> Term {
+        let mut coefficient = self.coefficient * other.coefficient;
+        let mut factors: Vec<(Expression, Expression)> = Vec::new();
+
+        for (base, exponent) in self.factors.into_iter().chain(other.factors) {
+            let key = base.to_string();
+            match factors.iter_mut().find(|(b, _)| b.to_string() == key) {
+                Some((_, existing)) if joins(existing, &exponent) => {
+                    *existing = add_exponents(existing, &exponent)
+                }
+                _ => factors.push((base, exponent)),
+            }
+        }
+
+        factors.retain(
+            |(base, exponent)| match (as_number(base), as_number(exponent)) {
+                (_, Some(e)) if e.is_zero() => false,
+                (Some(b), Some(e)) => match fold_power(&b, &e) {
+                    Some(folded) => {
+                        coefficient = coefficient.clone() * folded;
+                        false
+                    }
+                    None => true,
+                },
+                _ => true,
+            },
+        );
+        factors.sort_by_key(|(base, _)| base.to_string());
+
+        Term {
+            coefficient,
+            factors,
+        }
+    }
+
+    fn power(self, exponent: &Expression) -> Term {
+        // (x^2)^a isn't x^(2a) for x < 0, powers that aren't numbers stay
+        let Some(n) = as_exponent(exponent) else {
+            return match self.factors.as_slice() {
+                [(base, inner)]
+                    if self.coefficient == 1.0 && as_number(inner) == Some(1.into()) =>
+                {
+                    Term {
+                        coefficient: Number::Integer(1),
+                        factors: vec![(base.clone(), exponent.clone())],
+                    }
+                }
+                _ => Term::atom(power(rebuild(vec![self]), exponent.clone())),
+            };
+        };
+        let whole = is_integer(&n);
+        // (xy)^(1/2) isn't sqrt(x) * sqrt(y) for x, y < 0
+        let coefficient = match fold_power(&self.coefficient, &n) {
+            Some(c) if whole || self.coefficient > 0.0 && self.factors.len() <= 1 => c,
+            _ => return Term::atom(power(rebuild(vec![self]), exponent.clone())),
+        };
+
+        let mut factors = Vec::new();
+        for (base, e) in &self.factors {
+            match merge_powers(base, e, &n) {
+                Some(factor) => factors.push(factor),
+                // (x^(1/2))^2 is only x for x >= 0
+                None if whole => {
+                    let inner = Term {
+                        coefficient: Number::Integer(1),
+                        factors: vec![(base.clone(), e.clone())],
+                    };
+                    factors.push((rebuild(vec![inner]), constant(n.clone())))
+                }
+                None => return Term::atom(power(rebuild(vec![self]), exponent.clone())),
+            }
+        }
+        Term {
+            coefficient,
+            factors,
+        }
+        .multiply(Term::constant(Number::Integer(1)))
+    }
+
+    fn reciprocal(self) -> Option<Term> {
+        if self.coefficient.is_zero() {
+            return None;
+        }
+        Some(Term {
+            coefficient: Number::Integer(1) / self.coefficient,
+            factors: self
+                .factors
+                .into_iter()
+                .map(|(base, e)| (base, scale_exponent(&e, Number::Integer(-1))))
+                .collect(),
+        })
+    }
+
+    // The product without its coefficient, terms with the same key are like terms
+    fn key(&self) -> String {
+        rebuild_product(&Term {
+            coefficient: Number::Integer(1),
+            factors: self.factors.clone(),
+        })
+        .to_string()
+    }
+
+    fn degree(&self) -> Number {
+        self.factors
+            .iter()
+            .filter_map(|(_, e)| as_number(e))
+            .fold(Number::Integer(0), |sum, n| sum + n)
+    }
+}
+
+// Constant exponents are rebuilt as fractions, 1/2 reads back as a number
+fn as_exponent(expression: &Expression) -> Option<Number> {
+    as_number(expression).or_else(|| match terms(expression).as_slice() {
+        [term] if term.is_constant() => Some(term.coefficient.clone()),
+        _ => None,
+    })
+}
+
+// Even roots are only defined for x >= 0
+fn even_root(n: &Number) -> bool {
+    n.parts().is_some_and(|(_, d)| d % 2 == 0)
+}
+
+// x^(1/2) * x^(1/2) isn't x for x < 0, x^(1/2) / x^(1/2) is 1 like 1/x * x
+fn joins(a: &Expression, b: &Expression) -> bool {
+    match (as_exponent(a), as_exponent(b)) {
+        (Some(a), Some(b)) => {
+            let sum = a.clone() + b.clone();
+            !(even_root(&a) || even_root(&b)) || even_root(&sum) || sum.is_zero()
+        }
+        _ => true,
+    }
+}
+
+fn abs(base: &Expression) -> Expression {
+    match base {
+        Expression::FunctionCall(name, _) if name.to_string() == "abs" => base.clone(),
+        _ => Expression::FunctionCall(Ident::from("abs".to_string()), vec![base.clone()]),
+    }
+}
+
+// (x^a)^b as one factor where it has the same values and domain:
+// (x^3)^(1/3) is x, (x^2)^(1/2) is abs(x) and (x^(1/2))^2 doesn't merge
+fn merge_powers(base: &Expression, a: &Expression, b: &Number) -> Option<(Expression, Expression)> {
+    let Some(inner) = as_exponent(a) else {
+        return is_integer(b).then(|| (base.clone(), scale_exponent(a, b.clone())));
+    };
+    let product = inner.clone() * b.clone();
+    let ((p, q), (m, k)) = (inner.parts()?, product.parts()?);
+    let merged = constant(product);
+    match (q % 2 == 0, p % 2 == 0) {
+        (true, _) => (k % 2 == 0).then(|| (base.clone(), merged)),
+        (false, false) => Some((base.clone(), merged)),
+        // x^a is |x|^a, so is x^(ab) when it's an even power of an odd root
+        (false, true) if m % 2 == 0 && k % 2 != 0 => Some((base.clone(), merged)),
+        (false, true) => Some((abs(base), merged)),
+    }
+}
+
+fn add_exponents(a: &Expression, b: &Expression) -> Expression {
+    match (as_number(a), as_number(b)) {
+        (Some(a), Some(b)) => constant(a + b),
+        _ => simplify(&Expression::Opperations(Box::new(Add {
+            terms: vec![a.clone(), b.clone()],
+        }))),
+    }
+}
+
+fn scale_exponent(exponent: &Expression, by: Number) -> Expression {
+    match as_number(exponent) {
+        Some(n) => constant(n * by),
+        None => simplify(&multi(vec![constant(by), exponent.clone()])),
+    }
+}
+
+fn multi(terms: Vec<Expression>) -> Expression {
+    Expression::Opperations(Box::new(Multi { terms }))
+}
+
+fn power(base: Expression, exponent: Expression) -> Expression {
+    match as_number(&exponent) {
+        Some(n) if n == 1.0 => base,
+        _ => Expression::Opperations(Box::new(Exp { base, exponent })),
+    }
+}
+
+// A sum as a single term, a sum of several terms becomes an opaque factor
+fn single(mut terms: Vec<Term>) -> Term {
+    match terms.len() {
+        0 => Term::constant(Number::Integer(0)),
+        1 => terms.remove(0),
+        _ => Term::atom(rebuild(terms)),
+    }
+}
+
+// Constants are distributed, 2(a + 2) is 2a + 4, other sums aren't expanded
+fn multiply(a: Vec<Term>, b: Vec<Term>) -> Vec<Term> {
+    match (a.as_slice(), b.as_slice()) {
+        ([constant], _) if constant.is_constant() => {
+            let c = &constant.coefficient;
+            b.into_iter().map(|t| t.scale(c.clone())).collect()
+        }
+        (_, [constant]) if constant.is_constant() => {
+            let c = &constant.coefficient;
+            a.into_iter().map(|t| t.scale(c.clone())).collect()
+        }
+        _ => vec![single(a).multiply(single(b))],
+    }
+}
+
+fn negate(terms: Vec<Term>) -> Vec<Term> {
+    terms
+        .into_iter()
+        .map(|t| t.scale(Number::Integer(-1)))
+        .collect()
+}
+
+// Like terms added together, zeros dropped, highest degree first and the constant last
+fn collect(terms: Vec<Term>) -> Vec<Term> {
+    let mut collected: Vec<(String, Term)> = Vec::new();
+    for term in terms {
+        let key = term.key();
+        match collected.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => {
+                existing.coefficient = existing.coefficient.clone() + term.coefficient
+            }
+            None => collected.push((key, term)),
+        }
+    }
+
+    let mut terms = collected
+        .into_iter()
+        .filter(|(_, t)| !t.coefficient.is_zero())
+        .collect::<Vec<_>>();
+    terms.sort_by(|(ka, a), (kb, b)| {
+        a.is_constant()
+            .cmp(&b.is_constant())
+            .then(
+                b.degree()
+                    .partial_cmp(&a.degree())
+                    .unwrap_or(Ordering::Equal),
+            )
+            .then(ka.cmp(kb))
+    });
+    terms.into_iter().map(|(_, t)| t).collect()
+}
+
+fn terms(expression: &Expression) -> Vec<Term> {
+    let terms = match expression {
+        Expression::Opperations(opperation) => opperation.terms(),
+        Expression::Constant(Value::Number(n)) => vec![Term::constant(n.clone())],
+        Expression::Empty => Vec::new(),
+        Expression::List(items) => vec![Term::atom(Expression::List(
+            items.iter().map(simplify).collect(),
+        ))],
+        Expression::FunctionCall(ident, args) => vec![Term::atom(Expression::FunctionCall(
+            ident.clone(),
+            args.iter().map(simplify).collect(),
+        ))],
+        Expression::System(system) => system.terms(),
+        Expression::VariableRef(_) | Expression::Constant(_) => {
+            vec![Term::atom(expression.clone())]
+        }
+    };
+    collect(terms)
+}
+
+// Product with the coefficient and positive powers on top, negative powers underneath
+fn rebuild_product(term: &Term) -> Expression {
+    let mut numerator = Vec::new();
+    let mut denominator = Vec::new();
+
+    // x / 2 and 2x / 3 rather than a fraction in front
+    let c = &term.coefficient;
+    match c {
+        Number::Rational(n, d) => {
+            if *n != 1 {
+                numerator.push(number(*n));
+            }
+            denominator.push(number(*d));
+        }
+        _ if *c != 1.0 || term.factors.is_empty() => numerator.push(constant(c.clone())),
+        _ => {}
+    }
+
+    for (base, exponent) in &term.factors {
+        match as_number(exponent) {
+            Some(n) if n < 0.0 => denominator.push(power(base.clone(), constant(-n))),
+            _ => numerator.push(power(base.clone(), exponent.clone())),
+        }
+    }
+
+    let numerator = match numerator.len() {
+        0 => number(1),
+        1 => numerator.remove(0),
+        _ => multi(numerator),
+    };
+    match denominator.len() {
+        0 => numerator,
+        1 => Expression::Opperations(Box::new(Div {
+            first: numerator,
+            second: denominator.remove(0),
+        })),
+        _ => Expression::Opperations(Box::new(Div {
+            first: numerator,
+            second: multi(denominator),
+        })),
+    }
+}
+
+// A term on its own, -x rather than -1 * x and -(x / 3) rather than -1 * x / 3
+fn rebuild_term(term: &Term) -> Expression {
+    let negative =
+        term.coefficient == -1.0 || matches!(term.coefficient, Number::Rational(n, _) if n < 0);
+    if negative && !term.is_constant() {
+        Expression::Opperations(Box::new(Neg {
+            value: rebuild_product(&term.clone().scale(Number::Integer(-1))),
+        }))
+    } else {
+        rebuild_product(term)
+    }
+}
+
+// Sum with negative terms written as subtraction: x^2 - 2x + 1
+fn rebuild(terms: Vec<Term>) -> Expression {
+    let collapse = |mut run: Vec<Expression>| match run.len() {
+        1 => run.remove(0),
+        _ => Expression::Opperations(Box::new(Add { terms: run })),
+    };
+
+    let mut terms = terms.into_iter();
+    let Some(first) = terms.next() else {
+        return number(0);
+    };
+
+    let mut run = vec![rebuild_term(&first)];
+    for term in terms {
+        if term.coefficient < 0.0 {
+            run = vec![Expression::Opperations(Box::new(Sub {
+                first: collapse(run),
+                second: rebuild_product(&term.scale(Number::Integer(-1))),
+            }))];
+        } else {
+            run.push(rebuild_product(&term));
+        }
+    }
+    collapse(run)
+}
+
+impl Simplify for System {
+    fn terms(&self) -> Vec<Term> {
+        let expression = match self {
+            System::Print(expression) => {
+                Expression::System(Box::new(System::Print(simplify(expression))))
+            }
+            System::Simplify(expression) => return terms(expression),
+            // Derivatives are worked out, unless they have to stay symbolic
+            System::Diff(expression, x) => match diff(expression, x) {
+                Expression::System(system) if matches!(*system, System::Diff(..)) => {
+                    Expression::System(Box::new(System::Diff(simplify(expression), x.clone())))
+                }
+                derivative => return terms(&derivative),
+            },
+            System::Import(_)
+            | System::Frame(_)
+            | System::Data(..)
+            | System::Json(..)
+            | System::Plot(..) => Expression::System(Box::new(self.clone())),
+        };
+        vec![Term::atom(expression)]
+    }
+}
+
+impl Simplify for Add {
+    fn terms(&self) -> Vec<Term> {
+        self.terms.iter().flat_map(terms).collect()
+    }
+}
+
+impl Simplify for Sub {
+    fn terms(&self) -> Vec<Term> {
+        let mut result = terms(&self.first);
+        result.extend(negate(terms(&self.second)));
+        result
+    }
+}
+
+impl Simplify for Neg {
+    fn terms(&self) -> Vec<Term> {
+        negate(terms(&self.value))
+    }
+}
+
+impl Simplify for Multi {
+    fn terms(&self) -> Vec<Term> {
+        self.terms
+            .iter()
+            .map(terms)
+            .reduce(multiply)
+            .unwrap_or_else(|| vec![Term::constant(Number::Integer(1))])
+    }
+}
+
+impl Simplify for Div {
+    fn terms(&self) -> Vec<Term> {
+        let denominator = terms(&self.second);
+        let reciprocal = match denominator.len() {
+            1 => denominator[0].clone().reciprocal(),
+            _ => Some(Term::atom(rebuild(denominator.clone())).power(&number(-1))),
+        };
+        match reciprocal {
+            Some(reciprocal) => multiply(terms(&self.first), vec![reciprocal]),
+            // x / 0 is left for the evaluator to report
+            None => vec![Term::atom(Expression::Opperations(Box::new(Div {
+                first: simplify(&self.first),
+                second: simplify(&self.second),
+            })))],
+        }
+    }
+}
+
+impl Simplify for Exp {
+    fn terms(&self) -> Vec<Term> {
+        let exponent = simplify(&self.exponent);
+        match as_number(&exponent) {
+            Some(n) if n.is_zero() => return vec![Term::constant(Number::Integer(1))],
+            Some(n) if n == 1.0 => return terms(&self.base),
+            _ => (),
+        }
+        vec![single(terms(&self.base)).power(&exponent)]
+    }
+}
+
+impl Simplify for Root {
+    fn terms(&self) -> Vec<Term> {
+        let degree = simplify(&self.degree);
+        let radicand = simplify(&self.radicand);
+        // Folded when the root comes out exact, sqrt(16) and root(3, 8/27)
+        if let (Some(n), Some(r)) = (as_number(&degree), as_number(&radicand))
+            && let Some(result) = n.as_integer().and_then(|k| r.root(k))
+        {
+            return vec![Term::constant(result)];
+        }
+        vec![Term::atom(Expression::Opperations(Box::new(Root {
+            degree,
+            radicand,
+        })))]
+    }
+}
+
+impl Simplify for Index {
+    fn terms(&self) -> Vec<Term> {
+        vec![Term::atom(Expression::Opperations(Box::new(Index {
+            base: simplify(&self.base),
+            index: simplify(&self.index),
+        })))]
+    }
+}
+
+impl Simplify for Series {
+    fn terms(&self) -> Vec<Term> {
+        let from = simplify(&self.from);
+        let to = simplify(&self.to);
+        // Nothing to go over: the empty sum is 0, the empty product 1
+        if let (Some(a), Some(b)) = (as_number(&from), as_number(&to))
+            && b < a
+        {
+            return match self.symbol {
+                Symbols::Product => vec![Term::constant(Number::Integer(1))],
+                _ => Vec::new(),
+            };
+        }
+        vec![Term::atom(Expression::Opperations(Box::new(Series {
+            symbol: self.symbol.clone(),
+            index: self.index.clone(),
+            from,
+            to,
+            body: simplify(&self.body),
+        })))]
+    }
+}
+
+impl Simplify for Compare {
+    fn terms(&self) -> Vec<Term> {
+        let compare = Compare {
+            first: simplify(&self.first),
+            rest: self
+                .rest
+                .iter()
+                .map(|(symbol, e)| (symbol.clone(), simplify(e)))
+                .collect(),
+        };
+        // Comparisons of numbers are decided: 1 < 2 is true
+        let constant = as_number(&compare.first).is_some()
+            && compare.rest.iter().all(|(_, e)| as_number(e).is_some());
+        if constant && let Ok(Value::Bool(b)) = compare.evaluate(&Environment::new()) {
+            return vec![Term::atom(boolean(b))];
+        }
+        vec![Term::atom(Expression::Opperations(Box::new(compare)))]
+    }
+}
+
+impl Simplify for Logic {
+    // true and a is a, a single false decides an and (true an or)
+    fn terms(&self) -> Vec<Term> {
+        let or = self.symbol == Symbols::Or;
+        let mut kept = Vec::new();
+        for term in self.terms.iter().map(simplify) {
+            match as_bool(&term) {
+                Some(b) if b == or => return vec![Term::atom(boolean(or))],
+                Some(_) => {}
+                None => kept.push(term),
+            }
+        }
+        let expression = match kept.len() {
+            0 => boolean(!or),
+            1 => kept.remove(0),
+            _ => Expression::Opperations(Box::new(Logic {
+                symbol: self.symbol.clone(),
+                terms: kept,
+            })),
+        };
+        vec![Term::atom(expression)]
+    }
+}
+
+impl Simplify for Not {
+    fn terms(&self) -> Vec<Term> {
+        let value = simplify(&self.value);
+        let expression = match as_bool(&value) {
+            Some(b) => boolean(!b),
+            None => Expression::Opperations(Box::new(Not { value })),
+        };
+        vec![Term::atom(expression)]
+    }
+}
+
+impl Simplify for Piecewise {
+    // Cases that never hold are dropped, one that always holds is where the list ends
+    fn terms(&self) -> Vec<Term> {
+        let mut cases = Vec::new();
+        let mut otherwise = self.otherwise.as_ref();
+        for (value, condition) in &self.cases {
+            let condition = simplify(condition);
+            match as_bool(&condition) {
+                Some(true) => {
+                    otherwise = Some(value);
+                    break;
+                }
+                Some(false) => {}
+                None => cases.push((simplify(value), condition)),
+            }
+        }
+        match otherwise {
+            Some(otherwise) if cases.is_empty() => terms(otherwise),
+            otherwise => vec![Term::atom(Expression::Opperations(Box::new(Piecewise {
+                cases,
+                otherwise: otherwise.map(simplify),
+            })))],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::parser::math::expression::pexp;
+
+    fn s(input: &str) -> String {
+        let (rest, expression) = pexp(input).expect("should parse");
+        assert!(rest.is_empty(), "unparsed: {:?}", rest);
+        simplify(&expression).to_string()
+    }
+
+    #[test]
+    fn simplify_folds_and_collects() {
+        assert_eq!(s("2(a+2)+2"), "2 * a + 6");
+        assert_eq!(s("x + x - 3 + 3"), "2 * x");
+        assert_eq!(s("2^3 + sqrt(16)"), "12");
+        assert_eq!(s("x/2 + x/2"), "x");
+        assert_eq!(s("x/3 + x/3"), "2 * x / 3");
+        assert_eq!(s("0.1 + 0.2 - x/3"), "-(x / 3) + 3 / 10");
+    }
+
+    #[test]
+    fn simplify_series() {
+        assert_eq!(s("sum(i, 1, 2 + 1, i + i)"), "sum(i, 1, 3, 2 * i)");
+        assert_eq!(s("x + sum(i, 3, 1, i)"), "x");
+        assert_eq!(s("2prod(i, 1, 0, i)"), "2");
+    }
+
+    #[test]
+    fn simplify_conditions() {
+        assert_eq!(s("x + 0 > 2 - 1"), "x > 1");
+        assert_eq!(s("1 < 2 and (x = 1 or 3 <= 2)"), "x = 1");
+        assert_eq!(s("not 2 > 1 or false"), "false");
+        // Cases that can't hold go, one that always does ends the list
+        assert_eq!(
+            s("{ x if 2 < 1; x + x if x > 0; 0 otherwise }"),
+            "{ 2 * x if x > 0; 0 otherwise }"
+        );
+        assert_eq!(s("{ x if 0 < 1; 0 otherwise } + 1"), "x + 1");
+    }
+
+    #[test]
+    fn simplify_powers() {
+        assert_eq!(s("x * x^2"), "x^3");
+        assert_eq!(s("(x*y)^2"), "x^2 * y^2");
+        assert_eq!(s("x^a * x"), "x^(a + 1)");
+        assert_eq!(s("1/x * x"), "1");
+        assert_eq!(s("(x+1)^2 * (x+1)"), "(x + 1)^3");
+    }
+
+    #[test]
+    fn simplify_cancels_without_conditions() {
+        // Deliberately 1 even though x = 0 has no value
+        assert_eq!(s("x / x"), "1");
+        assert_eq!(s("x^0"), "1");
+        assert_eq!(s("(x - 1)^2 / (x - 1)^2"), "1");
+        assert_eq!(s("x^(1/2) / x^(1/2)"), "1");
+    }
+
+    #[test]
+    fn simplify_powers_of_powers() {
+        assert_eq!(s("(x^2)^3"), "x^6");
+        assert_eq!(s("(x^a)^2"), "x^(2 * a)");
+        assert_eq!(s("(x^3)^(1/3)"), "x");
+        // Even roots of even powers are positive
+        assert_eq!(s("(x^2)^(1/2)"), "abs(x)");
+        assert_eq!(s("(x^2)^0.5"), "abs(x)");
+        assert_eq!(s("(x^6)^(1/2)"), "abs(x)^3");
+        assert_eq!(s("(x^4)^(1/2)"), "x^2");
+        assert_eq!(s("(4x^2)^(1/2)"), "2 * abs(x)");
+        // x >= 0 stays required
+        assert_eq!(s("(x^(1/2))^2"), "(x^(1/2))^2");
+        assert_eq!(s("x^(1/2) * x^(1/2)"), "x^(1/2) * x^(1/2)");
+        assert_eq!(s("(x^(1/2))^(1/2)"), "x^(1/4)");
+        assert_eq!(s("(x^2)^a"), "(x^2)^a");
+        assert_eq!(s("(x*y)^(1/2)"), "(x * y)^(1 / 2)");
+    }
+
+    #[test]
+    fn simplify_identities_and_order() {
+        assert_eq!(s("x * 1 + 0"), "x");
+        assert_eq!(s("y + x"), "x + y");
+        assert_eq!(s("1 + x + x^2 * 3"), "3 * x^2 + x + 1");
+        assert_eq!(s("-(x - y)"), "-x + y");
+        assert_eq!(s("x / 0"), "x / 0");
+    }
+
+    #[test]
+    fn simplify_derivative() {
+        assert_eq!(s("diff(x^3 + 3x, x)"), "3 * x^2 + 3");
+    }
+
+    #[test]
+    fn simplify_is_idempotent() {
+        for input in [
+            "2(a+2)+2",
+            "x^2 - 2x + 1 + x",
+            "a/(b+c) - 1/(2x)",
+            "-2x + y/3",
+            "2^x * 2^x",
+            "f(2x + x) * sqrt(2)",
+            "(x + 1)^2 - x^a * x",
+        ] {
+            let once = s(input);
+            assert_eq!(s(&once), once, "simplifying {:?} twice", input);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::fs;
+
+use serde_json::Value as Json;
+
+use crate::{
+    ast::{base::Value, number::Number},
+    data::{DataError, text},
+};
+
+pub fn read_json(path: &str) -> Result<Json, DataError> {
+    let source = fs::read_to_string(path).map_err(|_| DataError::NotFound(path.to_string()))?;
+    parse_json(path, &source)
+}
+
+pub fn parse_json(file: &str, source: &str) -> Result<Json, DataError> {
+    serde_json::from_str(source).map_err(|error| DataError::Json {
+        file: file.to_string(),
+        line: error.line(),
+        column: error.column(),
+        // serde_json appends the position, it's already in the error
+        message: error
+            .to_string()
+            .split(" at line ")
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+// The field at "path.to.field", numbers index into arrays (runs.0 is the first run)
+pub fn field<'a>(file: &str, json: &'a Json, path: Option<&str>) -> Result<&'a Json, DataError> {
+    let Some(path) = path else {
+        return Ok(json);
+    };
+    let mut json = json;
+    for key in path.split('.') {
+        let next = match json {
+            Json::Object(object) => object.get(key),
+            Json::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        };
+        json = next.ok_or_else(|| DataError::MissingField {
+            file: file.to_string(),
+            path: path.to_string(),
+        })?;
+    }
+    Ok(json)
+}
+
+// Numbers, booleans (1 or 0), strings and (nested) arrays of them
+pub fn value(file: &str, path: Option<&str>, json: &Json) -> Result<Value, DataError> {
+    let not_a_value = |found: &str| DataError::NotAValue {
+        file: file.to_string(),
+        path: path.unwrap_or("(root)").to_string(),
+        found: found.to_string(),
+    };
+    match json {
+        Json::Number(n) => Number::parse(&n.to_string())
+            .or_else(|| n.as_f64().map(Number::Float))
+            .map(Value::Number)
+            .ok_or_else(|| not_a_value("a number out of range")),
+        Json::Bool(b) => Ok(Value::from(*b as i64)),
+        Json::String(string) => Ok(text(string)),
+        Json::Array(items) => Ok(Value::List(
+            items
+                .iter()
+                .map(|item| value(file, path, item))
+                .collect::<Result<_, _>>()?,
+        )),
+        Json::Null => Err(not_a_value("null")),
+        Json::Object(_) => Err(not_a_value("an object")),
+    }
+}
+
+// The value of json("file.json", "path.to.field")
+pub fn load_json(path: &str, at: Option<&str>) -> Result<Value, DataError> {
+    let json = read_json(path)?;
+    value(path, at, field(path, &json, at)?)
+}
+
+// The members of an object as named values, a member that isn't one (null, an object)
+// fails the same way json("file.json", "path.to.member") would
+pub fn constants(path: &str, at: Option<&str>) -> Result<Option<Vec<(String, Value)>>, DataError> {
+    let json = read_json(path)?;
+    members(path, &json, at)
+}
+
+fn members(
+    file: &str,
+    json: &Json,
+    at: Option<&str>,
+) -> Result<Option<Vec<(String, Value)>>, DataError> {
+    let Json::Object(object) = field(file, json, at)? else {
+        return Ok(None);
+    };
+    object
+        .iter()
+        .map(|(key, json)| {
+            let member = match at {
+                Some(at) => format!("{}.{}", at, key),
+                None => key.clone(),
+            };
+            Ok((key.clone(), value(file, Some(&member), json)?))
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    const RESULTS: &str =
+        r#"{"fit": {"k": 0.5, "runs": [[1, 2], [3, 4]]}, "name": "ab", "notes": null}"#;
+
+    fn load(path: Option<&str>) -> Result<Value, DataError> {
+        let json = parse_json("results.json", RESULTS)?;
+        value("results.json", path, field("results.json", &json, path)?)
+    }
+
+    #[test]
+    fn test_json_fields() {
+        assert_eq!(load(Some("fit.k")), Ok(Value::from(0.5)));
+        assert_eq!(
+            load(Some("fit.runs.1")),
+            Ok(Value::List(vec![Value::from(3), Value::from(4)]))
+        );
+        assert_eq!(
+            load(Some("name")),
+            Ok(Value::List(vec![Value::from('a'), Value::from('b')]))
+        );
+    }
+
+    #[test]
+    fn test_json_members() {
+        let json = parse_json("results.json", RESULTS).unwrap();
+        let fit = members("results.json", &json, Some("fit"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            fit.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(),
+            vec!["k", "runs"]
+        );
+        assert_eq!(members("results.json", &json, Some("name")), Ok(None));
+
+        // Members that can't be bound fail instead of going missing
+        assert_eq!(
+            members("results.json", &json, None)
+                .unwrap_err()
+                .to_string(),
+            "results.json: `fit` is an object, expected a number, string or array"
+        );
+        let json = parse_json("results.json", r#"{"fit": {"k": 1, "runs": [{"n": 1}]}}"#).unwrap();
+        assert_eq!(
+            members("results.json", &json, Some("fit"))
+                .unwrap_err()
+                .to_string(),
+            "results.json: `fit.runs` is an object, expected a number, string or array"
+        );
+    }
+
+    #[test]
+    fn test_json_errors() {
+        assert_eq!(
+            load(Some("fit.c")).unwrap_err().to_string(),
+            "results.json has no field `fit.c`"
+        );
+        assert_eq!(
+            load(Some("fit")).unwrap_err().to_string(),
+            "results.json: `fit` is an object, expected a number, string or array"
+        );
+        assert_eq!(
+            parse_json("results.json", "{\n  \"k\": 1,\n}")
+                .unwrap_err()
+                .to_string(),
+            "results.json:3:1: trailing comma"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+pub mod json;
+
+use std::fs;
+
+use thiserror::Error;
+
+use crate::ast::{base::Value, number::Number};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DataError {
+    #[error("data file not found: {0}")]
+    NotFound(String),
+    #[error("{file} is empty")]
+    Empty { file: String },
+    #[error("{file}:{line}: expected {expected} cells, found {found}")]
+    Ragged {
+        file: String,
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("{file}:{line}: `{cell}` in numeric column `{column}` is not a number")]
+    NotNumeric {
+        file: String,
+        line: usize,
+        column: String,
+        cell: String,
+    },
+    #[error("{file}: unterminated quote on line {line}")]
+    Unterminated { file: String, line: usize },
+    #[error("{file} has no column `{column}`")]
+    UnknownColumn { file: String, column: String },
+    #[error("{file}:{line}:{column}: {message}")]
+    Json {
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("{file} has no field `{path}`")]
+    MissingField { file: String, path: String },
+    #[error("{file}: `{path}` is {found}, expected a number, string or array")]
+    NotAValue {
+        file: String,
+        path: String,
+        found: String,
+    },
+}
+
+// A parsed CSV file, columns are named by the header (or numbered from 1 without one)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub file: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    // Every row as a list
+    pub fn value(&self) -> Value {
+        Value::List(self.rows.iter().cloned().map(Value::List).collect())
+    }
+
+    pub fn column(&self, name: &str) -> Result<Value, DataError> {
+        let Some(index) = self.columns.iter().position(|c| c == name) else {
+            return Err(DataError::UnknownColumn {
+                file: self.file.clone(),
+                column: name.to_string(),
+            });
+        };
+        Ok(Value::List(
+            self.rows.iter().map(|row| row[index].clone()).collect(),
+        ))
+    }
+}
+
+pub fn read_csv(path: &str) -> Result<Table, DataError> {
+    let source = fs::read_to_string(path).map_err(|_| DataError::NotFound(path.to_string()))?;
+    parse_csv(path, &source)
+}
+
+// Cells of one line, "quoted, cells" may hold commas and "" for a quote
+fn cells(file: &str, line: usize, text: &str) -> Result<Vec<String>, DataError> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            c => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err(DataError::Unterminated {
+            file: file.to_string(),
+            line,
+        });
+    }
+    cells.push(cell.trim().to_string());
+    Ok(cells)
+}
+
+// Text becomes a list of characters, like a string literal
+pub(crate) fn text(cell: &str) -> Value {
+    Value::List(cell.chars().map(Value::from).collect())
+}
+
+// The first line is a header when any of its cells isn't a number.
+// Exact when the cell is a plain literal, 1e400 and inf are floats
+fn number(cell: &str) -> Option<Number> {
+    Number::parse(cell).or_else(|| cell.parse::<f64>().ok().map(Number::Float))
+}
+
+// A column is numeric when at least half of its cells are numbers, its other cells are then
+// mistakes (warm, n/a) and reported. Mostly text columns (codes like A1, 7, B2) stay text.
+pub fn parse_csv(file: &str, source: &str) -> Result<Table, DataError> {
+    let mut lines = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        if !line.trim().is_empty() {
+            lines.push((i + 1, cells(file, i + 1, line)?));
+        }
+    }
+    let Some((_, first)) = lines.first() else {
+        return Err(DataError::Empty {
+            file: file.to_string(),
+        });
+    };
+
+    let width = first.len();
+    let header = first.iter().any(|cell| number(cell).is_none());
+    let columns = if header {
+        lines.remove(0).1
+    } else {
+        (1..=width).map(|i| i.to_string()).collect()
+    };
+    let numeric = (0..width)
+        .map(|i| {
+            let cells = lines.iter().filter_map(|(_, row)| row.get(i));
+            let numbers = cells.clone().filter(|cell| number(cell).is_some()).count();
+            numbers > 0 && 2 * numbers >= cells.count()
+        })
+        .collect::<Vec<_>>();
+
+    let mut rows = Vec::new();
+    for (line, row) in lines {
+        if row.len() != width {
+            return Err(DataError::Ragged {
+                file: file.to_string(),
+                line,
+                expected: width,
+                found: row.len(),
+            });
+        }
+        let mut values = Vec::new();
+        for ((cell, column), numeric) in row.iter().zip(&columns).zip(&numeric) {
+            if !numeric {
+                values.push(text(cell));
+                continue;
+            }
+            match number(cell) {
+                Some(n) => values.push(Value::Number(n)),
+                None => {
+                    return Err(DataError::NotNumeric {
+                        file: file.to_string(),
+                        line,
+                        column: column.clone(),
+                        cell: cell.clone(),
+                    });
+                }
+            }
+        }
+        rows.push(values);
+    }
+
+    Ok(Table {
+        file: file.to_string(),
+        columns,
+        rows,
+    })
+}
+
+// The value of data("file.csv") or data("file.csv").column
+pub fn load(path: &str, column: Option<&str>) -> Result<Value, DataError> {
+    let table = read_csv(path)?;
+    match column {
+        Some(column) => table.column(column),
+        None => Ok(table.value()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_header() {
+        let table = parse_csv("temps.csv", "city,celsius\nOslo,4\n\"Rome, IT\",18.5\n").unwrap();
+        assert_eq!(table.columns, vec!["city", "celsius"]);
+        assert_eq!(
+            table.column("celsius"),
+            Ok(Value::List(vec![Value::from(4), Value::from(18.5)]))
+        );
+        assert_eq!(table.rows[1][0], text("Rome, IT"));
+    }
+
+    #[test]
+    fn test_parse_csv_without_header() {
+        let table = parse_csv("grid.csv", "1,2\n3,4\n").unwrap();
+        assert_eq!(table.columns, vec!["1", "2"]);
+        assert_eq!(
+            table.value(),
+            Value::List(vec![
+                Value::List(vec![Value::from(1), Value::from(2)]),
+                Value::List(vec![Value::from(3), Value::from(4)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_mixed_columns() {
+        // The whole column decides, not its first row
+        let error = parse_csv("temps.csv", "city,celsius\nOslo,n/a\nRome,18.5\nNice,20\n");
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "temps.csv:2: `n/a` in numeric column `celsius` is not a number"
+        );
+        let table = parse_csv("codes.csv", "code,n\nA1,1\n7,2\nB2,3\n").unwrap();
+        assert_eq!(
+            table.column("code"),
+            Ok(Value::List(vec![text("A1"), text("7"), text("B2")]))
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_errors() {
+        let error = parse_csv("temps.csv", "city,celsius\nOslo,4\n\nRome,warm\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "temps.csv:4: `warm` in numeric column `celsius` is not a number"
+        );
+
+        let error = parse_csv("temps.csv", "a,b\n1,2\n3\n").unwrap_err();
+        assert_eq!(error.to_string(), "temps.csv:3: expected 2 cells, found 1");
+
+        let table = parse_csv("temps.csv", "a\n1\n").unwrap();
+        assert!(matches!(
+            table.column("b"),
+            Err(DataError::UnknownColumn { .. })
+        ));
+    }
+}
@@ -0,0 +1,1135 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    ast::{
+        Definition, Statement,
+        base::{Ident, Value},
+        complex::Complex,
+        expressions::Expression,
+        number::Number,
+        opperations::{
+            Add, Compare, Div, Exp, Index, Logic, Multi, Neg, Not, Opperation, Piecewise, Root,
+            Series, Sub,
+        },
+        system_func::System,
+    },
+    builtins::{self, Builtin},
+    calculus::diff_in,
+    data::{self, DataError, json},
+    symbols::Symbols,
+};
+
+// Deepest chain of function calls / constant lookups before giving up
+const MAX_DEPTH: usize = 128;
+// Most terms a sum / prod will go through
+const MAX_TERMS: i64 = 100_000;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EvalError {
+    #[error("unbound variable `{0}`")]
+    UnboundVariable(String),
+    #[error("unknown function `{0}`")]
+    UnknownFunction(String),
+    #[error("`{name}` expects {expected} argument(s) but was given {found}")]
+    Arity {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("{0}")]
+    Domain(String),
+    #[error("index {index} is out of bounds for a list of length {len}")]
+    IndexOutOfBounds { index: usize, len: usize },
+    #[error("type error: {0}")]
+    Type(String),
+    #[error("recursion limit reached while evaluating `{0}`")]
+    RecursionLimit(String),
+    #[error("`{0}` can't be evaluated")]
+    Unsupported(String),
+    #[error(transparent)]
+    Data(#[from] DataError),
+}
+
+// Anything that can be computed down to a Value
+pub trait Evaluate {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError>;
+}
+
+// Where a name was found while walking up the scopes
+pub(crate) enum Binding<'a> {
+    Value(&'a Value),
+    Definition(&'a Environment<'a>, &'a Definition),
+}
+
+// Constants and functions in scope, function calls get a child scope for their arguments
+#[derive(Default)]
+pub struct Environment<'a> {
+    parent: Option<&'a Environment<'a>>,
+    definitions: HashMap<String, &'a Definition>,
+    values: HashMap<String, Value>,
+    depth: usize,
+    // Complex mode, the letter for the imaginary unit
+    imaginary: Option<char>,
+}
+
+impl<'a> Environment<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn define(&mut self, definition: &'a Definition) {
+        let ident = match definition {
+            Definition::Function(ident, _, _) | Definition::Constant(ident, _) => ident,
+        };
+        self.definitions.insert(ident.inner.clone(), definition);
+    }
+
+    pub fn bind(&mut self, ident: &Ident, value: Value) {
+        self.values.insert(ident.inner.clone(), value);
+    }
+
+    // Complex mode: `unit` is the imaginary unit wherever nothing else has that name,
+    // and roots and powers of negative numbers take the principal branch
+    pub fn complex(&mut self, unit: char) {
+        self.imaginary = Some(unit);
+    }
+
+    pub fn imaginary(&self) -> Option<char> {
+        self.imaginary
+            .or_else(|| self.parent.and_then(|parent| parent.imaginary()))
+    }
+
+    // pi, e and the imaginary unit
+    fn constant(&self, name: &str) -> Option<Value> {
+        match self.imaginary() {
+            Some(unit) if name == unit.to_string() => Some(Value::Complex(Complex::i(unit))),
+            _ => builtins::constant(name).map(Value::Number),
+        }
+    }
+
+    // New (empty) scope on top of this one
+    pub fn child(&self) -> Environment<'_> {
+        Environment {
+            parent: Some(self),
+            definitions: HashMap::new(),
+            values: HashMap::new(),
+            depth: self.depth,
+            imaginary: None,
+        }
+    }
+
+    pub(crate) fn lookup(&self, name: &str) -> Option<Binding<'_>> {
+        if let Some(value) = self.values.get(name) {
+            return Some(Binding::Value(value));
+        }
+        if let Some(definition) = self.definitions.get(name) {
+            return Some(Binding::Definition(self, definition));
+        }
+        self.parent.and_then(|parent| parent.lookup(name))
+    }
+
+    // Definitions are evaluated under the scope that defined them, one level deeper than the caller
+    pub(crate) fn scope<'s>(
+        &self,
+        owner: &'s Environment<'s>,
+        name: &str,
+    ) -> Result<Environment<'s>, EvalError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(EvalError::RecursionLimit(name.to_string()));
+        }
+        Ok(Environment {
+            parent: Some(owner),
+            definitions: HashMap::new(),
+            values: HashMap::new(),
+            depth: self.depth + 1,
+            imaginary: None,
+        })
+    }
+
+    pub fn evaluate(&self, expression: &Expression) -> Result<Value, EvalError> {
+        expression.evaluate(self)
+    }
+
+    pub fn variable(&self, ident: &Ident) -> Result<Value, EvalError> {
+        match self.lookup(&ident.inner) {
+            Some(Binding::Value(value)) => Ok(value.clone()),
+            Some(Binding::Definition(owner, Definition::Constant(_, expression))) => {
+                self.scope(owner, &ident.inner)?.evaluate(expression)
+            }
+            Some(Binding::Definition(_, Definition::Function(_, params, _))) => {
+                Err(EvalError::Arity {
+                    name: ident.inner.clone(),
+                    expected: params.len(),
+                    found: 0,
+                })
+            }
+            None => self
+                .constant(&ident.inner)
+                .ok_or_else(|| EvalError::UnboundVariable(ident.inner.clone())),
+        }
+    }
+
+    pub fn call(&self, ident: &Ident, args: &[Expression]) -> Result<Value, EvalError> {
+        match self.lookup(&ident.inner) {
+            Some(Binding::Definition(owner, Definition::Function(_, params, body))) => {
+                if params.len() != args.len() {
+                    return Err(EvalError::Arity {
+                        name: ident.inner.clone(),
+                        expected: params.len(),
+                        found: args.len(),
+                    });
+                }
+                let mut scope = self.scope(owner, &ident.inner)?;
+                for (param, arg) in params.iter().zip(args) {
+                    scope.bind(param, self.evaluate(arg)?);
+                }
+                scope.evaluate(body)
+            }
+            // x(y+1) when x isn't a function is implicit multiplication
+            Some(_) if args.len() == 1 => multiply(self.variable(ident)?, self.evaluate(&args[0])?),
+            Some(_) => Err(EvalError::Type(format!(
+                "`{}` is not a function",
+                ident.inner
+            ))),
+            None => match builtins::lookup(&ident.inner) {
+                Some(builtin) => {
+                    if builtin.arity != args.len() {
+                        return Err(EvalError::Arity {
+                            name: ident.inner.clone(),
+                            expected: builtin.arity,
+                            found: args.len(),
+                        });
+                    }
+                    let args = args
+                        .iter()
+                        .map(|arg| self.evaluate(arg))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    apply(builtin, args, self.imaginary())
+                }
+                // pi(2) is implicit multiplication too
+                None => match self.constant(&ident.inner) {
+                    Some(value) if args.len() == 1 => multiply(value, self.evaluate(&args[0])?),
+                    _ => Err(EvalError::UnknownFunction(ident.inner.clone())),
+                },
+            },
+        }
+    }
+}
+
+impl<'a> From<&'a [Statement]> for Environment<'a> {
+    fn from(statements: &'a [Statement]) -> Self {
+        let mut env = Environment::new();
+        for statement in statements {
+            if let Statement::Definition(definition) = statement {
+                env.define(definition);
+            }
+        }
+        env
+    }
+}
+
+fn number(value: &Value) -> Result<Number, EvalError> {
+    match value {
+        Value::Number(n) => Ok(n.clone()),
+        other => Err(EvalError::Type(format!(
+            "expected a number, found {:?}",
+            other
+        ))),
+    }
+}
+
+fn truth(value: &Value) -> Result<bool, EvalError> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        other => Err(EvalError::Type(format!(
+            "expected true or false, found {:?}",
+            other
+        ))),
+    }
+}
+
+// Two scalars as numbers, or both as complex numbers when either one is
+enum Pair {
+    Real(Number, Number),
+    Complex(Complex, Complex),
+}
+
+fn pair(a: Value, b: Value) -> Result<Pair, EvalError> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => Ok(Pair::Real(a, b)),
+        (Value::Complex(a), Value::Complex(b)) => Ok(Pair::Complex(a, b)),
+        (Value::Complex(a), Value::Number(b)) => {
+            let unit = a.unit;
+            Ok(Pair::Complex(a, Complex::real(b, unit)))
+        }
+        (Value::Number(a), Value::Complex(b)) => Ok(Pair::Complex(Complex::real(a, b.unit), b)),
+        (a, b) => Err(EvalError::Type(format!(
+            "can't do arithmetic on {:?} and {:?}",
+            a, b
+        ))),
+    }
+}
+
+// Operation on two values, element-wise when either side is a list
+fn broadcast(
+    a: Value,
+    b: Value,
+    op: &impl Fn(Value, Value) -> Result<Value, EvalError>,
+) -> Result<Value, EvalError> {
+    match (a, b) {
+        (Value::List(a), Value::List(b)) => {
+            if a.len() != b.len() {
+                return Err(EvalError::Type(format!(
+                    "lists have different lengths ({} and {})",
+                    a.len(),
+                    b.len()
+                )));
+            }
+            a.into_iter()
+                .zip(b)
+                .map(|(a, b)| broadcast(a, b, op))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List)
+        }
+        (Value::List(a), b) => a
+            .into_iter()
+            .map(|a| broadcast(a, b.clone(), op))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::List),
+        (a, Value::List(b)) => b
+            .into_iter()
+            .map(|b| broadcast(a.clone(), b, op))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::List),
+        (a, b) => op(a, b),
+    }
+}
+
+pub(crate) fn add(a: Value, b: Value) -> Result<Value, EvalError> {
+    broadcast(a, b, &|a, b| match pair(a, b)? {
+        Pair::Real(a, b) => Ok(Value::Number(a + b)),
+        Pair::Complex(a, b) => Ok(Value::from(a + b)),
+    })
+}
+
+pub(crate) fn subtract(a: Value, b: Value) -> Result<Value, EvalError> {
+    broadcast(a, b, &|a, b| match pair(a, b)? {
+        Pair::Real(a, b) => Ok(Value::Number(a - b)),
+        Pair::Complex(a, b) => Ok(Value::from(a - b)),
+    })
+}
+
+pub(crate) fn multiply(a: Value, b: Value) -> Result<Value, EvalError> {
+    broadcast(a, b, &|a, b| match pair(a, b)? {
+        Pair::Real(a, b) => Ok(Value::Number(a * b)),
+        Pair::Complex(a, b) => Ok(Value::from(a * b)),
+    })
+}
+
+pub(crate) fn divide(a: Value, b: Value) -> Result<Value, EvalError> {
+    broadcast(a, b, &|a, b| match pair(a, b)? {
+        Pair::Real(_, b) if b.is_zero() => Err(EvalError::DivisionByZero),
+        Pair::Complex(_, b) if b.is_zero() => Err(EvalError::DivisionByZero),
+        Pair::Real(a, b) => Ok(Value::Number(a / b)),
+        Pair::Complex(a, b) => Ok(Value::from(a / b)),
+    })
+}
+
+// = and != work on any two values, the orderings only on real numbers
+pub(crate) fn compare(symbol: &Symbols, a: Value, b: Value) -> Result<Value, EvalError> {
+    broadcast(a, b, &|a, b| {
+        let holds = match symbol {
+            Symbols::Equal => a == b,
+            Symbols::NotEqual => a != b,
+            _ => {
+                let (a, b) = (number(&a)?, number(&b)?);
+                match symbol {
+                    Symbols::Less => a < b,
+                    Symbols::LessEqual => a <= b,
+                    Symbols::Greater => a > b,
+                    _ => a >= b,
+                }
+            }
+        };
+        Ok(Value::Bool(holds))
+    })
+}
+
+fn both(a: Value, b: Value) -> Result<Value, EvalError> {
+    broadcast(a, b, &|a, b| Ok(Value::Bool(truth(&a)? && truth(&b)?)))
+}
+
+fn either(a: Value, b: Value) -> Result<Value, EvalError> {
+    broadcast(a, b, &|a, b| Ok(Value::Bool(truth(&a)? || truth(&b)?)))
+}
+
+// `imaginary` is set in complex mode, a negative base then takes the principal branch
+pub(crate) fn power(
+    base: Value,
+    exponent: Value,
+    imaginary: Option<char>,
+) -> Result<Value, EvalError> {
+    broadcast(
+        base,
+        exponent,
+        &|base, exponent| match pair(base, exponent)? {
+            Pair::Real(base, exponent) => {
+                if base.is_zero() && exponent < 0.0 {
+                    return Err(EvalError::DivisionByZero);
+                }
+                // (-8)^(1/3) is the principal value in complex mode, not -2
+                if let Some(unit) = imaginary
+                    && base < 0.0
+                    && exponent.as_integer().is_none()
+                {
+                    return Ok(Value::from(
+                        Complex::real(base, unit).pow(&Complex::real(exponent, unit)),
+                    ));
+                }
+                let result = base.pow(&exponent);
+                match imaginary {
+                    _ if !result.to_f64().is_nan() => Ok(Value::Number(result)),
+                    Some(unit) => Ok(Value::from(
+                        Complex::real(base, unit).pow(&Complex::real(exponent, unit)),
+                    )),
+                    None => Err(EvalError::Domain(format!(
+                        "{}^{} is not a real number",
+                        base, exponent
+                    ))),
+                }
+            }
+            Pair::Complex(base, exponent) => {
+                if base.is_zero() && exponent.re <= 0.0 {
+                    return Err(EvalError::DivisionByZero);
+                }
+                Ok(Value::from(base.pow(&exponent)))
+            }
+        },
+    )
+}
+
+// Odd roots of negative numbers are real, in complex mode every root is the principal one
+pub(crate) fn root(
+    degree: Value,
+    radicand: Value,
+    imaginary: Option<char>,
+) -> Result<Value, EvalError> {
+    broadcast(degree, radicand, &|degree, radicand| {
+        let degree = number(&degree)?;
+        if degree.is_zero() {
+            return Err(EvalError::DivisionByZero);
+        }
+        let radicand = match radicand {
+            Value::Complex(z) => return Ok(Value::from(z.root(&degree))),
+            radicand => number(&radicand)?,
+        };
+        // root(3, -8) isn't -2 in complex mode, sqrt(-4) = 2i
+        if let Some(unit) = imaginary
+            && radicand < 0.0
+        {
+            return Ok(Value::from(Complex::real(radicand, unit).root(&degree)));
+        }
+        // root(2, 16) and root(3, -8/27) come out exact
+        if let Some(root) = degree.as_integer().and_then(|k| radicand.root(k)) {
+            return Ok(Value::Number(root));
+        }
+        let (d, r) = (degree.to_f64(), radicand.to_f64());
+        if r >= 0.0 {
+            return Ok(Value::Number(Number::Float(r.powf(1.0 / d))));
+        }
+        if d.fract() == 0.0 && d % 2.0 != 0.0 {
+            return Ok(Value::Number(Number::Float(-(-r).powf(1.0 / d))));
+        }
+        Err(EvalError::Domain(format!(
+            "root({}, {}) is not a real number",
+            degree, radicand
+        )))
+    })
+}
+
+// Function of one value, element-wise over lists
+fn each(value: Value, op: &impl Fn(Value) -> Result<Value, EvalError>) -> Result<Value, EvalError> {
+    match value {
+        Value::List(items) => items
+            .into_iter()
+            .map(|item| each(item, op))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::List),
+        value => op(value),
+    }
+}
+
+fn integer(name: &str, n: &Number) -> Result<i64, EvalError> {
+    n.as_integer().ok_or_else(|| {
+        EvalError::Domain(format!(
+            "`{}` is only defined for integers, found {}",
+            name, n
+        ))
+    })
+}
+
+fn gcd(a: &Number, b: &Number) -> Result<Number, EvalError> {
+    let (mut a, mut b) = (
+        integer("gcd", a)?.unsigned_abs(),
+        integer("gcd", b)?.unsigned_abs(),
+    );
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    Ok(i64::try_from(a).map_or(Number::Float(a as f64), Number::Integer))
+}
+
+fn factorial(n: &Number) -> Result<Number, EvalError> {
+    let n = integer("factorial", n)?;
+    if n < 0 {
+        return Err(EvalError::Domain(format!(
+            "`factorial` is only defined for n >= 0, found {}",
+            n
+        )));
+    }
+    Ok((1..=n).fold(Number::Integer(1), |acc, k| acc * Number::Integer(k)))
+}
+
+// A built-in on a complex number, ln and exp take the principal branch
+fn complex(name: &str, z: Complex) -> Result<Value, EvalError> {
+    let unit = z.unit;
+    Ok(match name {
+        "abs" => Value::Number(z.abs()),
+        "arg" => Value::Number(z.arg()),
+        "conj" => Value::from(z.conj()),
+        "re" => Value::Number(z.re),
+        "im" => Value::Number(z.im),
+        "ln" | "log" => Value::from(Complex::new(
+            Number::Float(z.abs().to_f64().ln()),
+            z.arg(),
+            unit,
+        )),
+        "exp" => Value::from(Complex::real(Number::Float(std::f64::consts::E), unit).pow(&z)),
+        _ => {
+            return Err(EvalError::Domain(format!(
+                "`{}` is only defined for real numbers, found {}",
+                name, z
+            )));
+        }
+    })
+}
+
+// sum and mean of a list, a single value is a list of one
+fn aggregate(name: &str, value: Value) -> Result<Value, EvalError> {
+    let items = match value {
+        Value::List(items) => items,
+        value => vec![value],
+    };
+    let count = items.len() as i64;
+    let total = items.into_iter().try_fold(Value::from(0), add)?;
+    match name {
+        "sum" => Ok(total),
+        _ if count == 0 => Err(EvalError::Domain("mean of an empty list".to_string())),
+        _ => divide(total, Value::from(count)),
+    }
+}
+
+// A built-in on its evaluated arguments (as many as its arity), element-wise over lists
+fn apply(
+    builtin: &Builtin,
+    mut args: Vec<Value>,
+    imaginary: Option<char>,
+) -> Result<Value, EvalError> {
+    let name = builtin.name;
+    if let "sum" | "mean" = name {
+        return aggregate(name, args.remove(0));
+    }
+    let mut args = match <[Value; 2]>::try_from(args) {
+        Ok([a, b]) => {
+            return broadcast(a, b, &|a, b| {
+                let (a, b) = (number(&a)?, number(&b)?);
+                match name {
+                    "min" => Ok(Value::Number(if b < a { b } else { a })),
+                    "max" => Ok(Value::Number(if b > a { b } else { a })),
+                    _ => gcd(&a, &b).map(Value::Number),
+                }
+            });
+        }
+        Err(args) => args,
+    };
+
+    each(args.remove(0), &|x| {
+        let x = match x {
+            Value::Complex(z) => return complex(name, z),
+            x => number(&x)?,
+        };
+        let float = |f: fn(f64) -> f64| Number::Float(f(x.to_f64()));
+        let result = match name {
+            // Exact stays exact
+            "abs" => x.abs(),
+            "floor" => x.floor(),
+            "ceil" => x.ceil(),
+            "factorial" => factorial(&x)?,
+            "re" | "conj" => x.clone(),
+            "im" => Number::Integer(0),
+            "arg" if x < 0.0 => Number::Float(std::f64::consts::PI),
+            "arg" => Number::Integer(0),
+            "sin" => float(f64::sin),
+            "cos" => float(f64::cos),
+            "tan" => float(f64::tan),
+            "asin" => float(f64::asin),
+            "acos" => float(f64::acos),
+            "atan" => float(f64::atan),
+            // ln(-1) = i pi in complex mode
+            "ln" | "log" if x < 0.0 => match imaginary {
+                Some(unit) => return complex(name, Complex::real(x.clone(), unit)),
+                None => Number::Float(f64::NAN),
+            },
+            "ln" | "log" if x <= 0.0 => Number::Float(f64::NAN),
+            "ln" | "log" => float(f64::ln),
+            _ => float(f64::exp),
+        };
+        if result.to_f64().is_nan() {
+            Err(EvalError::Domain(format!(
+                "{}({}) is not a real number",
+                name, x
+            )))
+        } else {
+            Ok(Value::Number(result))
+        }
+    })
+}
+
+impl Evaluate for Expression {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        match self {
+            Expression::Opperations(opperation) => opperation.evaluate(env),
+            Expression::List(items) => items
+                .iter()
+                .map(|item| item.evaluate(env))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
+            Expression::FunctionCall(ident, args) => env.call(ident, args),
+            Expression::System(system) => system.evaluate(env),
+            Expression::VariableRef(ident) => env.variable(ident),
+            Expression::Constant(value) => Ok(value.clone()),
+            Expression::Empty => Ok(Value::from(0)),
+        }
+    }
+}
+
+impl Evaluate for System {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        match self {
+            System::Print(expression) | System::Simplify(expression) => expression.evaluate(env),
+            // An unknown function has no derivative, it would only come back as diff(f(..), x)
+            System::Diff(Expression::FunctionCall(ident, _), _)
+                if env.lookup(&ident.inner).is_none() && !builtins::is_builtin(&ident.inner) =>
+            {
+                Err(EvalError::UnknownFunction(ident.inner.clone()))
+            }
+            System::Diff(expression, x) => diff_in(expression, x, env)?.evaluate(env),
+            System::Data(path, column) => Ok(data::load(
+                path,
+                column.as_ref().map(|column| column.inner.as_str()),
+            )?),
+            System::Json(path, field) => Ok(json::load_json(path, field.as_deref())?),
+            System::Import(_) | System::Frame(_) | System::Plot(..) => {
+                Err(EvalError::Unsupported(self.to_string()))
+            }
+        }
+    }
+}
+
+// Evaluate and fold terms left to right
+fn fold(
+    terms: &[Expression],
+    env: &Environment,
+    op: fn(Value, Value) -> Result<Value, EvalError>,
+) -> Result<Value, EvalError> {
+    let mut terms = terms.iter();
+    let first = match terms.next() {
+        Some(first) => first.evaluate(env)?,
+        None => return Ok(Value::from(0)),
+    };
+    terms.try_fold(first, |acc, term| op(acc, term.evaluate(env)?))
+}
+
+impl Evaluate for Add {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        fold(&self.terms, env, add)
+    }
+}
+
+impl Evaluate for Multi {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        fold(&self.terms, env, multiply)
+    }
+}
+
+impl Evaluate for Sub {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        subtract(self.first.evaluate(env)?, self.second.evaluate(env)?)
+    }
+}
+
+impl Evaluate for Div {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        divide(self.first.evaluate(env)?, self.second.evaluate(env)?)
+    }
+}
+
+impl Evaluate for Neg {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        subtract(Value::from(0), self.value.evaluate(env)?)
+    }
+}
+
+impl Evaluate for Exp {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        power(
+            self.base.evaluate(env)?,
+            self.exponent.evaluate(env)?,
+            env.imaginary(),
+        )
+    }
+}
+
+impl Evaluate for Root {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        root(
+            self.degree.evaluate(env)?,
+            self.radicand.evaluate(env)?,
+            env.imaginary(),
+        )
+    }
+}
+
+impl Evaluate for Series {
+    // The body is evaluated once per index in a child scope, an empty range is 0 (sum) or 1 (prod)
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        let name = self.symbol.as_str();
+        let bound = |expression: &Expression| -> Result<i64, EvalError> {
+            let n = number(&expression.evaluate(env)?)?;
+            n.as_integer().ok_or_else(|| {
+                EvalError::Domain(format!(
+                    "the bounds of `{}` must be integers, found {}",
+                    name, n
+                ))
+            })
+        };
+        let (from, to) = (bound(&self.from)?, bound(&self.to)?);
+        // In i128, the distance between far apart i64 bounds would overflow
+        if to as i128 - from as i128 >= MAX_TERMS as i128 {
+            return Err(EvalError::Domain(format!(
+                "`{}` over more than {} terms",
+                name, MAX_TERMS
+            )));
+        }
+
+        let product = self.symbol == Symbols::Product;
+        let mut acc = Value::from(if product { 1 } else { 0 });
+        for i in from..=to {
+            let mut scope = env.child();
+            scope.bind(&self.index, Value::from(i));
+            let value = self.body.evaluate(&scope)?;
+            acc = if product {
+                multiply(acc, value)?
+            } else {
+                add(acc, value)?
+            };
+        }
+        Ok(acc)
+    }
+}
+
+impl Evaluate for Compare {
+    // 0 < x <= 1 holds when every link does
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        let mut left = self.first.evaluate(env)?;
+        let mut result = Value::Bool(true);
+        for (symbol, expression) in &self.rest {
+            let right = expression.evaluate(env)?;
+            result = both(result, compare(symbol, left, right.clone())?)?;
+            left = right;
+        }
+        Ok(result)
+    }
+}
+
+impl Evaluate for Logic {
+    // Stops at the first false (and) or true (or), x != 0 and 1 / x > 2 never divides by 0
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        let or = self.symbol == Symbols::Or;
+        let op = if or { either } else { both };
+        let mut terms = self.terms.iter();
+        let mut acc = match terms.next() {
+            Some(first) => first.evaluate(env)?,
+            None => return Ok(Value::Bool(!or)),
+        };
+        for term in terms {
+            if acc == Value::Bool(or) {
+                break;
+            }
+            acc = op(acc, term.evaluate(env)?)?;
+        }
+        Ok(acc)
+    }
+}
+
+impl Evaluate for Not {
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        each(self.value.evaluate(env)?, &|value| {
+            Ok(Value::Bool(!truth(&value)?))
+        })
+    }
+}
+
+impl Evaluate for Piecewise {
+    // Only the case that is taken gets evaluated
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        for (value, condition) in &self.cases {
+            if truth(&condition.evaluate(env)?)? {
+                return value.evaluate(env);
+            }
+        }
+        match &self.otherwise {
+            Some(otherwise) => otherwise.evaluate(env),
+            None => Err(EvalError::Domain(format!(
+                "no case of {} holds",
+                self.stringify()
+            ))),
+        }
+    }
+}
+
+impl Evaluate for Index {
+    // Subscripts index into lists, starting at 1 (x_1 is the first element)
+    fn evaluate(&self, env: &Environment) -> Result<Value, EvalError> {
+        let base = self.base.evaluate(env)?;
+        let index = number(&self.index.evaluate(env)?)?;
+
+        match base {
+            Value::List(mut items) => match index.as_integer() {
+                Some(i) if i >= 1 && i as usize <= items.len() => {
+                    Ok(items.swap_remove(i as usize - 1))
+                }
+                _ => Err(EvalError::IndexOutOfBounds {
+                    index: index.to_f64().max(0.0) as usize,
+                    len: items.len(),
+                }),
+            },
+            other => Err(EvalError::Type(format!("can't index into {:?}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{
+        ast::module::ParseMode,
+        parser::math::{expression::pexp, module::parse_module},
+    };
+
+    // Evaluate `expression` with the definitions in `source` in scope
+    fn eval_with(source: &str, expression: &str) -> Result<Value, EvalError> {
+        let statements = match source {
+            "" => Vec::new(),
+            source => {
+                let (_, module) =
+                    parse_module(source, false, ParseMode::Module).expect("source parses");
+                module.into_parts().1
+            }
+        };
+        let (rest, expression) = pexp(expression).expect("expression parses");
+        assert!(rest.is_empty(), "unparsed: {:?}", rest);
+
+        Environment::from(statements.as_slice()).evaluate(&expression)
+    }
+
+    #[test]
+    fn evaluate_constant_definitions() {
+        assert_eq!(eval_with("a = 2\nb = a + 3", "a*b"), Ok(Value::from(10)));
+    }
+
+    #[test]
+    fn evaluate_exact_numbers() {
+        let exact = |expression| eval_with("", expression).map(|v| v.to_string());
+        assert_eq!(exact("1/3 + 1/6"), Ok("1/2".to_string()));
+        assert_eq!(exact("(1/3) * 3"), Ok("1".to_string()));
+        assert_eq!(exact("2/3 - 1"), Ok("-1/3".to_string()));
+        assert_eq!(exact("0.1 + 0.2"), Ok("3/10".to_string()));
+        assert_eq!(exact("(8/27)^(1/3)"), Ok("2/3".to_string()));
+        // Anything inexact turns the result into a float
+        assert_eq!(
+            exact("1/4 + sqrt(2)^2"),
+            Ok(format!("{}", 0.25 + 2f64.sqrt().powi(2)))
+        );
+    }
+
+    #[test]
+    fn evaluate_complex_mode() {
+        let complex = |expression| {
+            let (_, expression) = pexp(expression).expect("expression parses");
+            let mut env = Environment::new();
+            env.complex('i');
+            env.evaluate(&expression).map(|v| v.to_string())
+        };
+        let ok = |text: &str| Ok(text.to_string());
+        assert_eq!(complex("(3 + 4i) * (1 - 2i)"), ok("11 - 2i"));
+        assert_eq!(complex("1 / (1 + i)"), ok("(1/2) - (1/2)i"));
+        assert_eq!(complex("i^2"), ok("-1"));
+        assert_eq!(complex("sqrt(-4)"), ok("2i"));
+        assert_eq!(complex("(-4)^(3/2)"), ok("-8i"));
+        // Principal values rather than the real root -2
+        assert_eq!(complex("root(3, -8)"), ok("1 + 1.732050807568877i"));
+        assert_eq!(complex("(-8)^(1/3)"), ok("1 + 1.732050807568877i"));
+        assert_eq!(complex("(-8)^2"), ok("64"));
+        assert_eq!(complex("e^(i*pi)"), ok("-1"));
+        assert_eq!(complex("ln(-1)"), ok(&format!("{}i", std::f64::consts::PI)));
+        assert_eq!(
+            complex("abs(3 + 4i) + conj(1 + i) + re(2 - i) + im(5i)"),
+            ok("13 - i")
+        );
+        // The index of a sum is its own i
+        assert_eq!(complex("sum(i, 1, 3, i)"), ok("6"));
+        // Without complex mode there is no i
+        assert!(matches!(
+            eval_with("", "sqrt(-4)"),
+            Err(EvalError::Domain(_))
+        ));
+        assert_eq!(
+            eval_with("", "i"),
+            Err(EvalError::UnboundVariable("i".to_string()))
+        );
+    }
+
+    #[test]
+    fn evaluate_function_call() {
+        assert_eq!(
+            eval_with("f(x) = x^2 + 2x + 1\ng(x, y) = f(x) * y", "g(2, 3)"),
+            Ok(Value::from(27))
+        );
+    }
+
+    #[test]
+    fn evaluate_parameters_shadow_constants() {
+        assert_eq!(
+            eval_with("x = 100\nf(x) = x + 1", "f(1) + x"),
+            Ok(Value::from(102))
+        );
+    }
+
+    #[test]
+    fn evaluate_implicit_multiplication_of_constant() {
+        assert_eq!(eval_with("a = 3", "a(2+1)"), Ok(Value::from(9)));
+    }
+
+    #[test]
+    fn evaluate_nested_roots_and_powers() {
+        assert_eq!(eval_with("", "sqrt(root(3, 8)^2 * 4)"), Ok(Value::from(4)));
+        assert_eq!(eval_with("", "2^3^2"), Ok(Value::from(512)));
+        assert_eq!(eval_with("", "root(3, -8)"), Ok(Value::from(-2)));
+    }
+
+    #[test]
+    fn evaluate_lists() {
+        assert_eq!(
+            eval_with("v = [1, 2, 3]", "2v + 1"),
+            Ok(Value::List(vec![3.0.into(), 5.0.into(), 7.0.into()]))
+        );
+        assert_eq!(eval_with("v = [1, 2, 3]", "v_2"), Ok(Value::from(2)));
+        assert_eq!(
+            eval_with("v = [1, 2, 3]", "v_4"),
+            Err(EvalError::IndexOutOfBounds { index: 4, len: 3 })
+        );
+    }
+
+    #[test]
+    fn evaluate_builtins() {
+        assert_eq!(eval_with("", "cos(0) + exp(0)"), Ok(Value::from(2)));
+        assert_eq!(
+            eval_with("", "abs(-2) * floor(2.5) * ceil(0.5)"),
+            Ok(Value::from(4))
+        );
+        assert_eq!(
+            eval_with("", "max(1, 3) + min(2, 5) + gcd(12, 18) + factorial(4)"),
+            Ok(Value::from(35))
+        );
+        assert_eq!(eval_with("", "ln(1) + e - exp(1)"), Ok(Value::from(0)));
+        assert_eq!(
+            eval_with("v = [-1, 2]", "abs(v)"),
+            Ok(Value::List(vec![1.0.into(), 2.0.into()]))
+        );
+        // sum and mean take the whole list
+        assert_eq!(
+            eval_with("T = [1, 2, 6]", "sum(T) + mean(T)"),
+            Ok(Value::from(12))
+        );
+        assert_eq!(eval_with("", "mean([1, 2])"), Ok(Value::from(1.5)));
+        assert!(matches!(
+            eval_with("T = [1]", "mean(T, T)"),
+            Err(EvalError::Arity { .. })
+        ));
+        // A definition takes the place of the built-in, and of pi
+        assert_eq!(
+            eval_with("abs(x) = 7\npi = 3", "abs(1) + pi"),
+            Ok(Value::from(10))
+        );
+        assert_eq!(
+            eval_with("", "2pi"),
+            Ok(Value::from(2.0 * std::f64::consts::PI))
+        );
+    }
+
+    #[test]
+    fn evaluate_builtin_errors() {
+        assert_eq!(
+            eval_with("", "max(1)"),
+            Err(EvalError::Arity {
+                name: "max".to_string(),
+                expected: 2,
+                found: 1
+            })
+        );
+        assert!(matches!(eval_with("", "ln(0)"), Err(EvalError::Domain(_))));
+        assert!(matches!(
+            eval_with("", "asin(2)"),
+            Err(EvalError::Domain(_))
+        ));
+        assert!(matches!(
+            eval_with("", "factorial(-1)"),
+            Err(EvalError::Domain(_))
+        ));
+        assert!(matches!(
+            eval_with("", "diff(max(x, 1), x)"),
+            Err(EvalError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn evaluate_series() {
+        assert_eq!(eval_with("", "sum(i, 1, 3, i^2)"), Ok(Value::from(14)));
+        assert_eq!(eval_with("", "\\prod_{k=1}^{4} k"), Ok(Value::from(24)));
+        assert_eq!(eval_with("n = 3", "2Σ_(i=1)^n i + 1"), Ok(Value::from(13)));
+        assert_eq!(eval_with("", "sum(i, 1, 0, i)"), Ok(Value::from(0)));
+        assert_eq!(eval_with("", "prod(i, 1, 0, i)"), Ok(Value::from(1)));
+        // The index shadows a constant with the same name, and is gone after the sum
+        assert_eq!(
+            eval_with("i = 10\nf(x) = x + i", "sum(i, 1, 2, f(i))"),
+            Ok(Value::from(23))
+        );
+        assert_eq!(
+            eval_with("v = [1, 2, 3]", "sum(i, 1, 3, v_i)"),
+            Ok(Value::from(6))
+        );
+        assert!(matches!(
+            eval_with("", "sum(i, 1, 2.5, i)"),
+            Err(EvalError::Domain(_))
+        ));
+        // Bounds at the ends of i64 are too many terms, or none at all
+        assert!(matches!(
+            eval_with("", "sum(i, -9223372036854775807, 9223372036854775807, i)"),
+            Err(EvalError::Domain(_))
+        ));
+        assert_eq!(
+            eval_with("", "sum(i, 9223372036854775807, -9223372036854775807, i)"),
+            Ok(Value::from(0))
+        );
+    }
+
+    #[test]
+    fn evaluate_conditions() {
+        let ok = |b: bool| Ok(Value::Bool(b));
+        assert_eq!(eval_with("x = 3", "0 < x <= 3 and x != 2"), ok(true));
+        assert_eq!(eval_with("x = 3", "not x > 1 or x = 1/2"), ok(false));
+        assert_eq!(eval_with("", "1 = 1.0 and 1/2 ≤ 0.5"), ok(true));
+        // Only as far as needed, the division never happens
+        assert_eq!(eval_with("x = 0", "x != 0 and 1 / x > 2"), ok(false));
+        assert_eq!(
+            eval_with("v = [1, 2, 3]", "v >= 2"),
+            Ok(Value::List(vec![false.into(), true.into(), true.into()]))
+        );
+        assert!(matches!(
+            eval_with("", "1 and true"),
+            Err(EvalError::Type(_))
+        ));
+    }
+
+    #[test]
+    fn evaluate_piecewise() {
+        let source =
+            "f(x) = { x if x > 0; 0 otherwise }\ng(n) = { 1 if n <= 1; n * g(n - 1) otherwise }";
+        assert_eq!(eval_with(source, "f(-2) + f(3)"), Ok(Value::from(3)));
+        // The case that isn't taken isn't evaluated, so the recursion ends
+        assert_eq!(eval_with(source, "g(5)"), Ok(Value::from(120)));
+        assert!(matches!(
+            eval_with("h(x) = { 1 if x > 0 }", "h(-1)"),
+            Err(EvalError::Domain(_))
+        ));
+    }
+
+    #[test]
+    fn evaluate_derivative() {
+        assert_eq!(
+            eval_with("f(x) = diff(x^3, x)", "f(2)"),
+            Ok(Value::from(12))
+        );
+        assert_eq!(
+            eval_with("", "diff(g(2), x)"),
+            Err(EvalError::UnknownFunction("g".to_string()))
+        );
+    }
+
+    #[test]
+    fn evaluate_unbound_variable() {
+        assert_eq!(
+            eval_with("f(x) = x + y", "f(1)"),
+            Err(EvalError::UnboundVariable("y".to_string()))
+        );
+    }
+
+    #[test]
+    fn evaluate_arity_mismatch() {
+        assert_eq!(
+            eval_with("f(x, y) = x", "f(1)"),
+            Err(EvalError::Arity {
+                name: "f".to_string(),
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn evaluate_division_by_zero() {
+        let expression = Expression::Opperations(Box::new(Div {
+            first: Expression::Constant(1.0.into()),
+            second: Expression::Opperations(Box::new(Sub {
+                first: Expression::Constant(2.0.into()),
+                second: Expression::Constant(2.0.into()),
+            })),
+        }));
+        assert_eq!(
+            Environment::new().evaluate(&expression),
+            Err(EvalError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn evaluate_even_root_of_negative() {
+        assert!(matches!(
+            eval_with("", "sqrt(1 - 5)").unwrap_err(),
+            EvalError::Domain(_)
+        ));
+    }
+
+    #[test]
+    fn evaluate_recursive_constant() {
+        assert_eq!(
+            eval_with("a = a + 1", "a"),
+            Err(EvalError::RecursionLimit("a".to_string()))
+        );
+    }
+}
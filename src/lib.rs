@@ -1,4 +1,9 @@
 pub mod ast;
+pub mod builtins;
+pub mod calculus;
+pub mod data;
+pub mod eval;
+pub mod loader;
 pub mod parser;
 pub mod symbols;
 pub mod test;
@@ -0,0 +1,381 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::{
+    ast::{
+        Comment, Definition, Statement,
+        expressions::Expression,
+        module::{Metadata, ParseMode},
+        system_func::System,
+    },
+    data::{self, DataError, json},
+    eval::Environment,
+    parser::{
+        markdown::rebase_images,
+        math::module::{FileParseError, parse_entry_file, parse_module_file},
+        python::parse_python,
+    },
+};
+
+#[derive(Error, Debug)]
+pub enum LoadError {
+    #[error(transparent)]
+    Parse(#[from] FileParseError),
+    #[error("import cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+    #[error(transparent)]
+    Data(#[from] DataError),
+}
+
+// Entry file with its frames spliced in and the definitions it imports
+#[derive(Debug)]
+pub struct Document {
+    pub metadata: Metadata,
+    pub statements: Vec<Statement>,
+    pub imports: Vec<Definition>,
+    // Every file the document was built from (sources, imports and data), for watch mode
+    pub sources: Vec<PathBuf>,
+}
+
+impl Document {
+    // Imported definitions first, so the page can shadow them
+    pub fn environment(&self) -> Environment<'_> {
+        let mut env = Environment::new();
+        if let Some(unit) = self.metadata.imaginary {
+            env.complex(unit);
+        }
+        for definition in &self.imports {
+            env.define(definition);
+        }
+        for statement in &self.statements {
+            if let Statement::Definition(definition) = statement {
+                env.define(definition);
+            }
+        }
+        env
+    }
+}
+
+// Files currently being loaded (for cycles) and modules already imported
+#[derive(Default)]
+struct Loader {
+    // Directory of the entry file, prose in frames is rebased on it
+    root: PathBuf,
+    chain: Vec<(PathBuf, String)>,
+    imported: HashSet<PathBuf>,
+    imports: Vec<Definition>,
+    sources: Vec<PathBuf>,
+}
+
+pub fn load(entry_path: &str) -> Result<Document, LoadError> {
+    let entry = Path::new(entry_path);
+    let mut loader = Loader {
+        root: parent(entry).to_path_buf(),
+        ..Loader::default()
+    };
+
+    loader.enter(entry)?;
+    let (metadata, statements) = parse_entry_file(entry_path)?.into_parts();
+    let statements = loader.resolve(parent(entry), statements)?;
+    loader.chain.pop();
+
+    Ok(Document {
+        metadata,
+        statements,
+        imports: loader.imports,
+        sources: loader.sources,
+    })
+}
+
+fn parent(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+impl Loader {
+    // Push a file on the chain, failing if it is already being loaded
+    fn enter(&mut self, path: &Path) -> Result<PathBuf, LoadError> {
+        let name = path.to_string_lossy().to_string();
+        let key = fs::canonicalize(path).map_err(|_| FileParseError::NotFound(name.clone()))?;
+
+        if let Some(start) = self.chain.iter().position(|(k, _)| *k == key) {
+            let mut cycle = self.chain[start..]
+                .iter()
+                .map(|(_, name)| name.clone())
+                .collect::<Vec<_>>();
+            cycle.push(name);
+            return Err(LoadError::Cycle(cycle));
+        }
+        self.chain.push((key.clone(), name));
+        self.source(path);
+        Ok(key)
+    }
+
+    fn source(&mut self, path: &Path) {
+        if !self.sources.iter().any(|source| source == path) {
+            self.sources.push(path.to_path_buf());
+        }
+    }
+
+    // data("temps.csv") and json(..) are read relative to the file they're written in,
+    // the file is checked here so a bad cell fails the build
+    fn located(&mut self, expression: &Expression, base: &Path) -> Result<Expression, DataError> {
+        let mut each = |items: &[Expression]| {
+            items
+                .iter()
+                .map(|item| self.located(item, base))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(match expression {
+            Expression::List(items) => Expression::List(each(items)?),
+            Expression::FunctionCall(ident, args) => {
+                Expression::FunctionCall(ident.clone(), each(args)?)
+            }
+            Expression::System(system) => Expression::System(Box::new(match system.as_ref() {
+                System::Data(path, column) => {
+                    let path = base.join(path);
+                    self.source(&path);
+                    let path = path.to_string_lossy().to_string();
+                    data::load(&path, column.as_ref().map(|c| c.inner.as_str()))?;
+                    System::Data(path, column.clone())
+                }
+                System::Json(path, field) => {
+                    let path = base.join(path);
+                    self.source(&path);
+                    let path = path.to_string_lossy().to_string();
+                    json::load_json(&path, field.as_deref())?;
+                    System::Json(path, field.clone())
+                }
+                System::Print(expression) => System::Print(self.located(expression, base)?),
+                System::Simplify(expression) => System::Simplify(self.located(expression, base)?),
+                System::Diff(expression, x) => {
+                    System::Diff(self.located(expression, base)?, x.clone())
+                }
+                system => system.clone(),
+            })),
+            Expression::Opperations(opperation) => {
+                let mut error = None;
+                let opperation = opperation.map(&mut |operand| {
+                    self.located(operand, base).unwrap_or_else(|e| {
+                        error.get_or_insert(e);
+                        Expression::Empty
+                    })
+                });
+                match error {
+                    Some(error) => return Err(error),
+                    None => Expression::Opperations(opperation),
+                }
+            }
+            expression => expression.clone(),
+        })
+    }
+
+    // Splice frames in place, move the definitions of imports into the shared scope
+    fn resolve(
+        &mut self,
+        base: &Path,
+        statements: Vec<Statement>,
+    ) -> Result<Vec<Statement>, LoadError> {
+        let mut resolved = Vec::new();
+
+        for statement in statements {
+            match statement {
+                Statement::Expression(Expression::System(system)) => match *system {
+                    System::Frame(path) => {
+                        let path = base.join(path);
+                        self.enter(&path)?;
+                        let frame = parse_module_file(&path.to_string_lossy(), ParseMode::Frame)?;
+                        let (_, statements) = frame.into_parts();
+                        resolved.extend(self.resolve(parent(&path), statements)?);
+                        self.chain.pop();
+                    }
+                    System::Import(path) => self.import(&base.join(path))?,
+                    System::Json(path, field) => {
+                        resolved.extend(self.bind_json(base, path, field)?)
+                    }
+                    system => resolved.push(Statement::Expression(
+                        self.located(&Expression::System(Box::new(system)), base)?,
+                    )),
+                },
+                Statement::Expression(expression) => {
+                    resolved.push(Statement::Expression(self.located(&expression, base)?))
+                }
+                Statement::Definition(Definition::Function(ident, vars, expression)) => resolved
+                    .push(Statement::Definition(Definition::Function(
+                        ident,
+                        vars,
+                        self.located(&expression, base)?,
+                    ))),
+                Statement::Definition(Definition::Constant(ident, expression)) => {
+                    resolved.push(Statement::Definition(Definition::Constant(
+                        ident,
+                        self.located(&expression, base)?,
+                    )))
+                }
+                // Images are relative to the frame that shows them
+                Statement::Comment(Comment::Multi(text)) => {
+                    let relative = base.strip_prefix(&self.root).unwrap_or(base);
+                    resolved.push(Statement::Comment(Comment::Multi(rebase_images(
+                        &text, relative,
+                    ))))
+                }
+                statement => resolved.push(statement),
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    // A json(..) object on its own line binds each of its members as a constant,
+    // any other value is kept as a statement
+    fn bind_json(
+        &mut self,
+        base: &Path,
+        path: String,
+        field: Option<String>,
+    ) -> Result<Option<Statement>, LoadError> {
+        let file = base.join(&path);
+        let Some(constants) = json::constants(&file.to_string_lossy(), field.as_deref())? else {
+            let expression = Expression::System(Box::new(System::Json(path, field)));
+            return Ok(Some(Statement::Expression(
+                self.located(&expression, base)?,
+            )));
+        };
+        self.source(&file);
+        self.imports.extend(
+            constants.into_iter().map(|(name, value)| {
+                Definition::Constant(name.into(), Expression::Constant(value))
+            }),
+        );
+        Ok(None)
+    }
+
+    // Only the definitions of a module are kept, each module is loaded once
+    fn import(&mut self, path: &Path) -> Result<(), LoadError> {
+        let key = self.enter(path)?;
+        if self.imported.insert(key) {
+            let definitions = self.definitions(path)?;
+            self.imports.extend(definitions);
+        }
+        self.chain.pop();
+        Ok(())
+    }
+
+    // Python files (model.py) only hold definitions, they can't import anything themselves
+    fn definitions(&mut self, path: &Path) -> Result<Vec<Definition>, LoadError> {
+        let name = path.to_string_lossy();
+        if path.extension().is_some_and(|extension| extension == "py") {
+            let source =
+                fs::read_to_string(path).map_err(|_| FileParseError::NotFound(name.to_string()))?;
+            return Ok(parse_python(&name, &source).map_err(FileParseError::from)?);
+        }
+
+        let (_, statements) = parse_module_file(&name, ParseMode::Module)?.into_parts();
+        Ok(self
+            .resolve(parent(path), statements)?
+            .into_iter()
+            .filter_map(|statement| match statement {
+                Statement::Definition(definition) => Some(definition),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{ast::base::Value, parser::math::expression::pexp};
+
+    #[test]
+    fn test_load_imports_and_frames() {
+        let document = load("./test/loader/main.mtx").expect("should load");
+
+        // The frame's statements are spliced in, the import and frame calls are gone
+        assert_eq!(document.statements.len(), 3);
+        assert_eq!(document.imports.len(), 2);
+
+        let env = document.environment();
+        let (_, expression) = pexp("s(b)").unwrap();
+        assert_eq!(env.evaluate(&expression), Ok(Value::from(100)));
+        let (_, expression) = pexp("a").unwrap();
+        assert_eq!(env.evaluate(&expression), Ok(Value::from(10)));
+    }
+
+    #[test]
+    fn test_load_rebases_frame_images() {
+        let document = load("./test/loader/figures.mtx").expect("should load");
+        let prose = document
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Comment(Comment::Multi(text)) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(prose.len(), 2);
+        assert!(prose[0].contains("![cover](img/cover.png)"));
+        assert!(prose[1].contains("![plot](chapter/img/plot.png)"));
+    }
+
+    #[test]
+    fn test_load_data() {
+        let document = load("./test/loader/data.mtx").expect("should load");
+        let env = document.environment();
+        let (_, expression) = pexp("T_2").unwrap();
+        assert_eq!(env.evaluate(&expression), Ok(Value::from(18.5)));
+
+        let error = load("./test/loader/bad_data.mtx").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "./test/loader/bad.csv:3: `warm` in numeric column `celsius` is not a number"
+        );
+    }
+
+    #[test]
+    fn test_load_json() {
+        let document = load("./test/loader/json.mtx").expect("should load");
+        let env = document.environment();
+        // k and runs are bound by json("results.json", "fit")
+        let (_, expression) = pexp("k + r").unwrap();
+        assert_eq!(env.evaluate(&expression), Ok(Value::from(4.5)));
+        assert!(
+            document
+                .sources
+                .contains(&PathBuf::from("./test/loader/results.json"))
+        );
+    }
+
+    #[test]
+    fn test_load_python_module() {
+        let document = load("./test/loader/python.mtx").expect("should load");
+        assert_eq!(document.imports.len(), 3);
+
+        let env = document.environment();
+        let (_, expression) = pexp("area(r)").unwrap();
+        assert_eq!(env.evaluate(&expression), Ok(Value::from(12)));
+    }
+
+    #[test]
+    fn test_load_detects_cycles() {
+        let error = load("./test/loader/cycle_a.mtx").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "import cycle: ./test/loader/cycle_a.mtx -> ./test/loader/cycle_b.mtx -> ./test/loader/cycle_a.mtx"
+        );
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let error = load("./test/loader/missing.mtx").unwrap_err();
+        assert!(matches!(
+            error,
+            LoadError::Parse(FileParseError::NotFound(_))
+        ));
+    }
+}
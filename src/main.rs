@@ -3,16 +3,29 @@ use actix_web::{
     App, HttpRequest, HttpResponse, HttpServer, Responder, Result, get,
     web::{self, Data},
 };
-use clap::{Parser, Subcommand};
-use futures::{StreamExt, lock::Mutex};
-use notify::{Event, RecursiveMode, Watcher};
+use clap::{Parser, Subcommand, ValueEnum};
+use mtx::{
+    ast::module::Metadata,
+    data::DataError,
+    loader::{LoadError, load},
+    parser::math::module::FileParseError,
+    writer::{
+        display::html::{Displayed, html_escape},
+        latex,
+        python::{self, Library},
+        typst,
+    },
+};
+use notify::{Event, EventKind, RecursiveMode, Watcher, event::ModifyKind};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ops::Deref,
-    path::Path,
-    sync::{Arc, mpsc},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex, mpsc},
+    time::Duration,
 };
-use tokio::sync::{broadcast, watch};
+use thiserror::Error;
+use tokio::sync::broadcast;
 
 #[derive(Parser, Debug)]
 #[command(name = "dev-server")]
@@ -26,14 +39,32 @@ struct Args {
 enum Commands {
     /// Build the project without serving
     Build {
-        /// Source directory
-        #[arg(default_value = "./raw")]
+        /// Entry file
+        #[arg(default_value = "main.mtx")]
         source: String,
 
         /// Output directory
         #[arg(short, long, default_value = "./dist")]
         output: String,
     },
+    /// Export the document in another format
+    Export {
+        /// Entry file
+        #[arg(default_value = "main.mtx")]
+        entry: String,
+
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: ExportFormat,
+
+        /// Output file (defaults to the entry file with the format's extension)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Use numpy instead of math in Python exports
+        #[arg(long)]
+        numpy: bool,
+    },
     /// Serve files without hot reload
     Serve {
         /// Directory to serve
@@ -52,10 +83,11 @@ enum Commands {
     },
     /// Serve files with hot reload enabled
     Watch {
-        /// Directory to serve and watch
+        /// Entry file, it and every file it reads are watched
         #[arg(default_value = "main.mtx")]
         entry: String,
 
+        /// Output directory to serve
         #[arg(short, long, value_name = "dir", default_value = "./dist")]
         dir: String,
 
@@ -69,27 +101,380 @@ enum Commands {
     },
 }
 
-struct ReloadStatus {
-    pub hot_reload_enable: bool,
-    pub needs_reload: bool,
+#[derive(ValueEnum, Clone, Debug)]
+enum ExportFormat {
+    /// Standalone LaTeX document (.tex)
+    Latex,
+    /// Typst document (.typ)
+    Typst,
+    /// Importable Python module (.py)
+    Python,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Latex => "tex",
+            ExportFormat::Typst => "typ",
+            ExportFormat::Python => "py",
+        }
+    }
+}
+
+// A build failure as shown in the page's overlay, the position is known for syntax errors
+#[derive(Clone, Debug)]
+struct Diagnostic {
+    file: Option<String>,
+    line: Option<usize>,
+    column: Option<usize>,
+    snippet: Option<String>,
+    message: String,
+}
+
+impl From<&BuildError> for Diagnostic {
+    fn from(error: &BuildError) -> Self {
+        match error {
+            BuildError::Load(LoadError::Parse(FileParseError::Syntax(error))) => Diagnostic {
+                file: Some(error.file.clone()),
+                line: Some(error.line),
+                column: Some(error.column),
+                snippet: Some(error.snippet.clone()),
+                message: error.message.clone(),
+            },
+            BuildError::Load(LoadError::Data(DataError::Json {
+                file,
+                line,
+                column,
+                message,
+            })) => Diagnostic {
+                file: Some(file.clone()),
+                line: Some(*line),
+                column: Some(*column),
+                snippet: None,
+                message: message.clone(),
+            },
+            error => Diagnostic {
+                file: None,
+                line: None,
+                column: None,
+                snippet: None,
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+// {"status": "ok"} or {"status": "error", "error": {"file", "line", "column", "snippet", "message"}}
+fn build_json(result: &Result<(), Diagnostic>) -> serde_json::Value {
+    match result {
+        Ok(()) => serde_json::json!({ "status": "ok" }),
+        Err(diagnostic) => serde_json::json!({
+            "status": "error",
+            "error": {
+                "file": diagnostic.file,
+                "line": diagnostic.line,
+                "column": diagnostic.column,
+                "snippet": diagnostic.snippet,
+                "message": diagnostic.message,
+            }
+        }),
+    }
+}
+
+// Result of a rebuild, sent to every connected page
+#[derive(Clone, Debug)]
+struct Rebuilt {
+    paths: Vec<PathBuf>,
+    result: Result<(), Diagnostic>,
+}
+
+impl Rebuilt {
+    // Server-Sent Event carrying the changed paths and the build status
+    fn event(&self) -> String {
+        let mut data = build_json(&self.result);
+        data["paths"] = self
+            .paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        format!("event: rebuild\ndata: {}\n\n", data)
+    }
+}
+
+struct HotReload {
+    enabled: bool,
+    events: broadcast::Sender<Rebuilt>,
+    // The last build, for pages that load while it's broken
+    build: Mutex<Result<(), Diagnostic>>,
+}
+
+impl HotReload {
+    fn publish(&self, rebuilt: Rebuilt) {
+        *self.build.lock().unwrap() = rebuilt.result.clone();
+        // No connected pages is fine
+        let _ = self.events.send(rebuilt);
+    }
+}
+
+async fn reload_status(reload: web::Data<HotReload>) -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({ "hot_reload_enabled": reload.enabled }))
+}
+
+async fn build_status(reload: web::Data<HotReload>) -> impl Responder {
+    HttpResponse::Ok().json(build_json(&reload.build.lock().unwrap()))
+}
+
+// Event stream of rebuilds, 204 tells the page not to (re)connect when hot reload is off
+async fn reload_events(reload: web::Data<HotReload>) -> HttpResponse {
+    if !reload.enabled {
+        return HttpResponse::NoContent().finish();
+    }
+    let mut rx = reload.events.subscribe();
+    let stream = async_stream::stream! {
+        yield Ok::<_, actix_web::Error>(web::Bytes::from("retry: 1000\nevent: connected\ndata: {}\n\n"));
+        loop {
+            match rx.recv().await {
+                Ok(rebuilt) => yield Ok(web::Bytes::from(rebuilt.event())),
+                // A slow page only needs the latest build
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream)
+}
+
+// Page template, generated content replaces the "</- ... -/>" markers
+const TEMPLATE: &str = include_str!("../raw/index.html");
+
+#[derive(Error, Debug)]
+enum BuildError {
+    #[error(transparent)]
+    Load(#[from] LoadError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("image not found: {0}")]
+    ImageNotFound(String),
+    #[error("image `{0}` is outside the document's directory")]
+    ImageOutside(String),
+}
+
+// Local images are read relative to the entry file (the loader rebased those shown by frames)
+// and copied to the same place in the output
+fn copy_images(
+    displayed: &Displayed,
+    entry_path: &str,
+    directory: &str,
+) -> Result<Vec<PathBuf>, BuildError> {
+    let base = Path::new(entry_path).parent().unwrap_or(Path::new(""));
+    let mut copied = Vec::new();
+
+    for src in displayed.images() {
+        if src.contains("://") || src.starts_with("data:") {
+            continue;
+        }
+        let path = Path::new(src);
+        if !path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(BuildError::ImageOutside(src.to_string()));
+        }
+        let source = base.join(path);
+        if !source.is_file() {
+            return Err(BuildError::ImageNotFound(source.display().to_string()));
+        }
+        let target = Path::new(directory).join(path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&source, target)?;
+        copied.push(source);
+    }
+    Ok(copied)
+}
+
+// Build the page, returns the files it was built from
+async fn build_project(directory: &str, entry_path: &str) -> Result<Vec<PathBuf>, BuildError> {
+    let mut document = load(entry_path)?;
+    let metadata = std::mem::take(&mut document.metadata);
+    let mut sources = std::mem::take(&mut document.sources);
+    let displayed = Displayed::new(document);
+
+    std::fs::create_dir_all(directory)?;
+    // Watched like data files
+    sources.extend(copy_images(&displayed, entry_path, directory)?);
+    std::fs::write(
+        Path::new(directory).join("index.html"),
+        render_page(&metadata, &displayed),
+    )?;
+    Ok(sources)
+}
+
+fn export_project(
+    entry_path: &str,
+    format: &ExportFormat,
+    output: Option<String>,
+    library: Library,
+) -> Result<String, BuildError> {
+    let document = load(entry_path)?;
+    let output = output.unwrap_or_else(|| {
+        Path::new(entry_path)
+            .with_extension(format.extension())
+            .to_string_lossy()
+            .to_string()
+    });
+
+    let exported = match format {
+        ExportFormat::Latex => latex::document(&document.metadata, &document.statements),
+        ExportFormat::Typst => typst::document(&document.metadata, &document.statements),
+        ExportFormat::Python => python::document(&document, library),
+    };
+    std::fs::write(&output, exported)?;
+    Ok(output)
+}
+
+fn render_page(metadata: &Metadata, displayed: &Displayed) -> String {
+    let field = |value: &Option<String>| html_escape(value.as_deref().unwrap_or(""));
+
+    TEMPLATE
+        .replace(
+            "</- Title -/>",
+            &html_escape(metadata.title.as_deref().unwrap_or("Untitled")),
+        )
+        .replace("</- Author -/>", &field(&metadata.author))
+        .replace("</- Email -/>", &field(&metadata.author_email))
+        .replace("</- Contents -/>", &displayed.contents())
+        .replace("</- Replace Me -/>", &displayed.to_string())
+}
+
+// How long a change has to settle before rebuilding, editors often save in several steps
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+// What a document can be built from, anything else (logs, swap files) never triggers a rebuild
+const SOURCE_EXTENSIONS: [&str; 5] = ["mtx", "md", "py", "csv", "json"];
+
+// Files the last build read. Their directories are watched rather than the files themselves,
+// a file that is removed (or replaced on save) is then still seen when it comes back.
+#[derive(Default)]
+struct Sources {
+    files: HashSet<PathBuf>,
+    directories: HashSet<PathBuf>,
+    // Where the build writes to, its own output isn't a change
+    output: PathBuf,
 }
 
-async fn needs_reload(data: web::Data<Mutex<ReloadStatus>>) -> impl Responder {
-    let mut status = data.lock().await;
-    let needs_reload = status.needs_reload;
-    let hot_reload_enable = status.hot_reload_enable;
+impl Sources {
+    fn track(&mut self, watcher: &mut impl Watcher, sources: Vec<PathBuf>) {
+        self.files = sources
+            .iter()
+            .filter_map(|source| std::path::absolute(source).ok())
+            .collect();
+        for file in &self.files {
+            if let Some(directory) = file.parent()
+                && !self.directories.contains(directory)
+                && watcher
+                    .watch(directory, RecursiveMode::NonRecursive)
+                    .is_ok()
+            {
+                self.directories.insert(directory.to_path_buf());
+            }
+        }
+    }
+
+    // A file that could be a source the build hasn't read yet
+    fn could_be_source(&self, path: &Path) -> bool {
+        !path.starts_with(&self.output)
+            && path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| SOURCE_EXTENSIONS.contains(&extension))
+    }
 
-    if hot_reload_enable && needs_reload {
-        println!("/status -> Forcing Page Reload!");
-        status.needs_reload = false; // Reset after checking
+    // Sources touched by an event. After a failed build any source file counts, the fix may
+    // be a file the build never got to (a missing import being created).
+    fn changed(&self, event: &Event, failed: bool) -> Vec<PathBuf> {
+        match event.kind {
+            EventKind::Create(_) | EventKind::Remove(_) => (),
+            EventKind::Modify(kind) if !matches!(kind, ModifyKind::Metadata(_)) => (),
+            _ => return Vec::new(),
+        }
+        event
+            .paths
+            .iter()
+            .filter(|path| self.files.contains(*path) || failed && self.could_be_source(path))
+            .cloned()
+            .collect()
     }
+}
 
-    HttpResponse::Ok().json(
-        serde_json::json!({ "hot_reload_enabled": hot_reload_enable,  "reload": needs_reload }),
-    )
+// Sources changed by the next burst of events: the first event that touches a source and
+// whatever follows it within DEBOUNCE, one rebuild for a save that writes several files
+async fn next_change(
+    rx: &mut tokio::sync::mpsc::Receiver<Event>,
+    watched: &Sources,
+    failed: bool,
+) -> Option<Vec<PathBuf>> {
+    loop {
+        let event = rx.recv().await?;
+        let mut paths = watched.changed(&event, failed);
+        if paths.is_empty() {
+            continue;
+        }
+        while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+            for path in watched.changed(&event, failed) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        return Some(paths);
+    }
 }
 
-async fn build_project(directory: &str, entry_path: &str) -> () {}
+async fn watch_sources(
+    entry: String,
+    directory: String,
+    sources: Vec<PathBuf>,
+    mut failed: bool,
+    reload: web::Data<HotReload>,
+) {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+    let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
+        if let Ok(event) = res {
+            let _ = tx.blocking_send(event);
+        }
+    })
+    .expect("Failed to create watcher");
+
+    let mut watched = Sources {
+        output: std::path::absolute(&directory).unwrap_or_else(|_| PathBuf::from(&directory)),
+        ..Sources::default()
+    };
+    watched.track(&mut watcher, sources);
+    println!("👀 Watching {} source file(s)", watched.files.len());
+
+    while let Some(paths) = next_change(&mut rx, &watched, failed).await {
+        println!("📝 Source changed: {:?}, rebuilding...", paths);
+        let result = match build_project(&directory, &entry).await {
+            // New imports or data files are watched from now on
+            Ok(sources) => {
+                watched.track(&mut watcher, sources);
+                println!("✅ Build complete");
+                Ok(())
+            }
+            Err(error) => {
+                eprintln!("❌ Build failed\n{}", error);
+                Err(Diagnostic::from(&error))
+            }
+        };
+        failed = result.is_err();
+        reload.publish(Rebuilt { paths, result });
+    }
+}
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
@@ -101,8 +486,28 @@ async fn main() -> std::io::Result<()> {
             println!("📂 Source: {}", source);
             println!("📦 Output: {}", output);
 
-            // TODO: Implement build logic here
-            todo!("Build functionality not yet implemented");
+            if let Err(error) = build_project(&output, &source).await {
+                eprintln!("❌ Build failed\n{}", error);
+                std::process::exit(1);
+            }
+            println!("✅ Build complete");
+            return Ok(());
+        }
+        Commands::Export {
+            entry,
+            format,
+            output,
+            numpy,
+        } => {
+            let library = if numpy { Library::Numpy } else { Library::Math };
+            match export_project(&entry, &format, output, library) {
+                Ok(output) => println!("✅ Exported {}", output),
+                Err(error) => {
+                    eprintln!("❌ Export failed\n{}", error);
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
         }
         Commands::Serve {
             entry,
@@ -118,6 +523,16 @@ async fn main() -> std::io::Result<()> {
         } => (entry, dir, port, host, true),
     };
 
+    // A broken source shouldn't stop the server, the error is shown and the last build served
+    let (sources, built) = match build_project(&build_directory, &entr).await {
+        Ok(sources) => (sources, Ok(())),
+        Err(error) => {
+            eprintln!("❌ Build failed\n{}", error);
+            (vec![PathBuf::from(&entr)], Err(Diagnostic::from(&error)))
+        }
+    };
+    std::fs::create_dir_all(&build_directory)?;
+
     let bind_addr = format!("{}:{}", host, port);
 
     println!("🚀 Server running at http://{}:{}", host, port);
@@ -132,68 +547,30 @@ async fn main() -> std::io::Result<()> {
         println!("⚪ Hot reload: DISABLED");
     }
 
-    let data = web::Data::new(Mutex::new(ReloadStatus {
-        needs_reload: false,
-        hot_reload_enable: reload_enabled,
-    }));
+    let failed = built.is_err();
+    let data = web::Data::new(HotReload {
+        enabled: reload_enabled,
+        events: broadcast::channel(16).0,
+        build: Mutex::new(built),
+    });
 
-    // Directory Watcher (only if reload is enabled)
+    // Rebuild when the document or its data changes, then tell the open pages
     if reload_enabled {
-        let dir_clone = build_directory.clone();
-        let data_clone = data.clone();
-
-        tokio::spawn(async move {
-            use notify::Watcher;
-
-            let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-
-            // Create the watcher with a closure that sends events to the channel
-            let mut watcher =
-                notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
-                    if let Ok(event) = res {
-                        let _ = tx.blocking_send(event);
-                    }
-                })
-                .expect("Failed to create watcher");
-
-            watcher
-                .watch(Path::new(&dir_clone), RecursiveMode::Recursive)
-                .expect("Failed to watch directory");
-
-            println!("👀 Watcher started for: {}", &dir_clone);
-
-            // Keep the watcher alive and process events
-            while let Some(event) = rx.recv().await {
-                let mut status = data_clone.lock().await;
-                match event.kind {
-                    notify::EventKind::Access(access_kind) => {
-                        // DO NOTHING
-                        println!("📝 File Accessed: {:?}", event.paths)
-                    }
-                    notify::EventKind::Create(create_kind) => {
-                        // NEEDS_RELOAD
-
-                        status.needs_reload = true;
-                        println!("📝 File Created: {:?}... Needs Reload!", event.paths)
-                    }
-                    notify::EventKind::Modify(modify_kind) => {
-                        // NEEDS_RELOAD
-                        status.needs_reload = true;
-                        println!("📝 File changed: {:?}... Needs Reload", event.paths)
-                    }
-                    notify::EventKind::Remove(remove_kind) => {
-                        // DOESN'T NEED RELOAD
-                        println!("📝 File removed: {:?}, recreating...", event.paths)
-                    }
-                    _ => (),
-                };
-            }
-        });
+        tokio::spawn(watch_sources(
+            entr.clone(),
+            build_directory.clone(),
+            sources,
+            failed,
+            data.clone(),
+        ));
     }
+
     HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
-            .route("/status", web::get().to(needs_reload))
+            .route("/status", web::get().to(reload_status))
+            .route("/build", web::get().to(build_status))
+            .route("/events", web::get().to(reload_events))
             .service(
                 Files::new("/", &build_directory)
                     .show_files_listing() // Optional: displays a directory listing
@@ -205,3 +582,176 @@ async fn main() -> std::io::Result<()> {
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    // The JSON after "data: " in a rebuild event
+    fn payload(rebuilt: &Rebuilt) -> serde_json::Value {
+        let event = rebuilt.event();
+        let data = event
+            .strip_prefix("event: rebuild\ndata: ")
+            .and_then(|rest| rest.strip_suffix("\n\n"))
+            .expect("a rebuild event");
+        serde_json::from_str(data).expect("the data is JSON")
+    }
+
+    fn event(kind: EventKind, path: &str) -> Event {
+        Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    fn modified(path: &str) -> Event {
+        event(
+            EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Content)),
+            path,
+        )
+    }
+
+    fn sources() -> Sources {
+        Sources {
+            files: HashSet::from([
+                PathBuf::from("/notes/main.mtx"),
+                PathBuf::from("/notes/temps.csv"),
+            ]),
+            directories: HashSet::new(),
+            output: PathBuf::from("/notes/build"),
+        }
+    }
+
+    #[test]
+    fn test_changed_sources() {
+        let watched = sources();
+        let main = vec![PathBuf::from("/notes/main.mtx")];
+
+        // Tracked files count when written or removed, not when only touched
+        assert_eq!(watched.changed(&modified("/notes/main.mtx"), false), main);
+        let removed = event(
+            EventKind::Remove(notify::event::RemoveKind::File),
+            "/notes/main.mtx",
+        );
+        assert_eq!(watched.changed(&removed, false), main);
+        let touched = event(
+            EventKind::Modify(ModifyKind::Metadata(notify::event::MetadataKind::Any)),
+            "/notes/main.mtx",
+        );
+        assert!(watched.changed(&touched, false).is_empty());
+
+        // Other files only count after a failed build, and only when they could be sources
+        assert!(
+            watched
+                .changed(&modified("/notes/lib.mtx"), false)
+                .is_empty()
+        );
+        assert_eq!(
+            watched.changed(&modified("/notes/lib.mtx"), true),
+            vec![PathBuf::from("/notes/lib.mtx")]
+        );
+        assert!(
+            watched
+                .changed(&modified("/notes/main.mtx.swp"), true)
+                .is_empty()
+        );
+        assert!(
+            watched
+                .changed(&modified("/notes/build/data.json"), true)
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_next_change_debounces() {
+        let watched = sources();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        tokio::spawn(async move {
+            // A save writing two files in quick succession, with noise in between
+            tx.send(modified("/notes/main.swp")).await.unwrap();
+            tx.send(modified("/notes/main.mtx")).await.unwrap();
+            tokio::time::sleep(DEBOUNCE / 3).await;
+            tx.send(modified("/notes/temps.csv")).await.unwrap();
+            tx.send(modified("/notes/main.mtx")).await.unwrap();
+            // Long after the burst settled
+            tokio::time::sleep(DEBOUNCE * 3).await;
+            tx.send(modified("/notes/temps.csv")).await.unwrap();
+        });
+
+        assert_eq!(
+            next_change(&mut rx, &watched, false).await,
+            Some(vec![
+                PathBuf::from("/notes/main.mtx"),
+                PathBuf::from("/notes/temps.csv")
+            ])
+        );
+        assert_eq!(
+            next_change(&mut rx, &watched, false).await,
+            Some(vec![PathBuf::from("/notes/temps.csv")])
+        );
+        // The sender is gone
+        assert_eq!(next_change(&mut rx, &watched, false).await, None);
+    }
+
+    // What the overlay shows for a syntax error in `source`
+    fn diagnostic(source: &str) -> Diagnostic {
+        let error = mtx::parser::math::module::parse_source(
+            "notes.mtx",
+            source,
+            false,
+            mtx::ast::module::ParseMode::Module,
+        )
+        .unwrap_err();
+        Diagnostic::from(&BuildError::Load(LoadError::Parse(FileParseError::Syntax(
+            error,
+        ))))
+    }
+
+    #[test]
+    fn test_syntax_error_diagnostic() {
+        let error = diagnostic("a = 1\nb = 2 * * 3\n");
+        assert_eq!(error.file.as_deref(), Some("notes.mtx"));
+        assert_eq!((error.line, error.column), (Some(2), Some(9)));
+        assert_eq!(error.snippet.as_deref(), Some("b = 2 * * 3"));
+
+        // The overlay puts its caret at the column, in characters rather than bytes
+        let error = diagnostic("a = 1\nc = 'é' + * 3\n");
+        assert_eq!((error.line, error.column), (Some(2), Some(11)));
+        assert_eq!(error.snippet.as_deref(), Some("c = 'é' + * 3"));
+    }
+
+    #[test]
+    fn test_rebuild_event() {
+        let ok = Rebuilt {
+            paths: vec![PathBuf::from("notes.mtx"), PathBuf::from("data/temps.csv")],
+            result: Ok(()),
+        };
+        assert_eq!(
+            payload(&ok),
+            serde_json::json!({ "status": "ok", "paths": ["notes.mtx", "data/temps.csv"] })
+        );
+
+        let failed = Rebuilt {
+            paths: vec![PathBuf::from("notes.mtx")],
+            result: Err(Diagnostic {
+                file: Some("notes.mtx".to_string()),
+                line: Some(2),
+                column: Some(5),
+                snippet: Some("a = (1".to_string()),
+                message: "expected `)`, found end of line".to_string(),
+            }),
+        };
+        assert_eq!(
+            payload(&failed),
+            serde_json::json!({
+                "status": "error",
+                "paths": ["notes.mtx"],
+                "error": {
+                    "file": "notes.mtx",
+                    "line": 2,
+                    "column": 5,
+                    "snippet": "a = (1",
+                    "message": "expected `)`, found end of line",
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use nom::{Parser, character::complete::multispace0, sequence::preceded};
+
+use crate::{
+    ast::{Comment, Statement, module::Metadata},
+    parser::{
+        markdown::{Inline, parse_math},
+        math::{
+            error::{ParseError, diagnose, tracked},
+            module::{metadata, parse_statements},
+            statement::parse_statement,
+        },
+    },
+};
+
+// A literate document is markdown prose with mtx in between:
+//
+// ---
+// title: Falling bodies
+// ---
+// The height after $t$ seconds is
+// ```mtx
+// h(t) = h_0 - g t^2 / 2
+// ```
+// $$
+// h(2)
+// $$
+//
+// Prose becomes multi line comments, so the page shows it exactly like /* */ prose.
+
+// Lines with the offset they start at
+fn lines(source: &str) -> Vec<(usize, &str)> {
+    let mut offset = 0;
+    source
+        .split_inclusive('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len();
+            (start, line)
+        })
+        .collect()
+}
+
+// key: value lines between --- lines at the very top, and the line after them
+fn front_matter(lines: &[(usize, &str)]) -> (Metadata, usize) {
+    if lines.first().map(|(_, line)| line.trim_end()) != Some("---") {
+        return (Metadata::default(), 0);
+    }
+    match lines[1..]
+        .iter()
+        .position(|(_, line)| line.trim_end() == "---")
+    {
+        Some(end) => (
+            metadata(
+                lines[1..=end]
+                    .iter()
+                    .filter_map(|(_, line)| line.split_once(':')),
+            ),
+            end + 2,
+        ),
+        // Just a horizontal rule
+        None => (Metadata::default(), 0),
+    }
+}
+
+// The statements of a block at source[start..end], errors point into the whole document
+fn block(file: &str, source: &str, start: usize, end: usize) -> Result<Vec<Statement>, ParseError> {
+    let text = &source[start..end];
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    // Everything up to the end of the block, so what's left after it is a suffix
+    let upto = &source[..end];
+    match tracked(text, |input| {
+        preceded(multispace0, parse_statements).parse(input)
+    }) {
+        (Ok((rest, statements)), _) if rest.trim().is_empty() => Ok(statements),
+        (_, furthest) => Err(diagnose(file, upto, start + furthest)),
+    }
+}
+
+// Inline $...$ math in a line of prose has to be valid mtx, code spans are left alone
+fn check_math(file: &str, source: &str, at: usize, line: &str) -> Result<(), ParseError> {
+    let mut code = false;
+    let mut i = 0;
+    while let Some(c) = line[i..].chars().next() {
+        match c {
+            '`' => code = !code,
+            '$' if !code => {
+                if let Ok((after, Inline::Math(text))) = parse_math(&line[i..]) {
+                    let start = at + i + 1;
+                    let end = start + text.len();
+                    let (parsed, furthest) = tracked(&text, parse_statement);
+                    if !matches!(parsed, Ok((rest, _)) if rest.trim().is_empty()) {
+                        // Diagnosed as if the line ended at the closing $, the snippet is the whole line
+                        let mut error = diagnose(file, &source[..end], start + furthest);
+                        error.snippet = line.trim_end().to_string();
+                        return Err(error);
+                    }
+                    i = line.len() - after.len();
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    Ok(())
+}
+
+fn flush(prose: &mut String, statements: &mut Vec<Statement>) {
+    let text = std::mem::take(prose);
+    if !text.trim().is_empty() {
+        statements.push(Statement::Comment(Comment::Multi(text.trim().to_string())));
+    }
+}
+
+// Parse a literate document, ```mtx fences and $$ blocks hold statements and the rest is prose
+pub fn parse_literate(file: &str, source: &str) -> Result<(Metadata, Vec<Statement>), ParseError> {
+    let lines = lines(source);
+    let (metadata, mut i) = front_matter(&lines);
+
+    let mut statements = Vec::new();
+    let mut prose = String::new();
+    // Inside a fenced block of another language, which is prose
+    let mut fenced = false;
+
+    while let Some(&(at, line)) = lines.get(i) {
+        let trimmed = line.trim();
+        i += 1;
+
+        if fenced {
+            fenced = !trimmed.starts_with("```");
+            prose.push_str(line);
+            continue;
+        }
+
+        let close = match trimmed {
+            "```mtx" => "```",
+            "$$" => "$$",
+            // $$ h(2) $$ on one line
+            _ if trimmed.len() > 4 && trimmed.starts_with("$$") && trimmed.ends_with("$$") => {
+                let start = at + line.find("$$").unwrap_or(0) + 2;
+                let end = at + line.rfind("$$").unwrap_or(0);
+                flush(&mut prose, &mut statements);
+                statements.extend(block(file, source, start, end)?);
+                continue;
+            }
+            _ => {
+                fenced = trimmed.starts_with("```");
+                if !fenced {
+                    check_math(file, source, at, line)?;
+                }
+                prose.push_str(line);
+                continue;
+            }
+        };
+
+        let Some(end) = lines[i..].iter().position(|(_, l)| l.trim() == close) else {
+            return Err(ParseError::at(
+                file,
+                source,
+                at,
+                format!("`{}` block is never closed", trimmed),
+            ));
+        };
+        let start = lines
+            .get(i)
+            .map(|(start, _)| *start)
+            .unwrap_or(source.len());
+        let (end_at, _) = lines[i + end];
+        flush(&mut prose, &mut statements);
+        statements.extend(block(file, source, start, end_at)?);
+        i += end + 1;
+    }
+    flush(&mut prose, &mut statements);
+
+    Ok((metadata, statements))
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    const DOCUMENT: &str = "---
+title: Falling bodies
+author: piderking
+---
+# Drop
+
+The height after $t$ seconds is
+
+```mtx
+g = 9.8
+h(t) = 20 - g * t^2 / 2
+```
+
+```python
+x = $y$
+```
+
+$$ h(1) $$
+Done.
+";
+
+    #[test]
+    fn test_parse_literate() {
+        let (metadata, statements) = parse_literate("drop.md", DOCUMENT).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Falling bodies"));
+        assert_eq!(metadata.author.as_deref(), Some("piderking"));
+
+        let kinds = statements
+            .iter()
+            .map(|s| match s {
+                Statement::Comment(_) => "prose",
+                Statement::Definition(_) => "definition",
+                Statement::Expression(_) => "expression",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                "prose",
+                "definition",
+                "definition",
+                "prose",
+                "expression",
+                "prose"
+            ]
+        );
+        match &statements[3] {
+            Statement::Comment(Comment::Multi(text)) => assert!(text.starts_with("```python")),
+            _ => panic!("expected prose"),
+        }
+    }
+
+    #[test]
+    fn test_parse_literate_errors() {
+        let error = parse_literate("drop.md", "Intro\n\n```mtx\nx = 1\ny = (x\n```\n").unwrap_err();
+        assert_eq!((error.line, error.column), (5, 7));
+        assert_eq!(error.message, "expected `)`, found end of line");
+
+        let error = parse_literate("drop.md", "Intro\nso $x +$ is\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.snippet, "so $x +$ is");
+
+        let error = parse_literate("drop.md", "$$\nx\n").unwrap_err();
+        assert_eq!(error.message, "`$$` block is never closed");
+    }
+}
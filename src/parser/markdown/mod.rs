@@ -2,24 +2,45 @@ use nom::{
     IResult, Parser,
     branch::alt,
     bytes::complete::{tag, take_until, take_while, take_while1},
-    character::complete::{char, digit1, line_ending, multispace0, not_line_ending, space0},
+    character::complete::{char, line_ending, multispace0, not_line_ending, one_of, space0},
     combinator::{map, opt, recognize},
     multi::{many0, many1, separated_list0},
     sequence::{delimited, pair, preceded, terminated, tuple},
 };
 
+use std::path::{Component, Path, PathBuf};
+
+use crate::{ast::Statement, parser::math::statement::parse_statement};
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum MarkdownElement {
     Heading(u8, String),
     Paragraph(Vec<Inline>),
     CodeBlock(String, String),
-    UnorderedList(Vec<String>),
-    OrderedList(Vec<String>),
-    Blockquote(String),
+    UnorderedList(Vec<ListItem>),
+    OrderedList(Vec<ListItem>),
+    Blockquote(Vec<Inline>),
+    // Column alignments, the header cells and the rows of cells
+    Table(Vec<Alignment>, Vec<Vec<Inline>>, Vec<Vec<Vec<Inline>>>),
     HorizontalRule,
     LineBreak,
 }
 
+// A list item, nested lists are its children
+#[derive(Debug, PartialEq, Clone)]
+pub struct ListItem {
+    pub text: Vec<Inline>,
+    pub children: Vec<MarkdownElement>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Inline {
     Text(String),
@@ -27,6 +48,10 @@ pub enum Inline {
     Italic(String),
     Code(String),
     Link(String, String),
+    // ![alt](src)
+    Image(String, String),
+    // mtx source written between $...$
+    Math(String),
 }
 
 // Parse heading (# Header)
@@ -58,37 +83,70 @@ fn parse_code_block(input: &str) -> IResult<&str, MarkdownElement> {
     ))
 }
 
-// Parse unordered list item (- item or * item)
-fn parse_unordered_item(input: &str) -> IResult<&str, String> {
-    let (input, _) = alt((tag("- "), tag("* "))).parse(input)?;
-    let (input, text) = not_line_ending.parse(input)?;
-    let (input, _) = opt(line_ending).parse(input)?;
+// Indentation (a tab counts as 4), whether the list is ordered and the text of a list item line
+fn list_marker(line: &str) -> Option<(usize, bool, &str)> {
+    let content = line.trim_start_matches([' ', '\t']);
+    let indent = line[..line.len() - content.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
 
-    Ok((input, text.to_string()))
+    if let Some(text) = content.strip_prefix("- ").or(content.strip_prefix("* ")) {
+        return Some((indent, false, text));
+    }
+    let digits = content.len()
+        - content
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    match content[digits..].strip_prefix(". ") {
+        Some(text) if digits > 0 => Some((indent, true, text)),
+        _ => None,
+    }
 }
 
-// Parse unordered list
-fn parse_unordered_list(input: &str) -> IResult<&str, MarkdownElement> {
-    let (input, items) = many1(parse_unordered_item).parse(input)?;
+// Items at `indent`, more deeply indented items belong to the item above them
+fn parse_list_at(mut input: &str, indent: usize, ordered: bool) -> (&str, MarkdownElement) {
+    let mut items: Vec<ListItem> = Vec::new();
 
-    Ok((input, MarkdownElement::UnorderedList(items)))
-}
-
-// Parse ordered list item (1. item)
-fn parse_ordered_item(input: &str) -> IResult<&str, String> {
-    let (input, _) = digit1.parse(input)?;
-    let (input, _) = tag(". ").parse(input)?;
-    let (input, text) = not_line_ending.parse(input)?;
-    let (input, _) = opt(line_ending).parse(input)?;
+    loop {
+        let (line, rest) = input.split_once('\n').unwrap_or((input, ""));
+        match list_marker(line) {
+            Some((i, o, text)) if i == indent && o == ordered => {
+                items.push(ListItem {
+                    text: inlines(text.trim_end()),
+                    children: Vec::new(),
+                });
+                input = rest;
+            }
+            Some((i, o, _)) if i > indent => {
+                let (rest, child) = parse_list_at(input, i, o);
+                if let Some(item) = items.last_mut() {
+                    item.children.push(child);
+                }
+                input = rest;
+            }
+            _ => break,
+        }
+    }
 
-    Ok((input, text.to_string()))
+    let list = if ordered {
+        MarkdownElement::OrderedList(items)
+    } else {
+        MarkdownElement::UnorderedList(items)
+    };
+    (input, list)
 }
 
-// Parse ordered list
-fn parse_ordered_list(input: &str) -> IResult<&str, MarkdownElement> {
-    let (input, items) = many1(parse_ordered_item).parse(input)?;
-
-    Ok((input, MarkdownElement::OrderedList(items)))
+// Parse a list (- item, * item or 1. item), indented items are nested lists
+fn parse_list(input: &str) -> IResult<&str, MarkdownElement> {
+    let line = input.split('\n').next().unwrap_or_default();
+    let Some((indent, ordered, _)) = list_marker(line) else {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Tag,
+        )));
+    };
+    Ok(parse_list_at(input, indent, ordered))
 }
 
 // Parse blockquote (> text)
@@ -103,7 +161,91 @@ fn parse_blockquote_line(input: &str) -> IResult<&str, String> {
 fn parse_blockquote(input: &str) -> IResult<&str, MarkdownElement> {
     let (input, lines) = many1(parse_blockquote_line).parse(input)?;
 
-    Ok((input, MarkdownElement::Blockquote(lines.join("\n"))))
+    Ok((
+        input,
+        MarkdownElement::Blockquote(inlines(&lines.join("\n"))),
+    ))
+}
+
+// Cells of a table row, the outer pipes are optional and \| is a pipe inside a cell
+fn table_cells(line: &str) -> Option<Vec<String>> {
+    let line = line.trim();
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                cells.last_mut()?.push('|');
+            }
+            '|' => cells.push(String::new()),
+            c => cells.last_mut()?.push(c),
+        }
+    }
+    if cells.len() == 1 {
+        return None;
+    }
+    if line.starts_with('|') {
+        cells.remove(0);
+    }
+    if line.ends_with('|') && !line.ends_with("\\|") {
+        cells.pop();
+    }
+    Some(cells.iter().map(|cell| cell.trim().to_string()).collect())
+}
+
+// A delimiter row cell, :-- left, :-: center, --: right
+fn alignment(cell: &str) -> Option<Alignment> {
+    if !cell.contains('-') || !cell.chars().all(|c| c == '-' || c == ':') {
+        return None;
+    }
+    Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Alignment::Center,
+        (true, false) => Alignment::Left,
+        (false, true) => Alignment::Right,
+        (false, false) => Alignment::None,
+    })
+}
+
+// Parse a GFM table, a header row, a delimiter row and rows until a line without a pipe
+fn parse_table(input: &str) -> IResult<&str, MarkdownElement> {
+    let fail = || nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Verify));
+    let (header, rest) = input.split_once('\n').ok_or_else(fail)?;
+    let (delimiter, mut rest) = rest.split_once('\n').unwrap_or((rest, ""));
+
+    let header = table_cells(header).ok_or_else(fail)?;
+    let alignments = table_cells(delimiter)
+        .ok_or_else(fail)?
+        .iter()
+        .map(|cell| alignment(cell))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(fail)?;
+    if alignments.len() != header.len() {
+        return Err(fail());
+    }
+
+    // Rows are cut or padded to the header's width
+    let row = |cells: Vec<String>| {
+        (0..header.len())
+            .map(|i| cells.get(i).map(|cell| inlines(cell)).unwrap_or_default())
+            .collect::<Vec<_>>()
+    };
+    let mut rows = Vec::new();
+    while let Some((line, next)) = rest
+        .split_once('\n')
+        .or((!rest.is_empty()).then_some((rest, "")))
+    {
+        match table_cells(line) {
+            Some(cells) => rows.push(row(cells)),
+            None => break,
+        }
+        rest = next;
+    }
+
+    Ok((
+        rest,
+        MarkdownElement::Table(alignments, row(header.clone()), rows),
+    ))
 }
 
 // Parse horizontal rule (---, ***, ___)
@@ -136,6 +278,15 @@ fn parse_inline_code(input: &str) -> IResult<&str, Inline> {
     Ok((input, Inline::Code(text.to_string())))
 }
 
+// Parse image (![alt](src))
+fn parse_image(input: &str) -> IResult<&str, Inline> {
+    let (input, _) = char('!').parse(input)?;
+    let (input, text) = delimited(char('['), take_until("]"), char(']')).parse(input)?;
+    let (input, src) = delimited(char('('), take_until(")"), char(')')).parse(input)?;
+
+    Ok((input, Inline::Image(text.to_string(), src.to_string())))
+}
+
 // Parse link ([text](url))
 fn parse_link(input: &str) -> IResult<&str, Inline> {
     let (input, text) = delimited(char('['), take_until("]"), char(']')).parse(input)?;
@@ -144,10 +295,41 @@ fn parse_link(input: &str) -> IResult<&str, Inline> {
     Ok((input, Inline::Link(text.to_string(), url.to_string())))
 }
 
+// Parse inline math ($x^2$). Like pandoc, the opening $ can't be followed by a space and the
+// closing one can't follow a space or come before a digit, so "$5 and $6" stays text.
+pub(crate) fn parse_math(input: &str) -> IResult<&str, Inline> {
+    let fail = || nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Char));
+    let (rest, _) = char('$').parse(input)?;
+    let end = rest.find(['$', '\n']).ok_or_else(fail)?;
+    let (text, after) = (&rest[..end], &rest[end..]);
+
+    if text.is_empty()
+        || !after.starts_with('$')
+        || text.starts_with(char::is_whitespace)
+        || text.ends_with(char::is_whitespace)
+        || after[1..].starts_with(|c: char| c.is_ascii_digit())
+    {
+        return Err(fail());
+    }
+    Ok((&after[1..], Inline::Math(text.to_string())))
+}
+
+// The statement written between $...$, None when it isn't valid mtx
+pub fn math_statement(text: &str) -> Option<Statement> {
+    match parse_statement(text) {
+        Ok((rest, statement)) if rest.trim().is_empty() => Some(statement),
+        _ => None,
+    }
+}
+
 // Parse plain text
 fn parse_text(input: &str) -> IResult<&str, Inline> {
-    let (input, text) =
-        take_while1(|c: char| c != '*' && c != '`' && c != '[' && c != '\n').parse(input)?;
+    let (input, text) = alt((
+        take_while1(|c: char| !"*`[$!\n".contains(c)),
+        // A character that doesn't start anything (a lone * or a $ that doesn't open math)
+        recognize(one_of("*`[$!")),
+    ))
+    .parse(input)?;
 
     Ok((input, Inline::Text(text.to_string())))
 }
@@ -157,7 +339,9 @@ fn parse_inline(input: &str) -> IResult<&str, Inline> {
     alt((
         parse_bold,
         parse_inline_code,
+        parse_image,
         parse_link,
+        parse_math,
         parse_italic,
         parse_text,
     ))
@@ -173,13 +357,24 @@ fn parse_paragraph(input: &str) -> IResult<&str, MarkdownElement> {
         return Ok((input, MarkdownElement::LineBreak));
     }
 
-    // Try to parse inline elements, but if it fails, just treat as plain text
-    let inlines = match many1(parse_inline).parse(line) {
-        Ok((_, inlines)) => inlines,
-        Err(_) => vec![Inline::Text(line.to_string())],
-    };
+    Ok((input, MarkdownElement::Paragraph(inlines(line))))
+}
 
-    Ok((input, MarkdownElement::Paragraph(inlines)))
+// Inline elements of some text, lines are kept apart by a newline
+fn inlines(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            inlines.push(Inline::Text("\n".to_string()));
+        }
+        // Try to parse inline elements, but if it fails, just treat as plain text
+        match many1(parse_inline).parse(line) {
+            Ok((_, parsed)) => inlines.extend(parsed),
+            Err(_) if line.is_empty() => {}
+            Err(_) => inlines.push(Inline::Text(line.to_string())),
+        }
+    }
+    inlines
 }
 
 // Parse any markdown element
@@ -188,9 +383,9 @@ fn parse_element(input: &str) -> IResult<&str, MarkdownElement> {
         parse_code_block,
         parse_heading,
         parse_horizontal_rule,
-        parse_unordered_list,
-        parse_ordered_list,
+        parse_list,
         parse_blockquote,
+        parse_table,
         parse_paragraph,
     ))
     .parse(input)
@@ -228,6 +423,103 @@ pub fn parse_markdown(input: &str) -> IResult<&str, Vec<MarkdownElement>> {
     Ok((remaining, elements))
 }
 
+// Prose written inside a /* */ comment. The comment's own indentation is removed, what's left
+// nests lists. The first line starts right after the /* so it's left out of the common indent.
+pub fn parse_prose(text: &str) -> Vec<MarkdownElement> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let indent = lines
+        .iter()
+        .skip(1)
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let text = lines
+        .iter()
+        .enumerate()
+        .map(|(i, l)| match i {
+            0 => l.trim(),
+            _ => l.get(indent..).unwrap_or("").trim_end(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match parse_markdown(&text) {
+        Ok((_, elements)) => elements,
+        Err(_) => vec![MarkdownElement::Paragraph(vec![Inline::Text(text)])],
+    }
+}
+
+// Image sources in prose from another directory (a frame) with that directory in front,
+// so they stay relative to the entry file like the rest of the page
+pub fn rebase_images(text: &str, base: &Path) -> String {
+    let mut rebased = String::new();
+    let mut rest = text;
+    while let Some(at) = rest.find("![") {
+        rebased.push_str(&rest[..at]);
+        match parse_image(&rest[at..]) {
+            Ok((after, Inline::Image(alt, src)))
+                if !src.contains("://") && !src.starts_with("data:") && !src.starts_with('/') =>
+            {
+                // sub/../img is img, what climbs out of the base stays for the build to refuse
+                let mut path = PathBuf::new();
+                for component in base.join(&src).components() {
+                    match component {
+                        Component::CurDir => {}
+                        Component::ParentDir if path.file_name().is_some() => {
+                            path.pop();
+                        }
+                        component => path.push(component),
+                    }
+                }
+                rebased.push_str(&format!("![{}]({})", alt, path.to_string_lossy()));
+                rest = after;
+            }
+            _ => {
+                rebased.push_str("![");
+                rest = &rest[at + 2..];
+            }
+        }
+    }
+    rebased.push_str(rest);
+    rebased
+}
+
+fn images(inlines: &[Inline]) -> Vec<&str> {
+    inlines
+        .iter()
+        .filter_map(|inline| match inline {
+            Inline::Image(_, src) => Some(src.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+impl MarkdownElement {
+    // Sources of the images shown in this element
+    pub fn images(&self) -> Vec<&str> {
+        match self {
+            MarkdownElement::Paragraph(inlines) | MarkdownElement::Blockquote(inlines) => {
+                images(inlines)
+            }
+            MarkdownElement::UnorderedList(items) | MarkdownElement::OrderedList(items) => items
+                .iter()
+                .flat_map(|item| {
+                    images(&item.text)
+                        .into_iter()
+                        .chain(item.children.iter().flat_map(|child| child.images()))
+                })
+                .collect(),
+            MarkdownElement::Table(_, header, rows) => header
+                .iter()
+                .chain(rows.iter().flatten())
+                .flat_map(|cell| images(cell))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,18 +580,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_markdown_math() {
+        assert_eq!(
+            parse_math.parse("$x^2 + 1$ is"),
+            Ok((" is", Inline::Math("x^2 + 1".to_string())))
+        );
+        assert!(parse_math.parse("$5 and $6").is_err());
+        assert!(parse_math.parse("$x $").is_err());
+
+        let (_, inlines) = many1(parse_inline).parse("costs $5, or $f(2)$").unwrap();
+        assert_eq!(inlines.last(), Some(&Inline::Math("f(2)".to_string())));
+        assert!(math_statement("f(x) = x^2").is_some());
+        assert!(math_statement("x +").is_none());
+    }
+
+    fn item(text: &str) -> ListItem {
+        ListItem {
+            text: vec![Inline::Text(text.to_string())],
+            children: Vec::new(),
+        }
+    }
+
     #[test]
     fn test_parse_markdown_unordered_list() {
         let input = "- Item 1\n- Item 2\n- Item 3\n";
-        let result = parse_unordered_list.parse(input);
+        let result = parse_list.parse(input);
         assert_eq!(
             result,
             Ok((
                 "",
                 MarkdownElement::UnorderedList(vec![
-                    "Item 1".to_string(),
-                    "Item 2".to_string(),
-                    "Item 3".to_string()
+                    item("Item 1"),
+                    item("Item 2"),
+                    item("Item 3")
                 ])
             ))
         );
@@ -308,20 +622,89 @@ mod tests {
     #[test]
     fn test_parse_markdown_ordered_list() {
         let input = "1. First\n2. Second\n3. Third\n";
-        let result = parse_ordered_list.parse(input);
+        let result = parse_list.parse(input);
         assert_eq!(
             result,
             Ok((
                 "",
-                MarkdownElement::OrderedList(vec![
-                    "First".to_string(),
-                    "Second".to_string(),
-                    "Third".to_string()
-                ])
+                MarkdownElement::OrderedList(vec![item("First"), item("Second"), item("Third")])
             ))
         );
     }
 
+    #[test]
+    fn test_parse_markdown_nested_list() {
+        let input = "- **Fruit**\n  1. Apple\n  2. Pear\n- Bread\n\nAfter";
+        let (rest, list) = parse_list.parse(input).unwrap();
+        assert_eq!(rest, "\nAfter");
+        let MarkdownElement::UnorderedList(items) = list else {
+            panic!("expected an unordered list");
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].text, vec![Inline::Bold("Fruit".to_string())]);
+        assert_eq!(
+            items[0].children,
+            vec![MarkdownElement::OrderedList(vec![
+                item("Apple"),
+                item("Pear")
+            ])]
+        );
+
+        // A comment's indentation isn't nesting
+        let prose = parse_prose(" Shopping\n    - a\n      - b\n    - c\n");
+        assert!(matches!(&prose[1], MarkdownElement::UnorderedList(items) if items.len() == 2));
+    }
+
+    #[test]
+    fn test_parse_markdown_table() {
+        let input = "| x | f(x) | note |\n|:--|:-:|--:|\n| 1 | **2** |\n| 2 | 4 | a | b |\n\nAfter";
+        let (rest, table) = parse_table.parse(input).unwrap();
+        assert_eq!(rest, "\nAfter");
+        let MarkdownElement::Table(alignments, header, rows) = table else {
+            panic!("expected a table");
+        };
+        assert_eq!(
+            alignments,
+            vec![Alignment::Left, Alignment::Center, Alignment::Right]
+        );
+        assert_eq!(header[1], vec![Inline::Text("f(x)".to_string())]);
+        // Short rows are padded and long ones cut
+        assert_eq!(rows[0][1], vec![Inline::Bold("2".to_string())]);
+        assert_eq!(rows[0][2], vec![]);
+        assert_eq!(rows[1].len(), 3);
+
+        assert!(parse_table.parse("a | b\nnot a delimiter\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_markdown_table_escaped_pipe() {
+        let input = "| x | \\|x\\| |\n|---|---|\n| a \\| b | c |\n";
+        let (_, table) = parse_table.parse(input).unwrap();
+        let MarkdownElement::Table(_, header, rows) = table else {
+            panic!("expected a table");
+        };
+        assert_eq!(header[1], vec![Inline::Text("|x|".to_string())]);
+        assert_eq!(rows[0].len(), 2);
+        assert_eq!(rows[0][0], vec![Inline::Text("a | b".to_string())]);
+        assert_eq!(rows[0][1], vec![Inline::Text("c".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_markdown_image() {
+        let (_, element) = parse_paragraph
+            .parse("See ![plot](img/plot.png)!\n")
+            .unwrap();
+        assert_eq!(
+            element,
+            MarkdownElement::Paragraph(vec![
+                Inline::Text("See ".to_string()),
+                Inline::Image("plot".to_string(), "img/plot.png".to_string()),
+                Inline::Text("!".to_string()),
+            ])
+        );
+        assert_eq!(element.images(), vec!["img/plot.png"]);
+    }
+
     #[test]
     fn test_parse_markdown_blockquote() {
         let input = "> Quote line 1\n> Quote line 2\n";
@@ -330,7 +713,11 @@ mod tests {
             result,
             Ok((
                 "",
-                MarkdownElement::Blockquote("Quote line 1\nQuote line 2".to_string())
+                MarkdownElement::Blockquote(vec![
+                    Inline::Text("Quote line 1".to_string()),
+                    Inline::Text("\n".to_string()),
+                    Inline::Text("Quote line 2".to_string())
+                ])
             ))
         );
     }
@@ -379,6 +766,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_rebase_images() {
+        let base = Path::new("chapter");
+        assert_eq!(
+            rebase_images("See ![plot](img/plot.png) and ![a](../a.png).", base),
+            "See ![plot](chapter/img/plot.png) and ![a](a.png)."
+        );
+        // Links and images elsewhere are left alone
+        assert_eq!(
+            rebase_images("![w](https://x.org/w.png) [doc](img/doc.pdf) ![", base),
+            "![w](https://x.org/w.png) [doc](img/doc.pdf) !["
+        );
+    }
+
     #[test]
     fn test_parse_markdown_full_document() {
         let markdown = r#"# Title
@@ -0,0 +1,286 @@
+use std::{cell::Cell, fmt};
+
+use nom::{
+    IResult,
+    error::{ErrorKind, FromExternalError},
+};
+
+// A parse failure pointing at the offending line and column (both 1-based)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    // The whole source line the error is on
+    pub snippet: String,
+    pub message: String,
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    // Error at a byte offset, the snippet is the line it falls on
+    pub fn at(file: &str, source: &str, offset: usize, message: String) -> Self {
+        let line_start = source[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = source[offset..]
+            .find('\n')
+            .map(|i| offset + i)
+            .unwrap_or(source.len());
+        let (line, column) = locate(source, offset);
+        ParseError {
+            file: file.to_string(),
+            line,
+            column,
+            snippet: source[line_start..line_end].trim_end().to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    // error: expected `)`, found end of line
+    //  --> main.mtx:3:9
+    //   |
+    // 3 | f(x) = (x
+    //   |          ^
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(
+            f,
+            "{} --> {}:{}:{}",
+            gutter, self.file, self.line, self.column
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.snippet)?;
+        write!(f, "{} | {}^", gutter, " ".repeat(self.column - 1))
+    }
+}
+
+// Line and column of a byte offset into the source
+pub fn locate(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+// Error type of the mtx parsers. alt, opt and many0 throw failed branches away,
+// so every failure also records how far into the input it got: the furthest
+// one is where the source stops making sense.
+#[derive(Debug, PartialEq)]
+pub struct Failure<'a> {
+    pub input: &'a str,
+    pub kind: ErrorKind,
+}
+
+pub type PResult<'a, O> = IResult<&'a str, O, Failure<'a>>;
+
+thread_local! {
+    // Shortest input left at any failure since `tracked` started
+    static FURTHEST: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+impl<'a> Failure<'a> {
+    pub fn new(input: &'a str, kind: ErrorKind) -> Self {
+        FURTHEST.with(|furthest| furthest.set(furthest.get().min(input.len())));
+        Failure { input, kind }
+    }
+
+    // The one of two that got further
+    fn furthest(self, other: Self) -> Self {
+        if other.input.len() < self.input.len() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<'a> nom::error::ParseError<&'a str> for Failure<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        Failure::new(input, kind)
+    }
+
+    fn append(input: &'a str, kind: ErrorKind, other: Self) -> Self {
+        Failure::new(input, kind).furthest(other)
+    }
+
+    fn or(self, other: Self) -> Self {
+        self.furthest(other)
+    }
+}
+
+impl<'a, E> FromExternalError<&'a str, E> for Failure<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _: E) -> Self {
+        Failure::new(input, kind)
+    }
+}
+
+// Run a parser over `input`, also handing back the byte offset of the furthest failure
+pub fn tracked<'a, O>(
+    input: &'a str,
+    parser: impl FnOnce(&'a str) -> PResult<'a, O>,
+) -> (PResult<'a, O>, usize) {
+    let outer = FURTHEST.with(|furthest| furthest.replace(usize::MAX));
+    let result = parser(input);
+    let shortest = FURTHEST.with(|furthest| furthest.replace(outer));
+    let offset = match &result {
+        Ok((rest, _)) => input.len() - rest.len().min(shortest),
+        Err(_) => input.len() - shortest.min(input.len()),
+    };
+    (result, offset)
+}
+
+// The next token for "found ...", a word, a number or a single character
+fn found(rest: &str) -> String {
+    let line = rest.lines().next().unwrap_or("");
+    match line.chars().next() {
+        None => "end of line".to_string(),
+        Some(c) if c.is_alphanumeric() => format!(
+            "`{}`",
+            line.chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '.')
+                .collect::<String>()
+        ),
+        Some(c) => format!("`{}`", c),
+    }
+}
+
+// The bracket still open at the end of `text`
+fn unclosed(text: &str) -> Option<char> {
+    let mut open = Vec::new();
+    for c in text.chars() {
+        match c {
+            '(' => open.push(')'),
+            '[' => open.push(']'),
+            '{' => open.push('}'),
+            ')' | ']' | '}' => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    open.pop()
+}
+
+// An operator or opening bracket, the expression goes on after it
+fn continues(c: char) -> bool {
+    "+-*/^=,<>([".contains(c)
+}
+
+// The diagnostic for source that failed to parse, `at` is the furthest failure
+// (from `tracked`). Line breaks skipped looking for the rest of an expression
+// aren't part of it, `y = 2 +` and `y = (2` end on their own line.
+pub fn diagnose(file: &str, source: &str, at: usize) -> ParseError {
+    let written = source[..at].trim_end();
+    let unfinished = written.ends_with(continues) || unclosed(written).is_some();
+    let mut at = if source[written.len()..at].contains('\n') && unfinished {
+        written.len()
+    } else {
+        source.len() - source[at..].trim_start().len()
+    };
+
+    let line_start = source[..at].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = source[at..]
+        .find('\n')
+        .map(|i| at + i)
+        .unwrap_or(source.len());
+    let before = source[line_start..at].trim_end();
+    let rest = &source[at..line_end];
+    let found = found(rest.trim_end());
+
+    let message = match (before.chars().last(), unclosed(&source[..at])) {
+        (None, _) => format!("expected a statement, found {}", found),
+        (Some(op), _) if continues(op) => {
+            format!("expected an expression after `{}`, found {}", op, found)
+        }
+        (_, Some(close)) if rest.trim().is_empty() => {
+            at = line_start + source[line_start..line_end].trim_end().len();
+            format!("expected `{}`, found end of line", close)
+        }
+        (_, Some(close)) => format!("expected an operator or `{}`, found {}", close, found),
+        (_, None) if rest.starts_with(')') => "unmatched `)`".to_string(),
+        (_, None) => format!("expected an operator or end of line, found {}", found),
+    };
+
+    ParseError::at(file, source, at, message)
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{ast::module::ParseMode, parser::math::module::parse_source};
+
+    fn report(source: &str) -> ParseError {
+        parse_source("main.mtx", source, true, ParseMode::Frame).unwrap_err()
+    }
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(locate("ab\ncd", 0), (1, 1));
+        assert_eq!(locate("ab\ncd", 4), (2, 2));
+    }
+
+    #[test]
+    fn test_diagnose_unclosed_paren() {
+        let error = report("x = 1\nf(x) = (x + 1\n");
+        assert_eq!((error.line, error.column), (2, 14));
+        assert_eq!(error.message, "expected `)`, found end of line");
+        assert_eq!(error.snippet, "f(x) = (x + 1");
+    }
+
+    #[test]
+    fn test_diagnose_unclosed_list() {
+        let error = report("y = [1, 2\n");
+        assert_eq!((error.line, error.column), (1, 10));
+        assert_eq!(error.message, "expected `]`, found end of line");
+    }
+
+    #[test]
+    fn test_diagnose_dangling_operator() {
+        let error = report("y = 2 +\n");
+        assert_eq!((error.line, error.column), (1, 8));
+        assert_eq!(
+            error.message,
+            "expected an expression after `+`, found end of line"
+        );
+    }
+
+    #[test]
+    fn test_diagnose_inside_call() {
+        let error = report("y = sin(x y)\n");
+        assert_eq!((error.line, error.column), (1, 11));
+        assert_eq!(error.message, "expected an operator or `)`, found `y`");
+
+        let error = report("x = 1\nprint(diff(x^2 +, x))\n");
+        assert_eq!((error.line, error.column), (2, 17));
+        assert_eq!(error.message, "expected an expression after `+`, found `,`");
+    }
+
+    #[test]
+    fn test_diagnose_two_expressions_on_a_line() {
+        let error = report("a = 1 2\n");
+        assert_eq!((error.line, error.column), (1, 7));
+        assert_eq!(
+            error.message,
+            "expected an operator or end of line, found `2`"
+        );
+    }
+
+    #[test]
+    fn test_diagnose_bad_statement() {
+        let error = report("x = 1\n\n) = 2\n");
+        assert_eq!((error.line, error.column), (3, 1));
+        assert_eq!(error.message, "expected a statement, found `)`");
+    }
+
+    #[test]
+    fn test_display_caret() {
+        assert_eq!(
+            report("y = 2 $ 3\n").to_string(),
+            "error: expected an operator or end of line, found `$`\n  --> main.mtx:1:7\n  |\n1 | y = 2 $ 3\n  |       ^"
+        );
+    }
+}
@@ -1,27 +1,32 @@
 use std::path::Path;
 
 use nom::{
-    IResult, Parser,
+    Parser,
     branch::alt,
     bytes::complete::tag,
     character::complete::{alpha0, alpha1, char, digit1, multispace0, satisfy},
-    combinator::{all_consuming, map, map_res, peek, recognize},
+    combinator::{all_consuming, map, map_res, not, opt, peek, recognize},
     error::{Error, ErrorKind, ParseError, context},
     multi::{many_till, many0, separated_list0, separated_list1},
     number::complete::f32,
-    sequence::{delimited, pair, preceded, separated_pair, tuple},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
 };
 
 use crate::{
     ast::{
         base::Ident,
         expressions::Expression,
-        opperations::{Add, Exp, Index, Multi, Opperation, Root},
+        opperations::{
+            Add, Compare, Div, Exp, Index, Logic, Multi, Neg, Not, Opperation, Piecewise, Root,
+            Series, Sub,
+        },
         system_func::System,
     },
+    builtins,
     parser::math::{
-        value::{parse_float_value, parse_value},
-        whitespace::ws,
+        error::{Failure, PResult},
+        value::{parse_number_value, parse_value},
+        whitespace::{ws, ws_inline},
     },
     symbols::Symbols,
 };
@@ -31,7 +36,7 @@ use crate::{
 // Ident(Vec<Variable>)
 //
 
-pub fn parse_ident(input: &str) -> IResult<&str, Ident> {
+pub fn parse_ident(input: &str) -> PResult<'_, Ident> {
     log::debug!(">>> parse_ident {:?}", input);
 
     match alpha1(input) {
@@ -41,13 +46,13 @@ pub fn parse_ident(input: &str) -> IResult<&str, Ident> {
 }
 
 // Exponentiation (highest precedence, right-associative)
-pub fn parse_exponentiation(input: &str) -> IResult<&str, Expression> {
+pub fn parse_exponentiation(input: &str) -> PResult<'_, Expression> {
     log::debug!(">>> parse_exponentiation {:?}", input);
 
     let (input, base) = parse_atom(input)?;
 
-    // Check if there's a ^ operator
-    let result = preceded(ws(tag("^")), parse_exponentiation).parse(input);
+    // Check if there's a ^ operator (the exponent may be negated: 2^-1)
+    let result = preceded(ws(tag("^")), parse_unary).parse(input);
 
     match result {
         Ok((input, exponent)) => {
@@ -64,19 +69,19 @@ pub fn parse_exponentiation(input: &str) -> IResult<&str, Expression> {
     }
 }
 // Helper to parse string literals
-pub fn parse_string_literal(input: &str) -> IResult<&str, String> {
+pub fn parse_string_literal(input: &str) -> PResult<'_, String> {
     delimited(
         ws(tag("\"")),
         map(
-            recognize(many0(alt((alpha1, tag("/"), tag("."), tag("_"), tag("-"))))),
+            recognize(many0(alt((alpha1, digit1, tag("/"), tag("."), tag("_"), tag("-"))))),
             |s: &str| s.to_string(),
         ),
-        ws(tag("\"")),
+        ws_inline(tag("\"")),
     )
     .parse(input)
 }
 
-pub fn parse_index(input: &str) -> IResult<&str, Expression> {
+pub fn parse_index(input: &str) -> PResult<'_, Expression> {
     // Parse single letter OR multi-letter word
     let (input, base_name) = alpha1(input)?;
 
@@ -87,51 +92,95 @@ pub fn parse_index(input: &str) -> IResult<&str, Expression> {
 
     // Check what comes after
     alt((
-        // Multi-letter word followed by ( = function call or system function
+        // Word followed by ( = function call or system function
         preceded(peek(tag("(")), move |input| {
-            if base_name_clone1.len() > 1 {
-                // Try system function first
-                let func_lower = base_name_clone1.to_lowercase();
-                match func_lower.as_str() {
-                    "print" => {
-                        let (input, arg) =
-                            delimited(ws(tag("(")), parse_addition, ws(tag(")"))).parse(input)?;
-                        return Ok((input, Expression::System(Box::new(System::Print(arg)))));
-                    }
-                    "import" => {
-                        let (input, path) =
-                            delimited(ws(tag("(")), parse_string_literal, ws(tag(")")))
-                                .parse(input)?;
-                        return Ok((input, Expression::System(Box::new(System::Import(path)))));
-                    }
-                    "frame" => {
-                        let (input, path) =
-                            delimited(ws(tag("(")), parse_string_literal, ws(tag(")")))
-                                .parse(input)?;
-                        return Ok((input, Expression::System(Box::new(System::Frame(path)))));
-                    }
-                    _ => {
-                        // Regular function call
-                        let base_ident = Ident {
-                            inner: base_name_clone1.clone(),
-                        };
-                        return map(
-                            delimited(
-                                ws(tag("(")),
-                                separated_list0(ws(tag(",")), parse_addition),
-                                ws(tag(")")),
-                            ),
-                            move |args| Expression::FunctionCall(base_ident.clone(), args),
-                        )
-                        .parse(input);
-                    }
+            // Try system function first
+            let func_lower = base_name_clone1.to_lowercase();
+            match func_lower.as_str() {
+                "print" => map(
+                    delimited(ws(tag("(")), parse_logic, ws_inline(tag(")"))),
+                    |arg| Expression::System(Box::new(System::Print(arg))),
+                )
+                .parse(input),
+                "import" => map(
+                    delimited(ws(tag("(")), parse_string_literal, ws_inline(tag(")"))),
+                    |path| Expression::System(Box::new(System::Import(path))),
+                )
+                .parse(input),
+                "simplify" => map(
+                    delimited(ws(tag("(")), parse_logic, ws_inline(tag(")"))),
+                    |arg| Expression::System(Box::new(System::Simplify(arg))),
+                )
+                .parse(input),
+                "diff" => map(
+                    delimited(
+                        ws(tag("(")),
+                        separated_pair(parse_logic, ws(tag(",")), parse_ident),
+                        ws_inline(tag(")")),
+                    ),
+                    |(expression, x)| Expression::System(Box::new(System::Diff(expression, x))),
+                )
+                .parse(input),
+                "frame" => map(
+                    delimited(ws(tag("(")), parse_string_literal, ws_inline(tag(")"))),
+                    |path| Expression::System(Box::new(System::Frame(path))),
+                )
+                .parse(input),
+                "plot" => map(
+                    delimited(
+                        ws(tag("(")),
+                        (
+                            parse_addition,
+                            ws(tag(",")),
+                            parse_ident,
+                            ws(tag(",")),
+                            parse_addition,
+                            ws(tag(",")),
+                            parse_addition,
+                        ),
+                        ws_inline(tag(")")),
+                    ),
+                    |(expression, _, x, _, a, _, b)| {
+                        Expression::System(Box::new(System::Plot(expression, x, a, b)))
+                    },
+                )
+                .parse(input),
+                "json" => map(
+                    delimited(
+                        ws(tag("(")),
+                        (
+                            parse_string_literal,
+                            opt(preceded(ws(tag(",")), parse_string_literal)),
+                        ),
+                        ws_inline(tag(")")),
+                    ),
+                    |(path, field)| Expression::System(Box::new(System::Json(path, field))),
+                )
+                .parse(input),
+                "data" => map(
+                    (
+                        delimited(ws(tag("(")), parse_string_literal, ws_inline(tag(")"))),
+                        opt(preceded(tag("."), parse_ident)),
+                    ),
+                    |(path, column)| Expression::System(Box::new(System::Data(path, column))),
+                )
+                .parse(input),
+                _ => {
+                    // Regular function call, for a single letter this may also be
+                    // implicit multiplication x(y+1) (the evaluator decides)
+                    let base_ident = Ident {
+                        inner: base_name_clone1.clone(),
+                    };
+                    map(
+                        delimited(
+                            ws(tag("(")),
+                            separated_list0(ws(tag(",")), parse_logic),
+                            ws_inline(tag(")")),
+                        ),
+                        move |args| Expression::FunctionCall(base_ident.clone(), args),
+                    )
+                    .parse(input)
                 }
-            } else {
-                // Single letter - fail so implicit multiplication handles it
-                Err(nom::Err::Error(nom::error::Error::new(
-                    input,
-                    nom::error::ErrorKind::Tag,
-                )))
             }
         }),
         // Subscript: single letter followed by _
@@ -163,32 +212,27 @@ pub fn parse_index(input: &str) -> IResult<&str, Expression> {
                 )
                 .parse(input)
             } else {
-                Err(nom::Err::Error(nom::error::Error::new(
-                    input,
-                    nom::error::ErrorKind::Tag,
-                )))
+                Err(nom::Err::Error(Failure::new(input, ErrorKind::Tag)))
             }
         }),
-        // Just a variable (single letter) or function name (multi-letter, will fail later if used wrong)
+        // Just a variable (single letter or a predefined constant like pi)
+        // or function name (multi-letter, will fail later if used wrong)
         move |input| {
-            if base_name_clone3.len() == 1 {
+            if base_name_clone3.len() == 1 || builtins::constant(&base_name_clone3).is_some() {
                 let base_ident = Ident {
                     inner: base_name_clone3.clone(),
                 };
                 Ok((input, Expression::VariableRef(base_ident)))
             } else {
                 // Multi-letter without () - this is an error!
-                Err(nom::Err::Error(nom::error::Error::new(
-                    input,
-                    nom::error::ErrorKind::Tag,
-                )))
+                Err(nom::Err::Error(Failure::new(input, ErrorKind::Tag)))
             }
         },
     ))
     .parse(input)
 }
 
-pub fn parse_root(input: &str) -> IResult<&str, Expression> {
+pub fn parse_root(input: &str) -> PResult<'_, Expression> {
     log::debug!(">>> parse_root {:?}", input);
 
     alt((
@@ -208,7 +252,7 @@ pub fn parse_root(input: &str) -> IResult<&str, Expression> {
                     }))
                 },
             ),
-            ws(tag(")")),
+            ws_inline(tag(")")),
         ),
         // sqrt(x) - square root (syntactic sugar for root(2, x))
         delimited(
@@ -219,7 +263,7 @@ pub fn parse_root(input: &str) -> IResult<&str, Expression> {
                     radicand: arg,
                 }))
             }),
-            ws(tag(")")),
+            ws_inline(tag(")")),
         ),
         // √x - unicode square root
         preceded(
@@ -235,14 +279,103 @@ pub fn parse_root(input: &str) -> IResult<&str, Expression> {
     .parse(input)
 }
 
+// sum(i, 1, n, body) / prod(i, 1, n, body), Σ and ∏ work as names too.
+// \sum_{i=1}^{n} body (also Σ_(i=1)^n body) takes the product after it as the body
+pub fn parse_series(input: &str) -> PResult<'_, Expression> {
+    log::debug!(">>> parse_series {:?}", input);
+
+    let (input, symbol) = alt((
+        map(alt((tag("\\sum"), tag("sum"), tag("Σ"))), |_| Symbols::Summation),
+        map(alt((tag("\\prod"), tag("prod"), tag("∏"))), |_| Symbols::Product),
+    ))
+    .parse(input)?;
+
+    let call = delimited(
+        ws(tag("(")),
+        (
+            parse_ident,
+            preceded(ws(tag(",")), parse_addition),
+            preceded(ws(tag(",")), parse_addition),
+            preceded(ws(tag(",")), parse_addition),
+        ),
+        ws_inline(tag(")")),
+    );
+    // i=1 between {} or ()
+    let lower = |input| separated_pair(ws(parse_ident), tag("="), ws(parse_addition)).parse(input);
+    let scripts = map(
+        (
+            preceded(
+                tag("_"),
+                alt((
+                    delimited(tag("{"), lower, tag("}")),
+                    delimited(tag("("), lower, tag(")")),
+                )),
+            ),
+            preceded(
+                tag("^"),
+                alt((
+                    delimited(tag("{"), ws(parse_addition), tag("}")),
+                    parse_atom,
+                )),
+            ),
+            parse_multiplication,
+        ),
+        |((index, from), to, body)| (index, from, to, body),
+    );
+
+    map(alt((call, scripts)), move |(index, from, to, body)| {
+        Expression::Opperations(Box::new(Series {
+            symbol: symbol.clone(),
+            index,
+            from,
+            to,
+            body,
+        }))
+    })
+    .parse(input)
+}
+
+// A word on its own, `or` doesn't match the start of `order`
+fn keyword(word: &'static str) -> impl FnMut(&str) -> PResult<'_, &str> {
+    move |input| terminated(tag(word), not(satisfy(|c| c.is_alphanumeric()))).parse(input)
+}
+
+// and / or / not, or the sign for it
+fn connective(word: &'static str, sign: &'static str) -> impl FnMut(&str) -> PResult<'_, &str> {
+    move |input| alt((keyword(word), tag(sign))).parse(input)
+}
+
+// { x if x > 0; 0 otherwise }, only the last case can be `otherwise`
+pub fn parse_piecewise(input: &str) -> PResult<'_, Expression> {
+    log::debug!(">>> parse_piecewise {:?}", input);
+
+    let case = (parse_logic, preceded(ws(keyword("if")), parse_logic));
+    let otherwise = terminated(parse_logic, ws(keyword("otherwise")));
+
+    map(
+        delimited(
+            ws(tag("{")),
+            (
+                separated_list1(ws(tag(";")), case),
+                opt(preceded(ws(tag(";")), otherwise)),
+            ),
+            (opt(ws(tag(";"))), ws_inline(tag("}"))),
+        ),
+        |(cases, otherwise)| Expression::Opperations(Box::new(Piecewise { cases, otherwise })),
+    )
+    .parse(input)
+}
+
 // ATOMS - lowest level (no operators)
-pub fn parse_atom(input: &str) -> IResult<&str, Expression> {
+pub fn parse_atom(input: &str) -> PResult<'_, Expression> {
     log::debug!(">>> parse_atom {:?}", input);
     preceded(
         multispace0,
         alt((
+            parse_series,
             parse_root,
             parse_parens,
+            parse_piecewise,
             parse_index,
             parse_list,
             // variable ref is integrated into index
@@ -254,81 +387,225 @@ pub fn parse_atom(input: &str) -> IResult<&str, Expression> {
     .parse(input)
 }
 
-pub fn parse_multiplication(input: &str) -> IResult<&str, Expression> {
+// NEGATION - binds looser than ^ (-x^2 = -(x^2)), tighter than * and /
+pub fn parse_unary(input: &str) -> PResult<'_, Expression> {
+    log::debug!(">>> parse_unary {:?}", input);
+
+    alt((
+        map(
+            preceded(ws(tag(Symbols::Subtraction.as_str())), parse_unary),
+            |value| Expression::Opperations(Box::new(Neg { value })),
+        ),
+        parse_exponentiation,
+    ))
+    .parse(input)
+}
+
+// Left-associative chain: runs of the flat operator (+, *) share one node,
+// the inverse operator (-, /) takes everything before it as its left side
+fn chain(
+    first: Expression,
+    rest: Vec<(Symbols, Expression)>,
+    flat: Symbols,
+    collect: fn(Vec<Expression>) -> Expression,
+    binary: fn(Expression, Expression) -> Expression,
+) -> Expression {
+    let collapse = |mut terms: Vec<Expression>| {
+        if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            collect(terms)
+        }
+    };
+
+    let mut terms = vec![first];
+    for (symbol, expression) in rest {
+        if symbol == flat {
+            terms.push(expression);
+        } else {
+            terms = vec![binary(collapse(terms), expression)];
+        }
+    }
+    collapse(terms)
+}
+
+pub fn parse_multiplication(input: &str) -> PResult<'_, Expression> {
     log::debug!(">>> parse_multi {:?}", input);
 
-    let (input, first) = parse_exponentiation(input)?;
+    let (input, first) = parse_unary(input)?;
     let (input, rest) = many0(alt((
         // Explicit multiplication with *
-        preceded(
-            ws(tag(Symbols::Multiplication.as_str())),
-            parse_exponentiation,
+        map(
+            preceded(ws(tag(Symbols::Multiplication.as_str())), parse_unary),
+            |e| (Symbols::Multiplication, e),
         ),
-        // Implicit: followed by (, identifier, or digit
-        preceded(
-            peek(alt((
-                tag("("),
-                recognize(alpha1), // Variables like x, y
-                recognize(digit1), // Numbers like 1, 23
-            ))),
-            parse_atom,
+        // Division with /
+        map(
+            preceded(ws(tag(Symbols::Division.as_str())), parse_unary),
+            |e| (Symbols::Division, e),
+        ),
+        // Implicit: followed by (, { (a piecewise), identifier, or digit
+        map(
+            preceded(
+                peek(alt((
+                    tag("("),
+                    tag("{"),
+                    recognize(alpha1), // Variables like x, y
+                    recognize(digit1), // Numbers like 1, 23
+                    tag("\\sum"),    // Series like 2 \sum_{i=1}^{n} i
+                    tag("\\prod"),
+                    tag("Σ"),
+                    tag("∏"),
+                ))),
+                parse_exponentiation,
+            ),
+            |e| (Symbols::Multiplication, e),
         ),
     )))
     .parse(input)?;
 
-    if rest.is_empty() {
-        Ok((input, first))
-    } else {
-        let mut terms = vec![first];
-        terms.extend(rest);
-        Ok((input, Expression::Opperations(Box::new(Multi { terms }))))
-    }
+    Ok((
+        input,
+        chain(
+            first,
+            rest,
+            Symbols::Multiplication,
+            |terms| Expression::Opperations(Box::new(Multi { terms })),
+            |first, second| Expression::Opperations(Box::new(Div { first, second })),
+        ),
+    ))
 }
 // ADDITION - lower precedence
-pub fn parse_addition(input: &str) -> IResult<&str, Expression> {
+pub fn parse_addition(input: &str) -> PResult<'_, Expression> {
     log::debug!(">>> parse_addition {:?}", input);
 
     let (input, first) = parse_multiplication(input)?;
-    let (input, rest) = many0(preceded(
-        ws(tag(Symbols::Addition.as_str())),
-        parse_multiplication,
-    ))
+    let (input, rest) = many0(alt((
+        map(
+            preceded(ws(tag(Symbols::Addition.as_str())), parse_multiplication),
+            |e| (Symbols::Addition, e),
+        ),
+        map(
+            preceded(ws(tag(Symbols::Subtraction.as_str())), parse_multiplication),
+            |e| (Symbols::Subtraction, e),
+        ),
+    )))
     .parse(input)?;
 
+    Ok((
+        input,
+        chain(
+            first,
+            rest,
+            Symbols::Addition,
+            |terms| Expression::Opperations(Box::new(Add { terms })),
+            |first, second| Expression::Opperations(Box::new(Sub { first, second })),
+        ),
+    ))
+}
+
+fn parse_comparison_symbol(input: &str) -> PResult<'_, Symbols> {
+    // Two character forms before their prefixes
+    alt((
+        map(alt((tag("<="), tag("≤"))), |_| Symbols::LessEqual),
+        map(alt((tag(">="), tag("≥"))), |_| Symbols::GreaterEqual),
+        map(alt((tag("!="), tag("≠"))), |_| Symbols::NotEqual),
+        map(tag("<"), |_| Symbols::Less),
+        map(tag(">"), |_| Symbols::Greater),
+        map(tag("="), |_| Symbols::Equal),
+    ))
+    .parse(input)
+}
+
+// COMPARISON - looser than arithmetic, 0 < x <= 1 is one chain
+pub fn parse_comparison(input: &str) -> PResult<'_, Expression> {
+    log::debug!(">>> parse_comparison {:?}", input);
+
+    let (input, first) = parse_addition(input)?;
+    let (input, rest) = many0(pair(ws(parse_comparison_symbol), parse_addition)).parse(input)?;
+
     if rest.is_empty() {
         Ok((input, first))
     } else {
-        let mut terms = vec![first];
-        terms.extend(rest);
-        Ok((input, Expression::Opperations(Box::new(Add { terms }))))
+        Ok((
+            input,
+            Expression::Opperations(Box::new(Compare { first, rest })),
+        ))
+    }
+}
+
+// NOT - binds looser than a comparison (not x < 1 = not (x < 1))
+pub fn parse_not(input: &str) -> PResult<'_, Expression> {
+    log::debug!(">>> parse_not {:?}", input);
+
+    alt((
+        map(preceded(ws(connective("not", "¬")), parse_not), |value| {
+            Expression::Opperations(Box::new(Not { value }))
+        }),
+        parse_comparison,
+    ))
+    .parse(input)
+}
+
+// Runs of `and` (or `or`) share one node
+fn logic(
+    symbol: Symbols,
+    word: &'static str,
+    sign: &'static str,
+    operand: fn(&str) -> PResult<'_, Expression>,
+) -> impl FnMut(&str) -> PResult<'_, Expression> {
+    move |input| {
+        let (input, terms) = separated_list1(ws(connective(word, sign)), operand).parse(input)?;
+        Ok((
+            input,
+            match terms.len() {
+                1 => terms.into_iter().next().unwrap(),
+                _ => Expression::Opperations(Box::new(Logic {
+                    symbol: symbol.clone(),
+                    terms,
+                })),
+            },
+        ))
     }
 }
 
+// AND - tighter than or
+pub fn parse_and(input: &str) -> PResult<'_, Expression> {
+    log::debug!(">>> parse_and {:?}", input);
+    logic(Symbols::And, "and", "∧", parse_not)(input)
+}
+
+// OR - lowest precedence
+pub fn parse_logic(input: &str) -> PResult<'_, Expression> {
+    log::debug!(">>> parse_logic {:?}", input);
+    logic(Symbols::Or, "or", "∨", parse_and)(input)
+}
+
 // PARENTHESES - recursion point
-pub fn parse_parens(input: &str) -> IResult<&str, Expression> {
+pub fn parse_parens(input: &str) -> PResult<'_, Expression> {
     log::debug!(">>> parse_paren {:?}", input);
 
     delimited(
         ws(tag("(")),
-        parse_addition, // Goes back to top-level expression
-        ws(tag(")")),
+        parse_logic, // Goes back to top-level expression
+        ws_inline(tag(")")),
     )
     .parse(input)
 }
 
-pub fn parse_list(input: &str) -> IResult<&str, Expression> {
+pub fn parse_list(input: &str) -> PResult<'_, Expression> {
     log::debug!(">>> parse_list {:?}", input);
 
     let (input, _) = ws(tag("[")).parse(input)?;
-    let (input, items) = separated_list1(ws(tag(",")), parse_addition).parse(input)?;
-    let (input, _) = ws(tag("]")).parse(input)?;
+    let (input, items) = separated_list1(ws(tag(",")), parse_logic).parse(input)?;
+    let (input, _) = ws_inline(tag("]")).parse(input)?;
     Ok((input, Expression::List(items)))
 }
 // TOP-LEVEL ENTRY POINT
-pub fn pexp(input: &str) -> IResult<&str, Expression> {
+pub fn pexp(input: &str) -> PResult<'_, Expression> {
     log::debug!(">>> pexp {:?}", input);
     // LISTS CAN ONLY EXSIST
-    parse_addition(input)
+    parse_logic(input)
 }
 
 macro_rules! test_expression {
@@ -359,6 +636,7 @@ macro_rules! test_expression {
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use crate::{ast::base::Value, eval::Environment};
 
     #[test]
     fn test_path() {
@@ -381,6 +659,10 @@ mod tests {
     fn test_system_func() {
         test_expression!("frame(\"data.mtx\")");
         test_expression!("frame(\"path/to/data.mtx\")");
+        test_expression!("data(\"temps.csv\")");
+        test_expression!("data(\"temps.csv\").celsius");
+        test_expression!("plot(f, x, 0, 1)");
+        test_expression!("plot([f(x), 2x], x, -1, 1)");
     }
     #[test]
     fn test_advanced() {
@@ -437,6 +719,97 @@ mod tests {
         log::debug!("{:?}", pexp(input))
     }
 
+    fn eval(input: &str) -> Value {
+        let (rest, expression) = pexp(input).expect("should parse");
+        assert!(rest.is_empty(), "unparsed: {:?}", rest);
+        let mut env = Environment::new();
+        env.bind(&"x".to_string().into(), 3.0.into());
+        env.evaluate(&expression).expect("should evaluate")
+    }
+
+    #[test]
+    fn test_subtraction_left_associative() {
+        let (_, result) = pexp("2 - 3 - 4").unwrap();
+        assert_eq!(result.to_string(), "2 - 3 - 4");
+        assert_eq!(eval("2 - 3 - 4"), Value::from(-5));
+        assert_eq!(eval("2 - (3 - 4)"), Value::from(3));
+        assert_eq!(eval("10 - 2 + 3 - 1"), Value::from(10));
+    }
+
+    #[test]
+    fn test_division_left_associative() {
+        let (_, result) = pexp("a/b").unwrap();
+        assert_eq!(result.to_string(), "a / b");
+        assert_eq!(eval("8 / 4 / 2"), Value::from(1));
+        assert_eq!(eval("1 + 6 / 2x"), Value::from(10));
+        assert_eq!(eval("x - 1"), Value::from(2));
+    }
+
+    #[test]
+    fn test_negation() {
+        let (_, result) = pexp("-x^2").unwrap();
+        assert!(format!("{:?}", result).starts_with("Opperations(Neg { value: Opperations(Exp"));
+        assert_eq!(result.to_string(), "-x^2");
+        assert_eq!(eval("-x^2"), Value::from(-9));
+        assert_eq!(eval("(-x)^2"), Value::from(9));
+        assert_eq!(eval("2^-1"), Value::from(0.5));
+        assert_eq!(eval("4 * -x"), Value::from(-12));
+    }
+
+    #[test]
+    fn test_implicit_multiplication_with_power() {
+        assert_eq!(eval("2x^2"), Value::from(18));
+    }
+
+    #[test]
+    fn test_stringify_keeps_grouping() {
+        let (_, result) = pexp("(a - b) / (c + d)").unwrap();
+        assert_eq!(result.to_string(), "(a - b) / (c + d)");
+        let (_, result) = pexp("2(a+2)+2").unwrap();
+        assert_eq!(result.to_string(), "2 * (a + 2) + 2");
+        let (_, result) = pexp("a - (b - c)").unwrap();
+        assert_eq!(result.to_string(), "a - (b - c)");
+    }
+
+    #[test]
+    fn test_conditions() {
+        let (_, result) = pexp("0 < x ≤ 1 and not x = 1 or x>=5").unwrap();
+        assert_eq!(result.to_string(), "0 < x <= 1 and not x = 1 or x >= 5");
+        let (_, result) = pexp("(a or b) and c").unwrap();
+        assert_eq!(result.to_string(), "(a or b) and c");
+        // `or` is a word of its own
+        let (rest, _) = pexp("x order").unwrap();
+        assert_eq!(rest, " order");
+        assert_eq!(eval("x + 1 > 3"), Value::from(true));
+    }
+
+    #[test]
+    fn test_conditions_as_arguments() {
+        let parsed = |input| {
+            let (rest, result) = pexp(input).unwrap();
+            assert!(rest.is_empty(), "unparsed: {:?}", rest);
+            result.to_string()
+        };
+        assert_eq!(parsed("print(1 < 2)"), "print(1 < 2)");
+        assert_eq!(parsed("f(x > 0)"), "f(x > 0)");
+        assert_eq!(parsed("simplify(not y or y)"), "simplify(not y or y)");
+        let (_, list) = pexp("[a and b, x = 1]").unwrap();
+        assert!(matches!(list, Expression::List(items) if items.len() == 2));
+        assert_eq!(
+            eval("[1 < 2, 2 < 1]"),
+            Value::List(vec![true.into(), false.into()])
+        );
+    }
+
+    #[test]
+    fn test_piecewise() {
+        let (rest, result) = pexp("{ x if x > 0; 0 otherwise }").unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(result.to_string(), "{ x if x > 0; 0 otherwise }");
+        assert_eq!(eval("2{ x^2 if x < 0; x if x < 5; }"), Value::from(6));
+        assert!(pexp("{ 0 otherwise; 1 if x > 0 }").is_err());
+    }
+
     #[test]
     fn parse_ident_name() {
         assert_eq!(
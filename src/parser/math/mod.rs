@@ -1,5 +1,6 @@
+pub mod error;
 pub(crate) mod expression;
-pub(crate) mod module;
+pub mod module;
 pub(crate) mod statement;
 pub(crate) mod value;
 pub(crate) mod whitespace;
@@ -1,9 +1,19 @@
 use crate::{
-    ast::module::{Metadata, Module, ParseMode},
-    parser::math::{statement::parse_statement, whitespace::ws},
+    ast::{
+        Statement,
+        module::{Metadata, Module, ParseMode},
+    },
+    parser::{
+        literate::parse_literate,
+        math::{
+            error::{PResult, ParseError, diagnose, tracked},
+            statement::parse_statement,
+            whitespace::ws,
+        },
+    },
 };
 use nom::{
-    IResult, Parser,
+    Parser,
     branch::alt,
     bytes::{
         complete::{is_not, tag},
@@ -11,19 +21,19 @@ use nom::{
     },
     character::complete::{alpha1, line_ending, multispace0},
     combinator::{map, opt, peek},
-    multi::{many0, separated_list0, separated_list1},
+    multi::{many0, many1, separated_list0},
     sequence::{delimited, separated_pair},
 };
 
-fn comment_ending(input: &str) -> IResult<&str, ()> {
+fn comment_ending(input: &str) -> PResult<'_, ()> {
     map((ws(tag(";")), opt(line_ending)), |_| ()).parse(input)
 }
 
-fn nothing(input: &str) -> IResult<&str, ()> {
+fn nothing(input: &str) -> PResult<'_, ()> {
     Ok((input, ()))
 }
 //metadata comment block
-fn parse_metadata_comment(input: &str) -> IResult<&str, Vec<(&str, &str)>> {
+fn parse_metadata_comment(input: &str) -> PResult<'_, Vec<(&str, &str)>> {
     delimited(
         ws(tag("/*")),
         map(
@@ -45,62 +55,101 @@ fn parse_metadata_comment(input: &str) -> IResult<&str, Vec<(&str, &str)>> {
     .parse(input)
 }
 
-pub fn parse_metadata(input: &str) -> IResult<&str, Metadata> {
-    map(parse_metadata_comment, |pairs| {
-        let mut metadata = Metadata {
-            author: None,
-            author_email: None,
-            url: None,
-            title: None,
-        };
+pub fn parse_metadata(input: &str) -> PResult<'_, Metadata> {
+    if input.trim().is_empty() {
+        // Nothing to read, every field is left unset
+        return Ok((input, Metadata::default()));
+    }
+
+    map(parse_metadata_comment, metadata).parse(input)
+}
+
+// Metadata from key: value pairs, shared with the front matter of literate documents
+pub fn metadata<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Metadata {
+    let mut metadata = Metadata::default();
 
-        for (key, value) in pairs {
-            match key {
-                "author" => metadata.author = Some(value.trim().to_string()),
-                "author_email" => metadata.author_email = Some(value.trim().to_string()),
-                "url" => metadata.url = Some(value.trim().to_string()),
-                "title" => metadata.title = Some(value.trim().to_string()),
-                _ => {} // Ignore unknown keys
+    for (key, value) in pairs {
+        match key.trim() {
+            "author" => metadata.author = Some(value.trim().to_string()),
+            "author_email" => metadata.author_email = Some(value.trim().to_string()),
+            "url" => metadata.url = Some(value.trim().to_string()),
+            "title" => metadata.title = Some(value.trim().to_string()),
+            "imaginary" => {
+                metadata.imaginary = match value.trim() {
+                    "i" => Some('i'),
+                    "j" => Some('j'),
+                    _ => None,
+                }
             }
+            _ => {} // Ignore unknown keys
         }
+    }
 
-        metadata
-    })
-    .parse(input)
+    metadata
 }
 
-pub fn parse_module(input: &str, is_entry: bool, mode: ParseMode) -> IResult<&str, Module> {
+// One statement per line, blank lines in between are skipped. A statement often
+// takes the line break after it along, otherwise one has to follow: `a = 1 2` isn't two.
+pub fn parse_statements(input: &str) -> PResult<'_, Vec<Statement>> {
+    let mut statements = Vec::new();
+    let mut rest = input;
+    let mut line_ended = true;
+    loop {
+        let line_break: PResult<'_, Vec<&str>> = if line_ended {
+            many0(line_ending).parse(rest)
+        } else {
+            many1(line_ending).parse(rest)
+        };
+        let Ok((next, _)) = line_break else {
+            return Ok((rest, statements));
+        };
+        match parse_statement(next) {
+            Ok((after, statement)) => {
+                let taken = &next[..next.len() - after.len()];
+                line_ended = taken.trim_end_matches([' ', '\t']).ends_with('\n');
+                statements.push(statement);
+                rest = after;
+            }
+            Err(error) if statements.is_empty() => return Err(error),
+            Err(_) => return Ok((rest, statements)),
+        }
+    }
+}
+
+pub fn module(
+    metadata: Metadata,
+    statements: Vec<Statement>,
+    is_entry: bool,
+    mode: &ParseMode,
+) -> Module {
+    if is_entry {
+        Module::Entry {
+            metadata,
+            statements,
+        }
+    } else {
+        match mode {
+            ParseMode::Module => Module::Module {
+                statements,
+                metadata,
+            },
+            ParseMode::Frame => Module::Frame {
+                metadata,
+                statements,
+            },
+        }
+    }
+}
+
+pub fn parse_module(input: &str, is_entry: bool, mode: ParseMode) -> PResult<'_, Module> {
     map(
         (
             opt(parse_metadata), // Make metadata optional
             many0(line_ending),  // Consume any empty newlines after metadata
-            separated_list1(many0(line_ending), parse_statement),
+            parse_statements,
         ),
         |(metadata, _, statements)| {
-            let metadata = metadata.unwrap_or(Metadata {
-                author: None,
-                author_email: None,
-                url: None,
-                title: None,
-            });
-
-            if is_entry {
-                Module::Entry {
-                    metadata,
-                    statements,
-                }
-            } else {
-                match mode {
-                    ParseMode::Module => Module::Module {
-                        statements,
-                        metadata,
-                    },
-                    ParseMode::Frame => Module::Frame {
-                        metadata,
-                        statements,
-                    },
-                }
-            }
+            module(metadata.unwrap_or_default(), statements, is_entry, &mode)
         },
     )
     .parse(input)
@@ -114,24 +163,50 @@ use thiserror::Error;
 pub enum FileParseError {
     #[error("the data for key `{0}` is not available")]
     Redaction(String),
-    #[error("file not found")]
+    #[error("file not found: {0}")]
     NotFound(String),
     #[error("unknown data store error")]
     Unknown,
+    #[error(transparent)]
+    Syntax(#[from] ParseError),
 }
 
 pub fn parse_entry_file(path: &str) -> Result<Module, FileParseError> {
+    parse_file(path, true, ParseMode::Frame)
+}
+
+// Imported (module) or embedded (frame) files
+pub fn parse_module_file(path: &str, mode: ParseMode) -> Result<Module, FileParseError> {
+    parse_file(path, false, mode)
+}
+
+fn parse_file(path: &str, is_entry: bool, mode: ParseMode) -> Result<Module, FileParseError> {
     // Open the File
     if let Ok(file) = fs::read_to_string(path) {
-        match parse_module(file.as_str(), true, ParseMode::Frame) {
-            Ok((_, module)) => Ok(module),
-            Err(error) => panic!("{:?}", error),
+        // Markdown files are literate documents, prose with mtx blocks
+        if path.ends_with(".md") {
+            let (metadata, statements) = parse_literate(path, &file)?;
+            return Ok(module(metadata, statements, is_entry, &mode));
         }
+        Ok(parse_source(path, file.as_str(), is_entry, mode)?)
     } else {
         // Can't Open => File Not Found
         Err(FileParseError::NotFound(path.to_string()))
     }
 }
+
+// Parse a whole file, anything left over is reported against `file`
+pub fn parse_source(
+    file: &str,
+    source: &str,
+    is_entry: bool,
+    mode: ParseMode,
+) -> Result<Module, ParseError> {
+    match tracked(source, |input| parse_module(input, is_entry, mode)) {
+        (Ok((rest, module)), _) if rest.trim().is_empty() => Ok(module),
+        (_, furthest) => Err(diagnose(file, source, furthest)),
+    }
+}
 #[cfg(test)]
 mod tests {
 
@@ -154,6 +229,26 @@ mod tests {
         println!("{:?}", metadata)
     }
     #[test]
+    fn test_parse_module_statement_per_line() {
+        let input = "frame(\"section.mtx\")\nimport(\"lib.mtx\")\nx";
+        let (_, module) = parse_module(input, false, ParseMode::Module).unwrap();
+        assert_eq!(module.statements().len(), 3)
+    }
+    #[test]
+    fn test_parse_statements_need_line_breaks() {
+        let (rest, statements) = parse_statements("a = 1 2").unwrap();
+        assert_eq!((statements.len(), rest), (1, "2"));
+        let (rest, statements) = parse_statements("a = 1\n\n2").unwrap();
+        assert_eq!((statements.len(), rest), (2, ""));
+    }
+    #[test]
+    fn test_parse_source_reports_position() {
+        let source = "x = 1\ny = (x + 2\n";
+        let error = parse_source("main.mtx", source, true, ParseMode::Frame).unwrap_err();
+        assert_eq!((error.file.as_str(), error.line), ("main.mtx", 2));
+        assert_eq!(error.message, "expected `)`, found end of line");
+    }
+    #[test]
     fn test_parse_metadata_empty() {
         let input = "";
         let (_, metadata) = parse_metadata(input).unwrap();
@@ -163,6 +258,7 @@ mod tests {
                 author_email: Option::None,
                 url: Option::None,
                 title: Option::None,
+                imaginary: Option::None,
             },
             metadata
         )
@@ -182,8 +278,17 @@ mod tests {
                 author_email: Some("piderking8@gmail.com".to_string()),
                 url: Some("https://github.com/piderking/mtx".to_string()),
                 title: Some("MTX Example".to_string()),
+                imaginary: None,
             },
             metadata
         )
     }
+
+    #[test]
+    fn test_parse_metadata_imaginary() {
+        let unit = |input| parse_metadata(input).unwrap().1.imaginary;
+        assert_eq!(unit("/* imaginary: j */"), Some('j'));
+        assert_eq!(unit("/* title: AC; imaginary: i; */"), Some('i'));
+        assert_eq!(unit("/* imaginary: k */"), None);
+    }
 }
@@ -1,5 +1,5 @@
 use nom::{
-    IResult, Parser,
+    Parser,
     branch::alt,
     bytes::complete::{is_not, tag, take_until},
     character::complete::{alpha1, multispace0, newline},
@@ -11,13 +11,14 @@ use nom::{
 use crate::{
     ast::{Comment, Definition, Statement, base::Ident},
     parser::math::{
+        error::PResult,
         expression::{parse_ident, pexp},
         whitespace::ws,
     },
 };
 
 // PARENTHESES - recursion point
-pub fn parse_function_def(input: &str) -> IResult<&str, Definition> {
+pub fn parse_function_def(input: &str) -> PResult<'_, Definition> {
     map(
         (
             ws(parse_ident),
@@ -35,34 +36,34 @@ pub fn parse_function_def(input: &str) -> IResult<&str, Definition> {
 }
 
 // PARENTHESES - recursion point
-pub fn parse_constant_def(input: &str) -> IResult<&str, Definition> {
+pub fn parse_constant_def(input: &str) -> PResult<'_, Definition> {
     map((ws(parse_ident), tag("="), ws(pexp)), |(ident, _, expr)| {
         Definition::Constant(ident, expr)
     })
     .parse(input)
 }
 
-pub fn parse_definition(input: &str) -> IResult<&str, Definition> {
+pub fn parse_definition(input: &str) -> PResult<'_, Definition> {
     alt((parse_function_def, parse_constant_def)).parse(input)
 }
 
-pub fn parse_single_comment(input: &str) -> IResult<&str, Comment> {
+pub fn parse_single_comment(input: &str) -> PResult<'_, Comment> {
     let (input, (_, _, v, _)) = (tag("//"), multispace0, is_not("\n"), newline).parse(input)?;
     Ok((input, Comment::Single(v.to_string())))
 }
-pub fn parse_multi_comment(input: &str) -> IResult<&str, Comment> {
+pub fn parse_multi_comment(input: &str) -> PResult<'_, Comment> {
     map(
-        delimited(tag("/*"), ws(is_not("*/")), tag("*/")),
+        delimited(tag("/*"), ws(take_until("*/")), tag("*/")),
         |f: &str| Comment::Multi(f.to_string()),
     )
     .parse(input)
 }
 
-pub fn parse_comment(input: &str) -> IResult<&str, Comment> {
+pub fn parse_comment(input: &str) -> PResult<'_, Comment> {
     alt((parse_single_comment, parse_multi_comment)).parse(input)
 }
 
-pub fn parse_statement(input: &str) -> IResult<&str, Statement> {
+pub fn parse_statement(input: &str) -> PResult<'_, Statement> {
     alt((
         map(parse_definition, Statement::Definition),
         map(parse_comment, Statement::Comment),
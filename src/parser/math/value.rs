@@ -1,27 +1,45 @@
 use nom::Parser;
 use nom::bytes::complete::take_until;
 use nom::character::anychar;
-use nom::number::complete::double;
 use nom::{
-    Err, IResult, Input, branch::alt, bytes::complete::tag, character::complete::char,
+    Err, Input, branch::alt, bytes::complete::tag, character::complete::char,
     combinator::map, number::complete::float,
 };
+use nom::character::complete::{alphanumeric1, digit1, one_of};
+use nom::combinator::{map_opt, not, opt, recognize};
 use nom::sequence::delimited;
 
 
-use crate::ast::base::Value;
+use crate::{
+    ast::{base::Value, number::Number},
+    parser::math::error::PResult,
+};
 
-// A parser that recognizes a float and maps it to Value::Float
-pub fn parse_float(input: &str) -> IResult<&str, f64> {
-    double(input)
+// A number literal (12, 1.5, .5, 1e-3) as the narrowest exact number,
+// an e without digits after it is left alone so 2e is 2 * e
+pub fn parse_number(input: &str) -> PResult<'_, Number> {
+    let mantissa = alt((
+        // opt(digit1) rather than digit0, which loses its place at the end of the input
+        recognize((digit1, opt((char('.'), opt(digit1))))),
+        recognize((char('.'), digit1)),
+    ));
+    let literal = recognize((
+        opt(one_of("+-")),
+        mantissa,
+        opt((one_of("eE"), opt(one_of("+-")), digit1)),
+    ));
+    map_opt(literal, |text: &str| {
+        Number::parse(text.strip_prefix('+').unwrap_or(text))
+    })
+    .parse(input)
 }
 // A parser that recognizes a float and maps it to Value::Float
-pub fn parse_char(input: &str) -> IResult<&str, char> {
+pub fn parse_char(input: &str) -> PResult<'_, char> {
     anychar(input)
 }
 
 // A parser that recognizes a float and maps it to Value::Float
-pub fn parse_char_value(input: &str) -> IResult<&str, Value> {
+pub fn parse_char_value(input: &str) -> PResult<'_, Value> {
     let mut parser = delimited(tag("'"), parse_char, tag("'"));
     let value = parser.parse(input);
     if let Ok((out, c)) = value{
@@ -31,9 +49,9 @@ pub fn parse_char_value(input: &str) -> IResult<&str, Value> {
     }
 
 }
-// A parser that recognizes a float and maps it to Value::Float
-pub fn parse_float_value(input: &str) -> IResult<&str, Value> {
-    map(parse_float, |f| f.into()).parse(input)
+// A parser that recognizes a number and maps it to Value::Number
+pub fn parse_number_value(input: &str) -> PResult<'_, Value> {
+    map(parse_number, Value::Number).parse(input)
 
     
 
@@ -41,9 +59,17 @@ pub fn parse_float_value(input: &str) -> IResult<&str, Value> {
 
 
 
-pub fn parse_value(input: &str) -> IResult<&str, Value>{
+// true or false, as a whole word
+pub fn parse_bool_value(input: &str) -> PResult<'_, Value> {
+    let (input, b) =
+        alt((map(tag("true"), |_| true), map(tag("false"), |_| false))).parse(input)?;
+    let (input, _) = not(alphanumeric1).parse(input)?;
+    Ok((input, Value::Bool(b)))
+}
+
+pub fn parse_value(input: &str) -> PResult<'_, Value> {
     //alt((parse_float_value, parse_char_value)).parse(input)
-    alt((parse_float_value, parse_char_value)).parse(input)
+    alt((parse_number_value, parse_bool_value, parse_char_value)).parse(input)
     
 }
 #[cfg(test)]
@@ -55,7 +81,21 @@ mod tests {
     fn parse_constant_float() {
         
         let (remaining_input, output): (_, Value) = parse_value("12.0").expect("Should Work");
-        assert_eq!(output, Value::Number(12.0));
+        assert_eq!(output, Value::from(12));
+    }
+    #[test]
+    fn parse_constant_exact() {
+        let number = |input| parse_number(input).expect("Should Work");
+        assert!(matches!(number("12.0"), ("", Number::Integer(12))));
+        assert!(matches!(number("0.25"), ("", Number::Rational(1, 4))));
+        assert!(matches!(number("1.5e3"), ("", Number::Integer(1500))));
+        assert!(matches!(number(".5"), ("", Number::Rational(1, 2))));
+        assert!(matches!(number("2e"), ("e", Number::Integer(2))));
+    }
+    #[test]
+    fn parse_constant_bool() {
+        assert_eq!(parse_value("true").unwrap(), ("", Value::Bool(true)));
+        assert!(parse_bool_value("falsey").is_err());
     }
     #[test]
     fn parse_constant_char() {
@@ -1,4 +1,4 @@
-use nom::character::complete::multispace0;
+use nom::character::complete::{multispace0, space0};
 use nom::error::ParseError;
 use nom::sequence::delimited;
 use nom::{IResult, Parser};
@@ -8,4 +8,13 @@ pub fn ws<'a, O, E: ParseError<&'a str>>(
 ) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
 {
     move |input| delimited(multispace0, &mut inner, multispace0).parse(input)
-}
\ No newline at end of file
+}
+
+// Like ws, but trailing whitespace stops at the end of the line
+// (a closing bracket shouldn't pull the next statement into the expression)
+pub fn ws_inline<'a, O, E: ParseError<&'a str>>(
+    mut inner: impl FnMut(&'a str) -> IResult<&'a str, O, E>
+) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
+{
+    move |input| delimited(multispace0, &mut inner, space0).parse(input)
+}
@@ -1,4 +1,5 @@
 // pub mod cmd;
+pub mod literate;
 pub mod markdown;
 pub mod math;
-// pub mod python;
+pub mod python;
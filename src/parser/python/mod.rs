@@ -1,108 +1,517 @@
-use rustpython_parser::ast::{Expr, ExprName, Identifier, Mod, ModModule};
+use std::collections::HashMap;
 
-use crate::ast::{AST, Definition, Expression, Statement,};
+use rustpython_parser::{
+    Mode,
+    ast::{
+        Arguments, CmpOp, Constant, Expr, ExprAttribute, ExprBinOp, ExprCall, ExprConstant,
+        ExprName, ExprSubscript, ExprUnaryOp, Identifier, Mod, Operator, Ranged, Stmt,
+        StmtFunctionDef, UnaryOp,
+    },
+    parse,
+};
+use thiserror::Error;
 
-impl From<Identifier> for Variable {
-    fn from(value: Identifier) -> Self {
-        Self {
-            inner: String::from(value.as_str()),
+use crate::{
+    ast::{
+        Definition,
+        base::{Ident, Value},
+        complex::Complex,
+        expressions::Expression,
+        number::Number,
+        opperations::{Add, Div, Exp, Index, Multi, Neg, Opperation, Root, Sub},
+    },
+    builtins,
+    parser::math::error::ParseError,
+    writer::python::KEYWORDS,
+};
+
+// Python that has no mtx equivalent, offsets are bytes into the source
+#[derive(Error, Debug, PartialEq)]
+pub enum PythonError {
+    #[error("{message}")]
+    Syntax { message: String, offset: usize },
+    #[error("unsupported python: {construct}")]
+    Unsupported { construct: String, offset: usize },
+    #[error("function `{name}` has to end with a return")]
+    MissingReturn { name: String, offset: usize },
+}
+
+impl PythonError {
+    fn unsupported(construct: impl Into<String>, node: &impl Ranged) -> Self {
+        PythonError::Unsupported {
+            construct: construct.into(),
+            offset: node.start().to_usize(),
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        match self {
+            PythonError::Syntax { offset, .. }
+            | PythonError::Unsupported { offset, .. }
+            | PythonError::MissingReturn { offset, .. } => *offset,
         }
     }
 }
 
-impl From<ExprName> for Variable {
-    fn from(value: ExprName) -> Self {
-        value.id.into()
+// Definitions of a Python file (functions returning a formula and constants)
+pub fn parse_python(file: &str, source: &str) -> Result<Vec<Definition>, ParseError> {
+    let definitions = parse(source, Mode::Module, file)
+        .map_err(|error| PythonError::Syntax {
+            message: error.error.to_string(),
+            offset: error.offset.to_usize(),
+        })
+        .and_then(definitions);
+    definitions.map_err(|error| ParseError::at(file, source, error.offset(), error.to_string()))
+}
+
+fn definitions(module: Mod) -> Result<Vec<Definition>, PythonError> {
+    let Mod::Module(module) = module else {
+        return Ok(Vec::new());
+    };
+    let mut definitions = Vec::new();
+    for statement in module.body {
+        match statement {
+            Stmt::FunctionDef(function) => definitions.push(function.try_into()?),
+            Stmt::Assign(assign) => {
+                let name = assigned(&assign.targets, &assign)?;
+                definitions.push(Definition::Constant(name, (*assign.value).try_into()?));
+            }
+            // The imports are what the formulas are written against (math, numpy)
+            Stmt::Import(_) | Stmt::ImportFrom(_) => {}
+            Stmt::Expr(expr) if is_docstring(&expr.value) => {}
+            Stmt::If(branch) if is_main_guard(&branch.test) => {}
+            statement => return Err(PythonError::unsupported(kind(&statement), &statement)),
+        }
     }
+    Ok(definitions)
+}
+
+// `If(StmtIf { .. })` -> `if` statements
+fn kind(node: &impl std::fmt::Debug) -> String {
+    let debug = format!("{:?}", node);
+    let name = debug.split('(').next().unwrap_or_default();
+    format!("`{}`", name.to_lowercase())
+}
+
+fn is_docstring(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Constant(ExprConstant {
+            value: Constant::Str(_),
+            ..
+        })
+    )
 }
-impl From<Mod> for AST {
-    fn from(value: Mod) -> Self {
-        match value {
-            Mod::Module(mod_module) => todo!(),
-            Mod::Interactive(mod_interactive) => todo!(),
-            Mod::Expression(mod_expression) => todo!(),
-            Mod::FunctionType(mod_function_type) => panic!("unsupported feature for now"),
+
+// if __name__ == "__main__":
+fn is_main_guard(test: &Expr) -> bool {
+    match test {
+        Expr::Compare(compare) => {
+            compare.ops == [CmpOp::Eq]
+                && matches!(compare.left.as_ref(), Expr::Name(name) if name.id.as_str() == "__name__")
         }
+        _ => false,
     }
 }
-impl From<Expr> for Expression {
-    fn from(expr: Expr) -> Self {
-        match expr {
-            Expr::BoolOp(expr_bool_op) => todo!("unsupported type: lists"),
-            Expr::NamedExpr(expr_named_expr) => {
-                panic!("unsupported, no assignment allowed in expressions")
+
+// Name bound by `name = ...`, unpacking and attributes aren't definitions
+fn assigned(targets: &[Expr], node: &impl Ranged) -> Result<Ident, PythonError> {
+    match targets {
+        [Expr::Name(name)] => Ok(name.id.clone().into()),
+        _ => Err(PythonError::unsupported(
+            "assignment to anything but a name",
+            node,
+        )),
+    }
+}
+
+fn parameters(arguments: &Arguments, node: &impl Ranged) -> Result<Vec<Ident>, PythonError> {
+    if arguments.vararg.is_some() || arguments.kwarg.is_some() {
+        return Err(PythonError::unsupported("`*args` and `**kwargs`", node));
+    }
+    if !arguments.kwonlyargs.is_empty() {
+        return Err(PythonError::unsupported("keyword-only parameters", node));
+    }
+    arguments
+        .posonlyargs
+        .iter()
+        .chain(&arguments.args)
+        .map(|arg| match arg.default {
+            Some(_) => Err(PythonError::unsupported("default values", &arg.def)),
+            None => Ok(arg.def.arg.clone().into()),
+        })
+        .collect()
+}
+
+impl From<Identifier> for Expression {
+    // Keywords were written with a trailing underscore (lambda_)
+    fn from(identifier: Identifier) -> Self {
+        let name = identifier.as_str();
+        let name = match name.strip_suffix('_') {
+            Some(keyword) if KEYWORDS.contains(&keyword) => keyword,
+            _ => name,
+        };
+        Expression::VariableRef(name.to_string().into())
+    }
+}
+
+impl TryFrom<StmtFunctionDef> for Definition {
+    type Error = PythonError;
+
+    // Local assignments are substituted into the returned formula
+    fn try_from(function: StmtFunctionDef) -> Result<Self, Self::Error> {
+        if !function.decorator_list.is_empty() {
+            return Err(PythonError::unsupported("decorators", &function));
+        }
+        let params = parameters(&function.args, &function)?;
+        let mut locals = HashMap::new();
+
+        for (i, statement) in function.body.iter().enumerate() {
+            match statement {
+                Stmt::Return(ret) if i + 1 == function.body.len() => {
+                    let Some(value) = &ret.value else {
+                        break;
+                    };
+                    let body = Expression::try_from(value.as_ref().clone())?;
+                    return Ok(Definition::Function(
+                        function.name.into(),
+                        params,
+                        body.substitute(&locals),
+                    ));
+                }
+                Stmt::Assign(assign) => {
+                    let name = assigned(&assign.targets, assign)?;
+                    let value = Expression::try_from(assign.value.as_ref().clone())?;
+                    locals.insert(name.inner, value.substitute(&locals));
+                }
+                Stmt::Expr(expr) if i == 0 && is_docstring(&expr.value) => {}
+                statement => return Err(PythonError::unsupported(kind(statement), statement)),
+            }
+        }
+
+        Err(PythonError::MissingReturn {
+            name: function.name.to_string(),
+            offset: function.start().to_usize(),
+        })
+    }
+}
+
+fn opperation(opperation: impl Opperation + 'static) -> Expression {
+    Expression::Opperations(Box::new(opperation))
+}
+
+fn number(n: impl Into<Number>) -> Expression {
+    Expression::Constant(Value::Number(n.into()))
+}
+
+fn float(n: f64) -> Number {
+    Number::parse(&n.to_string()).unwrap_or(Number::Float(n))
+}
+
+// Operands of a + b + c (parsed as (a + b) + c), so the sum has three terms
+fn operands(expr: Expr, op: Operator, out: &mut Vec<Expr>) {
+    match expr {
+        Expr::BinOp(binop) if binop.op == op => {
+            operands(*binop.left, op, out);
+            out.push(*binop.right);
+        }
+        expr => out.push(expr),
+    }
+}
+
+impl TryFrom<ExprBinOp> for Expression {
+    type Error = PythonError;
+
+    fn try_from(binop: ExprBinOp) -> Result<Self, Self::Error> {
+        let range = binop.range;
+        match binop.op {
+            op @ (Operator::Add | Operator::Mult) => {
+                let mut exprs = Vec::new();
+                operands(Expr::BinOp(binop), op, &mut exprs);
+                let terms = exprs
+                    .into_iter()
+                    .map(Expression::try_from)
+                    .collect::<Result<_, _>>()?;
+                Ok(match op {
+                    Operator::Add => opperation(Add { terms }),
+                    _ => opperation(Multi { terms }),
+                })
             }
-            Expr::BinOp(expr_bin_op) => todo!(),
-            Expr::UnaryOp(expr_unary_op) => todo!(),
-            Expr::Lambda(expr_lambda) => todo!(),
-            Expr::IfExp(expr_if_exp) => todo!(),
-            Expr::Dict(expr_dict) => todo!(),
-            Expr::Set(expr_set) => todo!(),
-            Expr::ListComp(expr_list_comp) => todo!(),
-            Expr::SetComp(expr_set_comp) => todo!(),
-            Expr::DictComp(expr_dict_comp) => todo!(),
-            Expr::GeneratorExp(expr_generator_exp) => todo!(),
-            Expr::Await(expr_await) => todo!(),
-            Expr::Yield(expr_yield) => todo!(),
-            Expr::YieldFrom(expr_yield_from) => todo!(),
-            Expr::Compare(expr_compare) => todo!(),
-            Expr::Call(expr_call) => todo!(),
-            Expr::FormattedValue(expr_formatted_value) => todo!(),
-            Expr::JoinedStr(expr_joined_str) => todo!(),
-            Expr::Constant(expr_constant) => todo!(),
-            Expr::Attribute(expr_attribute) => todo!(),
-            Expr::Subscript(expr_subscript) => todo!(),
-            Expr::Starred(expr_starred) => todo!(),
-            Expr::Name(expr_name) => todo!(),
-            Expr::List(expr_list) => todo!(),
-            Expr::Tuple(expr_tuple) => todo!(),
-            Expr::Slice(expr_slice) => todo!(),
+            Operator::Sub => Ok(opperation(Sub {
+                first: (*binop.left).try_into()?,
+                second: (*binop.right).try_into()?,
+            })),
+            Operator::Div => Ok(opperation(Div {
+                first: (*binop.left).try_into()?,
+                second: (*binop.right).try_into()?,
+            })),
+            Operator::Pow => Ok(opperation(Exp {
+                base: (*binop.left).try_into()?,
+                exponent: (*binop.right).try_into()?,
+            })),
+            op => Err(PythonError::Unsupported {
+                construct: format!("the {:?} operator", op),
+                offset: range.start().to_usize(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<ExprUnaryOp> for Expression {
+    type Error = PythonError;
+
+    fn try_from(unary: ExprUnaryOp) -> Result<Self, Self::Error> {
+        match unary.op {
+            UnaryOp::USub => match Expression::try_from(*unary.operand)? {
+                Expression::Constant(Value::Number(n)) => Ok(number(-n)),
+                value => Ok(opperation(Neg { value })),
+            },
+            UnaryOp::UAdd => (*unary.operand).try_into(),
+            op => Err(PythonError::unsupported(
+                format!("the {:?} operator", op),
+                &unary,
+            )),
+        }
+    }
+}
+
+// `sqrt` for math.sqrt and np.sqrt, other attributes have no meaning in mtx
+fn library(attribute: &ExprAttribute) -> Option<&str> {
+    match attribute.value.as_ref() {
+        Expr::Name(module) if ["math", "np", "numpy"].contains(&module.id.as_str()) => {
+            Some(attribute.attr.as_str())
+        }
+        _ => None,
+    }
+}
+
+// math.sqrt / np.sqrt / sqrt (from math import sqrt) all name `sqrt`
+fn callee(func: &Expr) -> Option<&str> {
+    match func {
+        Expr::Name(name) => Some(name.id.as_str()),
+        Expr::Attribute(attribute) => library(attribute),
+        _ => None,
+    }
+}
+
+impl TryFrom<ExprCall> for Expression {
+    type Error = PythonError;
+
+    fn try_from(call: ExprCall) -> Result<Self, Self::Error> {
+        if !call.keywords.is_empty() {
+            return Err(PythonError::unsupported("keyword arguments", &call));
         }
+        let Some(name) = callee(&call.func) else {
+            return Err(PythonError::unsupported(
+                "calls to anything but a function name",
+                &call,
+            ));
+        };
+        // Any other math / numpy function has no mtx counterpart
+        let attribute = matches!(call.func.as_ref(), Expr::Attribute(_));
+        let name = match (name, builtins::from_python(name)) {
+            ("sqrt" | "cbrt" | "log", _) => name.to_string(),
+            (_, Some(builtin)) if attribute || builtin.name == name => builtin.name.to_string(),
+            _ if attribute => {
+                return Err(PythonError::unsupported(format!("`{}`", name), &call));
+            }
+            _ => name.to_string(),
+        };
+        let mut args = call
+            .args
+            .iter()
+            .cloned()
+            .map(Expression::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let root = |degree: i64, radicand| {
+            opperation(Root {
+                degree: number(degree),
+                radicand,
+            })
+        };
+        let ln = |x| Expression::FunctionCall("ln".to_string().into(), vec![x]);
+        Ok(match (name.as_str(), args.len()) {
+            ("sqrt", 1) => root(2, args.remove(0)),
+            ("cbrt", 1) => root(3, args.remove(0)),
+            // math.log is the natural logarithm, with a second argument it's log base b
+            ("log", 1) => ln(args.remove(0)),
+            ("log", 2) => {
+                let base = args.remove(1);
+                opperation(Div {
+                    first: ln(args.remove(0)),
+                    second: ln(base),
+                })
+            }
+            _ => Expression::FunctionCall(name.into(), args),
+        })
     }
 }
-impl From<ModModule> for Statement {
-    fn from(value: ModModule) -> Self {
-        match value.body.iter().nth(0) {
-            Some(n) => match n {
-                rustpython_parser::ast::Stmt::FunctionDef(func_def) => {
-                    Statement::Definition(Definition::Function(
-                        func_def.name.clone().into(),
-                        func_def // Implement All Other Arg than just positional args
-                            .args
-                            .posonlyargs
-                            .iter()
-                            .map(|i| i.def.arg.clone().into())
-                            .collect(),
-                        {
-                            // Do Stuff with the body of the function
-                            // Iterate Through Body
-                            // Value of Return Result
-                            match func_def.body.last() {
-                                Some(n) => match n {
-                                    rustpython_parser::ast::Stmt::Return(ret) => {
-                                        Expression::from_return_value(ret.clone())
-                                    }
-                                    _ => todo!("Value MUST be returned..."),
-                                },
-
-                                None => Expression::Empty,
-                            }
-                        },
-                    ))
+
+impl TryFrom<ExprSubscript> for Expression {
+    type Error = PythonError;
+
+    // Python lists start at 0, subscripts at 1: x[0] is x_1 and x[int(i) - 1] is x_i
+    fn try_from(subscript: ExprSubscript) -> Result<Self, Self::Error> {
+        let index = match *subscript.slice {
+            Expr::BinOp(ExprBinOp {
+                op: Operator::Sub,
+                left,
+                right,
+                ..
+            }) if is_one(&right) => match *left {
+                Expr::Call(ExprCall { func, mut args, .. })
+                    if args.len() == 1 && callee(&func) == Some("int") =>
+                {
+                    args.remove(0).try_into()?
                 }
-                // rustpython_parser::ast::Stmt::AsyncFunctionDef(stmt_async_function_def) => todo!(),
-                _ => todo!("Not Implemented Yet"),
+                // x[i - 1] is x_i
+                left => left.try_into()?,
+            },
+            slice => match Expression::try_from(slice)? {
+                Expression::Constant(Value::Number(n)) => number(n + Number::Integer(1)),
+                index => opperation(Add {
+                    terms: vec![index, number(1)],
+                }),
+            },
+        };
+        Ok(opperation(Index {
+            base: (*subscript.value).try_into()?,
+            index,
+        }))
+    }
+}
+
+fn is_one(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Constant(ExprConstant { value: Constant::Int(n), .. }) if n.to_string() == "1"
+    )
+}
+
+impl TryFrom<ExprConstant> for Expression {
+    type Error = PythonError;
+
+    fn try_from(constant: ExprConstant) -> Result<Self, Self::Error> {
+        match &constant.value {
+            // Literals are read from their digits, 0.1 is the fraction 1/10
+            Constant::Int(n) => Ok(number(
+                Number::parse(&n.to_string()).unwrap_or(Number::Float(f64::INFINITY)),
+            )),
+            Constant::Float(n) => Ok(number(float(*n))),
+            // 2j, Python writes the imaginary unit j
+            Constant::Complex { real, imag } => Ok(Expression::Constant(Value::from(
+                Complex::new(float(*real), float(*imag), 'j'),
+            ))),
+            Constant::Str(text) if text.chars().count() == 1 => Ok(Expression::Constant(
+                text.chars().next().unwrap_or_default().into(),
+            )),
+            value => Err(PythonError::unsupported(
+                format!("the constant {:?}", value),
+                &constant,
+            )),
+        }
+    }
+}
+
+impl TryFrom<Expr> for Expression {
+    type Error = PythonError;
+
+    fn try_from(expr: Expr) -> Result<Self, Self::Error> {
+        match expr {
+            Expr::BinOp(binop) => binop.try_into(),
+            Expr::UnaryOp(unary) => unary.try_into(),
+            Expr::Call(call) => call.try_into(),
+            Expr::Subscript(subscript) => subscript.try_into(),
+            Expr::Constant(constant) => constant.try_into(),
+            Expr::Name(ExprName { id, .. }) => Ok(id.into()),
+            // math.pi, np.e
+            Expr::Attribute(attribute) => match library(&attribute) {
+                Some(name @ ("pi" | "e")) => Ok(Expression::VariableRef(name.to_string().into())),
+                _ => Err(PythonError::unsupported(
+                    format!("the attribute `{}`", attribute.attr),
+                    &attribute,
+                )),
             },
-            None => panic!("Empty Mod Module"),
+            Expr::List(list) => Ok(Expression::List(
+                list.elts
+                    .into_iter()
+                    .map(Expression::try_from)
+                    .collect::<Result<_, _>>()?,
+            )),
+            // np.array([1, 2]) is a list
+            expr => Err(PythonError::unsupported(
+                format!("{} expressions", kind(&expr)),
+                &expr,
+            )),
         }
     }
 }
+
 #[cfg(test)]
 mod tests {
 
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
 
+    fn text(definition: Definition) -> String {
+        definition.to_string()
+    }
+
+    #[test]
+    fn test_python_function() {
+        let source = "import math\n\ndef f(x, y):\n    \"\"\"Norm\"\"\"\n    s = x ** 2 + y ** 2\n    return math.sqrt(s) / 2\n";
+        let definitions = parse_python("model.py", source).expect("should convert");
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(
+            text(definitions.into_iter().next().unwrap()),
+            "f(x,y) = sqrt(x^2 + y^2) / 2"
+        );
+    }
+
+    #[test]
+    fn test_python_expressions() {
+        let source = "a = -2 * (b - 1)\nc = [1, 2.5]\nd = c[0] + c[i]\ne = math.log(a, 10) + np.sin(lambda_)\n";
+        let definitions = parse_python("model.py", source)
+            .expect("should convert")
+            .into_iter()
+            .map(text)
+            .collect::<Vec<_>>();
+        assert_eq!(definitions[0], "a = -2 * (b - 1)");
+        assert_eq!(definitions[2], "d = c_1 + c_(i + 1)");
+        assert_eq!(definitions[3], "e = ln(a) / ln(10) + sin(lambda)");
+    }
+
+    #[test]
+    fn test_python_builtins() {
+        let source = "a = np.arcsin(x) + math.floor(x) + abs(x)\nb = np.maximum(a, math.pi)\nc = np.conj(2j) + np.real(0.5j)\n";
+        let definitions = parse_python("model.py", source)
+            .expect("should convert")
+            .into_iter()
+            .map(text)
+            .collect::<Vec<_>>();
+        assert_eq!(definitions[0], "a = asin(x) + floor(x) + abs(x)");
+        assert_eq!(definitions[1], "b = max(a,pi)");
+        assert_eq!(definitions[2], "c = conj(2j) + re((1/2)j)");
+    }
+
     #[test]
-    fn test_python_expression() {}
+    fn test_python_unsupported() {
+        let source = "def f(x):\n    while x:\n        x -= 1\n    return x\n";
+        let error = parse_python("model.py", source).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+        assert_eq!(error.message, "unsupported python: `while`");
+
+        let error = parse_python("model.py", "def f(x):\n    y = x\n").unwrap_err();
+        assert_eq!(error.message, "function `f` has to end with a return");
+
+        let error = parse_python("model.py", "a = 7 % 2\n").unwrap_err();
+        assert_eq!(error.message, "unsupported python: the Mod operator");
+    }
+
+    #[test]
+    fn test_python_syntax_error() {
+        let error = parse_python("model.py", "def f(x)\n    return x\n").unwrap_err();
+        assert_eq!((error.file.as_str(), error.line), ("model.py", 1));
+    }
 }
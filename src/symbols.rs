@@ -1,35 +1,146 @@
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Symbols {
     Addition,
     Subtraction,
     Division,
     Multiplication,
     Summation,
+    Product,
+    // Comparisons
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    // Logic
+    And,
+    Or,
+    Not,
 }
 
+// Variable names written out as greek letters by the exports
+pub(crate) const GREEK: [&str; 24] = [
+    "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "eta", "theta", "iota", "kappa",
+    "lambda", "mu", "nu", "xi", "pi", "rho", "sigma", "tau", "upsilon", "phi", "chi", "psi",
+    "omega", "Omega",
+];
+
 #[derive(Clone)]
 pub(crate) struct Definition {
     pub(crate) id: Symbols,
     pub(crate) value: &'static str,
     pub(crate) optional_value: Option<&'static str>,
+    pub(crate) latex: &'static str,
+    pub(crate) typst: &'static str,
+    pub(crate) mathml: &'static str,
 }
 
-const DEFINITIONS: [Definition; 4] = [Definition {
+const DEFINITIONS: [Definition; 15] = [Definition {
     id: Symbols::Addition,
     value: "+",
     optional_value: Option::None,
+    latex: "+",
+    typst: "+",
+    mathml: "+",
 }, Definition {
     id: Symbols::Subtraction,
     value: "-",
     optional_value: Option::None,
+    latex: "-",
+    typst: "-",
+    mathml: "\u{2212}",
 }, Definition {
     id: Symbols::Division,
     value: "/",
     optional_value: Option::None,
+    latex: "\\div",
+    typst: "div",
+    mathml: "\u{00F7}",
 }, Definition {
     id: Symbols::Multiplication,
     value: "*",
     optional_value: Option::None,
+    latex: "\\cdot",
+    typst: "dot",
+    mathml: "\u{22C5}",
+}, Definition {
+    id: Symbols::Summation,
+    value: "sum",
+    optional_value: Option::Some("\u{03A3}"),
+    latex: "\\sum",
+    typst: "sum",
+    mathml: "\u{2211}",
+}, Definition {
+    id: Symbols::Product,
+    value: "prod",
+    optional_value: Option::Some("\u{220F}"),
+    latex: "\\prod",
+    typst: "product",
+    mathml: "\u{220F}",
+}, Definition {
+    id: Symbols::Less,
+    value: "<",
+    optional_value: Option::None,
+    latex: "<",
+    typst: "<",
+    mathml: "&lt;",
+}, Definition {
+    id: Symbols::LessEqual,
+    value: "<=",
+    optional_value: Option::Some("\u{2264}"),
+    latex: "\\leq",
+    typst: "<=",
+    mathml: "\u{2264}",
+}, Definition {
+    id: Symbols::Greater,
+    value: ">",
+    optional_value: Option::None,
+    latex: ">",
+    typst: ">",
+    mathml: "&gt;",
+}, Definition {
+    id: Symbols::GreaterEqual,
+    value: ">=",
+    optional_value: Option::Some("\u{2265}"),
+    latex: "\\geq",
+    typst: ">=",
+    mathml: "\u{2265}",
+}, Definition {
+    id: Symbols::Equal,
+    value: "=",
+    optional_value: Option::None,
+    latex: "=",
+    typst: "=",
+    mathml: "=",
+}, Definition {
+    id: Symbols::NotEqual,
+    value: "!=",
+    optional_value: Option::Some("\u{2260}"),
+    latex: "\\neq",
+    typst: "!=",
+    mathml: "\u{2260}",
+}, Definition {
+    id: Symbols::And,
+    value: "and",
+    optional_value: Option::Some("\u{2227}"),
+    latex: "\\land",
+    typst: "and",
+    mathml: "\u{2227}",
+}, Definition {
+    id: Symbols::Or,
+    value: "or",
+    optional_value: Option::Some("\u{2228}"),
+    latex: "\\lor",
+    typst: "or",
+    mathml: "\u{2228}",
+}, Definition {
+    id: Symbols::Not,
+    value: "not",
+    optional_value: Option::Some("\u{00AC}"),
+    latex: "\\lnot",
+    typst: "not",
+    mathml: "\u{00AC}",
 }];
 
 impl Symbols {
@@ -50,7 +161,7 @@ impl Symbols {
 }
 impl Symbols {
     pub fn as_str(&self) -> &'static str {
-        // TODO: DIFFERENT EXPORTS (LIKE TYPST)
+        // Source form, exports use as_latex / as_typst / as_mathml
         DEFINITIONS
             .iter()
             .filter_map(|d| {
@@ -65,3 +176,33 @@ impl Symbols {
             .expect(format!("Couldn't Find Matching Symbol").as_str())
     }
 }
+
+impl Symbols {
+    pub fn as_latex(&self) -> &'static str {
+        DEFINITIONS
+            .iter()
+            .find(|d| d.id == *self)
+            .map(|d| d.latex)
+            .expect("Couldn't Find Matching Symbol")
+    }
+}
+
+impl Symbols {
+    pub fn as_typst(&self) -> &'static str {
+        DEFINITIONS
+            .iter()
+            .find(|d| d.id == *self)
+            .map(|d| d.typst)
+            .expect("Couldn't Find Matching Symbol")
+    }
+}
+
+impl Symbols {
+    pub fn as_mathml(&self) -> &'static str {
+        DEFINITIONS
+            .iter()
+            .find(|d| d.id == *self)
+            .map(|d| d.mathml)
+            .expect("Couldn't Find Matching Symbol")
+    }
+}
@@ -1,41 +1,207 @@
+use std::fmt;
+
 use crate::{
-    ast::{Statement, module::Metadata},
-    parser::markdown::{Inline, MarkdownElement},
+    ast::{Comment, Statement, expressions::Expression, system_func::System},
+    eval::Environment,
+    loader::Document,
+    parser::markdown::{Alignment, Inline, ListItem, MarkdownElement, math_statement, parse_prose},
+    writer::display::{
+        mathml::{inline_math, math},
+        plot,
+    },
 };
 
 pub enum Display {
     Equation(Vec<Statement>),
     Markdown(Vec<MarkdownElement>),
+    // The plot statement and its rendered SVG (or error)
+    Plot(Statement, String),
 }
 
 pub struct Displayed(Vec<Display>);
 
+impl Display {
+    // Label used for the container header and the table of contents
+    pub fn title(&self) -> String {
+        match self {
+            Display::Equation(statements) => statements
+                .first()
+                .map(|s| s.to_string())
+                .unwrap_or_default(),
+            Display::Markdown(markdown_elements) => markdown_elements
+                .iter()
+                .find_map(|e| match e {
+                    MarkdownElement::Heading(_, text) => Some(text.clone()),
+                    _ => None,
+                })
+                .unwrap_or_else(|| "Notes".to_string()),
+            Display::Plot(statement, _) => statement.to_string(),
+        }
+    }
+}
+
 impl ToString for Display {
     fn to_string(&self) -> String {
         match self {
-            Display::Equation(statements) => todo!(),
+            Display::Equation(statements) => statements
+                .iter()
+                .map(|s| {
+                    format!(
+                        "<div class=\"snippet mtx bg-gray-800 p-4 rounded border border-gray-700 font-mono text-lg mb-2 text-gray-200\">{}</div>\n<div class=\"snippet math p-4 mb-4 text-xl text-gray-100 overflow-x-auto\">{}</div>",
+                        html_escape(&s.to_string()),
+                        math(s)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
             Display::Markdown(markdown_elements) => markdown_elements
                 .iter()
                 .map(|a| a.to_string())
                 .collect::<Vec<_>>()
                 .join("\n"),
+            Display::Plot(statement, svg) => format!(
+                "<div class=\"snippet mtx bg-gray-800 p-4 rounded border border-gray-700 font-mono text-lg mb-2 text-gray-200\">{}</div>\n<div class=\"snippet plot p-4 mb-4 overflow-x-auto\">{}</div>",
+                html_escape(&statement.to_string()),
+                svg
+            ),
         }
     }
 }
 
+// The SVG for plot(..) statements, evaluated against the document's definitions
+fn plotted(statement: &Statement, env: &Environment) -> Option<String> {
+    let Statement::Expression(Expression::System(system)) = statement else {
+        return None;
+    };
+    let System::Plot(expression, x, a, b) = system.as_ref() else {
+        return None;
+    };
+    Some(match plot::svg(expression, x, a, b, env) {
+        Ok(svg) => svg,
+        Err(error) => format!(
+            "<div class=\"text-red-400 font-mono text-sm\">{}</div>",
+            html_escape(&error.to_string())
+        ),
+    })
+}
+
+fn plots(statements: &[Statement], env: &Environment) -> Vec<Option<String>> {
+    statements
+        .iter()
+        .map(|statement| plotted(statement, env))
+        .collect()
+}
+
+impl Displayed {
+    // The page of a loaded document, plots see its imports and definitions
+    pub fn new(document: Document) -> Self {
+        let plots = plots(&document.statements, &document.environment());
+        Displayed::grouped(document.statements, plots)
+    }
+
+    // Multi line comments are prose (markdown), plots stand on their own and
+    // everything else is grouped into equations
+    fn grouped(statements: Vec<Statement>, plots: Vec<Option<String>>) -> Self {
+        let mut displays = Vec::new();
+        let mut equations = Vec::new();
+
+        for (statement, plot) in statements.into_iter().zip(plots) {
+            let display = match statement {
+                Statement::Comment(Comment::Multi(text)) => Display::Markdown(parse_prose(&text)),
+                // Source comments aren't shown on the page
+                Statement::Comment(Comment::Single(_)) => continue,
+                statement => match plot {
+                    Some(svg) => Display::Plot(statement, svg),
+                    None => {
+                        equations.push(statement);
+                        continue;
+                    }
+                },
+            };
+            if !equations.is_empty() {
+                displays.push(Display::Equation(std::mem::take(&mut equations)));
+            }
+            displays.push(display);
+        }
+        if !equations.is_empty() {
+            displays.push(Display::Equation(equations));
+        }
+
+        Displayed(displays)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Display> {
+        self.0.iter()
+    }
+
+    // Sources of every image in the prose
+    pub fn images(&self) -> Vec<&str> {
+        self.0
+            .iter()
+            .flat_map(|display| match display {
+                Display::Markdown(elements) => elements.iter().flat_map(|e| e.images()).collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    // Sidebar links, one per container
+    pub fn contents(&self) -> String {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(i, d)| {
+                format!(
+                    "<a href=\"#container{}\" class=\"toc-item block px-3 py-2 rounded text-sm text-gray-300 hover:text-gray-100\">{}</a>",
+                    i + 1,
+                    html_escape(&d.title())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl From<Vec<Statement>> for Displayed {
+    fn from(statements: Vec<Statement>) -> Self {
+        let plots = plots(&statements, &Environment::from(statements.as_slice()));
+        Displayed::grouped(statements, plots)
+    }
+}
+
 impl ToString for Displayed {
     fn to_string(&self) -> String {
         self.0
             .iter()
-            .map(|f| f.to_string())
+            .enumerate()
+            .map(|(i, f)| {
+                let n = i + 1;
+                format!(
+                    r#"<div id="container{n}" class="bg-gray-800 pb-2 transition-all duration-300 border border-gray-700 rounded">
+    <div class="p-4 flex items-center justify-between">
+        <span class="text-base font-medium text-gray-200">{title}</span>
+        <button onclick="toggleBox('box{n}', 'container{n}'); event.stopPropagation();" class="text-gray-400 hover:text-gray-200 px-2 py-1">
+            <span id="arrow{n}">▼</span>
+        </button>
+    </div>
+    <div id="box{n}" class="px-4 pb-4">
+        <div class="bg-gray-900 rounded p-6 border border-gray-700">
+{content}
+        </div>
+    </div>
+</div>"#,
+                    title = html_escape(&f.title()),
+                    content = f.to_string()
+                )
+            })
             .collect::<Vec<String>>()
             .join("\n")
     }
 }
 
-impl ToString for Inline {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for Inline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let html = match self {
             Inline::Text(text) => html_escape(text),
             Inline::Bold(text) => {
                 format!("<strong class=\"font-bold\">{}</strong>", html_escape(text))
@@ -50,10 +216,48 @@ impl ToString for Inline {
                 html_escape(url),
                 html_escape(text)
             ),
-        }
+            Inline::Image(alt, src) => format!(
+                "<img src=\"{}\" alt=\"{}\" class=\"inline-block max-w-full rounded border border-gray-700 my-2\" />",
+                html_escape(src),
+                html_escape(alt)
+            ),
+            Inline::Math(text) => match math_statement(text) {
+                Some(statement) => inline_math(&statement),
+                None => Inline::Code(text.clone()).to_string(),
+            },
+        };
+        f.write_str(&html)
     }
 }
 
+fn text(inlines: &[Inline]) -> String {
+    inlines.iter().map(|i| i.to_string()).collect()
+}
+
+// Nested lists sit inside their item and are indented instead of spaced below
+fn list(tag: &str, marker: &str, items: &[ListItem], nested: bool) -> String {
+    let items = items
+        .iter()
+        .map(|item| {
+            let children = item
+                .children
+                .iter()
+                .map(|child| match child {
+                    MarkdownElement::UnorderedList(items) => list("ul", "list-disc", items, true),
+                    MarkdownElement::OrderedList(items) => list("ol", "list-decimal", items, true),
+                    child => child.to_string(),
+                })
+                .collect::<String>();
+            format!("<li class=\"mb-2\">{}{}</li>", text(&item.text), children)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let spacing = if nested { "ml-6 mt-2" } else { "mb-4" };
+    format!(
+        "<{tag} class=\"{marker} list-inside {spacing} space-y-1 text-gray-300\">\n{items}\n</{tag}>"
+    )
+}
+
 impl ToString for MarkdownElement {
     fn to_string(&self) -> String {
         match self {
@@ -75,8 +279,10 @@ impl ToString for MarkdownElement {
                 )
             }
             MarkdownElement::Paragraph(inlines) => {
-                let content = inlines.iter().map(|i| i.to_string()).collect::<String>();
-                format!("<p class=\"mb-4 text-gray-300 leading-7\">{}</p>", content)
+                format!(
+                    "<p class=\"mb-4 text-gray-300 leading-7\">{}</p>",
+                    text(inlines)
+                )
             }
             MarkdownElement::CodeBlock(lang, code) => {
                 let lang_display = if lang.is_empty() {
@@ -93,32 +299,40 @@ impl ToString for MarkdownElement {
                     html_escape(code)
                 )
             }
-            MarkdownElement::UnorderedList(items) => {
-                let items_html = items
-                    .iter()
-                    .map(|item| format!("<li class=\"mb-2\">{}</li>", html_escape(item)))
-                    .collect::<Vec<_>>()
-                    .join("\n");
+            MarkdownElement::UnorderedList(items) => list("ul", "list-disc", items, false),
+            MarkdownElement::OrderedList(items) => list("ol", "list-decimal", items, false),
+            MarkdownElement::Blockquote(inlines) => {
                 format!(
-                    "<ul class=\"list-disc list-inside mb-4 space-y-1 text-gray-300\">\n{}\n</ul>",
-                    items_html
+                    "<blockquote class=\"border-l-4 border-blue-500 pl-4 py-2 mb-4 italic text-gray-400 bg-gray-800/50\">{}</blockquote>",
+                    text(inlines)
                 )
             }
-            MarkdownElement::OrderedList(items) => {
-                let items_html = items
+            MarkdownElement::Table(alignments, header, rows) => {
+                let cells = |tag: &str, classes: &str, row: &[Vec<Inline>]| {
+                    row.iter()
+                        .zip(alignments)
+                        .map(|(cell, alignment)| {
+                            let align = match alignment {
+                                Alignment::Center => "text-center",
+                                Alignment::Right => "text-right",
+                                Alignment::Left | Alignment::None => "text-left",
+                            };
+                            format!(
+                                "<{tag} class=\"px-3 py-2 border border-gray-700 {classes} {align}\">{}</{tag}>",
+                                text(cell)
+                            )
+                        })
+                        .collect::<String>()
+                };
+                let body = rows
                     .iter()
-                    .map(|item| format!("<li class=\"mb-2\">{}</li>", html_escape(item)))
+                    .map(|row| format!("<tr>{}</tr>", cells("td", "", row)))
                     .collect::<Vec<_>>()
                     .join("\n");
                 format!(
-                    "<ol class=\"list-decimal list-inside mb-4 space-y-1 text-gray-300\">\n{}\n</ol>",
-                    items_html
-                )
-            }
-            MarkdownElement::Blockquote(text) => {
-                format!(
-                    "<blockquote class=\"border-l-4 border-blue-500 pl-4 py-2 mb-4 italic text-gray-400 bg-gray-800/50\">{}</blockquote>",
-                    html_escape(text)
+                    "<div class=\"mb-4 overflow-x-auto\"><table class=\"border-collapse text-sm text-gray-300\">\n<thead><tr>{}</tr></thead>\n<tbody>\n{}\n</tbody>\n</table></div>",
+                    cells("th", "bg-gray-800 font-semibold text-gray-100", header),
+                    body
                 )
             }
             MarkdownElement::HorizontalRule => {
@@ -129,7 +343,7 @@ impl ToString for MarkdownElement {
     }
 }
 
-fn html_escape(text: &str) -> String {
+pub fn html_escape(text: &str) -> String {
     text.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -163,4 +377,38 @@ fn main() {}
         println!("{} ==> {:?}", s, result);
         println!("{}", Display::Markdown(result).to_string())
     }
+
+    #[test]
+    fn test_markdown_html_tables_and_lists() {
+        let (_, elements) =
+            parse_markdown("| a | b |\n|---|--:|\n| 1 | 2 |\n\n- **x**\n  - y\n").unwrap();
+        let html = Display::Markdown(elements).to_string();
+        assert!(html.contains("<th class=\"px-3 py-2 border border-gray-700 bg-gray-800 font-semibold text-gray-100 text-right\">b</th>"));
+        assert!(html.contains("<li class=\"mb-2\"><strong class=\"font-bold\">x</strong><ul class=\"list-disc list-inside ml-6 mt-2"));
+    }
+
+    #[test]
+    fn test_displayed_from_statements() {
+        let (_, module) = crate::parser::math::module::parse_module(
+            "/* # Intro\nSome **prose** */\nf(x) = x\nf(1)\n// hidden\n",
+            true,
+            crate::ast::module::ParseMode::Frame,
+        )
+        .expect("parse should work");
+        let (_, statements) = module.into_parts();
+        let displayed = Displayed::from(statements);
+
+        let displays = displayed.iter().collect::<Vec<_>>();
+        assert_eq!(displays.len(), 2);
+        assert!(matches!(displays[0], Display::Markdown(_)));
+        assert!(matches!(displays[1], Display::Equation(s) if s.len() == 2));
+        assert_eq!(displays[0].title(), "Intro");
+
+        let html = displayed.to_string();
+        assert!(html.contains("id=\"container2\""));
+        assert!(html.contains("f(x) = x"));
+        assert!(html.contains("<div class=\"snippet math"));
+        assert!(html.contains("<math display=\"block\">"));
+        assert!(displayed.contents().contains("href=\"#container1\""));
+    }
 }
@@ -0,0 +1,506 @@
+use crate::{
+    ast::{
+        Definition, Statement,
+        base::{Ident, Value},
+        expressions::Expression,
+        opperations::{
+            Add, Compare, Div, Exp, Index, Logic, Multi, Neg, Not, Opperation, Piecewise,
+            Precedence, Root, Series, Sub,
+        },
+        system_func::System,
+    },
+    builtins,
+    calculus::simplify,
+    symbols::Symbols,
+    writer::display::html::html_escape,
+};
+
+// Render as presentation MathML (without the surrounding <math>)
+pub trait Mathml {
+    fn mathml(&self) -> String;
+}
+
+// Fractions, roots and scripts are already grouped visually
+fn precedence(expression: &Expression) -> Precedence {
+    match expression {
+        Expression::Opperations(opperation) if opperation.ty() == "div" => Precedence::Atom,
+        expression => expression.precedence(),
+    }
+}
+
+fn row(content: String) -> String {
+    format!("<mrow>{}</mrow>", content)
+}
+
+fn fenced(open: &str, content: String, close: &str) -> String {
+    row(format!("<mo>{}</mo>{}<mo>{}</mo>", open, content, close))
+}
+
+// MathML form of an operand, in parentheses when it binds looser than `min`
+fn group(expression: &Expression, min: Precedence) -> String {
+    if precedence(expression) < min {
+        fenced("(", expression.mathml(), ")")
+    } else {
+        expression.mathml()
+    }
+}
+
+fn separated(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join("<mo>,</mo>")
+}
+
+fn greek(name: &str) -> Option<char> {
+    let letter = match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "rho" => 'ρ',
+        "sigma" => 'σ',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Omega" => 'Ω',
+        _ => return None,
+    };
+    Some(letter)
+}
+
+// A whole statement as a block <math> element
+pub fn math(statement: &Statement) -> String {
+    format!("<math display=\"block\">{}</math>", row(statement.mathml()))
+}
+
+// A statement in the flow of text ($...$ in prose)
+pub fn inline_math(statement: &Statement) -> String {
+    format!("<math>{}</math>", row(statement.mathml()))
+}
+
+impl Mathml for Ident {
+    fn mathml(&self) -> String {
+        match greek(&self.inner) {
+            Some(letter) => format!("<mi>{}</mi>", letter),
+            None if self.inner.chars().count() > 1 => {
+                format!(
+                    "<mi mathvariant=\"normal\">{}</mi>",
+                    html_escape(&self.inner)
+                )
+            }
+            None => format!("<mi>{}</mi>", html_escape(&self.inner)),
+        }
+    }
+}
+
+impl Mathml for Value {
+    fn mathml(&self) -> String {
+        match self {
+            Value::Char(_) => format!("<mtext>{}</mtext>", html_escape(&self.to_string())),
+            Value::Number(n) if *n < 0.0 => row(format!(
+                "<mo>{}</mo>{}",
+                Symbols::Subtraction.as_mathml(),
+                Value::Number(-n.clone()).mathml()
+            )),
+            Value::Number(n) => match n.fraction() {
+                Some((n, d)) => format!("<mfrac><mn>{}</mn><mn>{}</mn></mfrac>", n, d),
+                None => format!("<mn>{}</mn>", n),
+            },
+            Value::Complex(z) => row(z.render(
+                |n| Value::Number(n).mathml(),
+                &format!("<mi>{}</mi>", z.unit),
+                &format!("<mo>{}</mo>", Symbols::Addition.as_mathml()),
+                &format!("<mo>{}</mo>", Symbols::Subtraction.as_mathml()),
+            )),
+            Value::Bool(b) => format!("<mtext>{}</mtext>", b),
+            Value::List(values) => fenced("[", separated(values.iter().map(|v| v.mathml())), "]"),
+        }
+    }
+}
+
+impl Mathml for Expression {
+    fn mathml(&self) -> String {
+        match self {
+            Expression::Opperations(opperation) => opperation.mathml(),
+            Expression::List(items) => {
+                fenced("[", separated(items.iter().map(|i| i.mathml())), "]")
+            }
+            // U+2061 is the invisible function application operator
+            Expression::FunctionCall(ident, args) => {
+                match (ident.inner.as_str(), args.as_slice()) {
+                    ("abs", [u]) => fenced("|", u.mathml(), "|"),
+                    ("floor", [u]) => fenced("\u{230A}", u.mathml(), "\u{230B}"),
+                    ("ceil", [u]) => fenced("\u{2308}", u.mathml(), "\u{2309}"),
+                    ("factorial", [u]) => row(format!("{}<mo>!</mo>", group(u, Precedence::Atom))),
+                    ("conj", [u]) => format!("<mover>{}<mo>&#x203E;</mo></mover>", row(u.mathml())),
+                    ("sum", [u]) => {
+                        row(format!("<mo>&#x2211;</mo>{}", fenced("(", u.mathml(), ")")))
+                    }
+                    (name, _) => {
+                        // asin is written arcsin
+                        let name = match builtins::lookup(name).map(|b| b.latex.strip_prefix('\\'))
+                        {
+                            Some(Some(name)) => format!("<mi mathvariant=\"normal\">{}</mi>", name),
+                            _ => ident.mathml(),
+                        };
+                        row(format!(
+                            "{}<mo>&#x2061;</mo>{}",
+                            name,
+                            fenced("(", separated(args.iter().map(|a| a.mathml())), ")")
+                        ))
+                    }
+                }
+            }
+            Expression::System(system) => system.mathml(),
+            Expression::VariableRef(ident) => ident.mathml(),
+            Expression::Constant(value) => value.mathml(),
+            Expression::Empty => "<mrow></mrow>".to_string(),
+        }
+    }
+}
+
+impl Mathml for System {
+    fn mathml(&self) -> String {
+        match self {
+            System::Print(expression) => expression.mathml(),
+            System::Simplify(expression) => simplify(expression).mathml(),
+            System::Diff(expression, x) => row(format!(
+                "<mfrac><mi mathvariant=\"normal\">d</mi><mrow><mi mathvariant=\"normal\">d</mi>{}</mrow></mfrac>{}",
+                x.mathml(),
+                group(expression, Precedence::Atom)
+            )),
+            System::Import(_)
+            | System::Frame(_)
+            | System::Data(..)
+            | System::Json(..)
+            | System::Plot(..) => {
+                format!("<mtext>{}</mtext>", html_escape(&self.to_string()))
+            }
+        }
+    }
+}
+
+impl Mathml for Add {
+    fn mathml(&self) -> String {
+        row(self
+            .terms
+            .iter()
+            .map(|t| group(t, Precedence::Sum))
+            .collect::<Vec<_>>()
+            .join(&format!("<mo>{}</mo>", Symbols::Addition.as_mathml())))
+    }
+}
+
+impl Mathml for Sub {
+    fn mathml(&self) -> String {
+        row(format!(
+            "{}<mo>{}</mo>{}",
+            group(&self.first, Precedence::Sum),
+            Symbols::Subtraction.as_mathml(),
+            group(&self.second, Precedence::Product)
+        ))
+    }
+}
+
+impl Mathml for Multi {
+    fn mathml(&self) -> String {
+        row(self
+            .terms
+            .iter()
+            .map(|t| group(t, Precedence::Product))
+            .collect::<Vec<_>>()
+            .join(&format!("<mo>{}</mo>", Symbols::Multiplication.as_mathml())))
+    }
+}
+
+impl Mathml for Div {
+    fn mathml(&self) -> String {
+        format!(
+            "<mfrac>{}{}</mfrac>",
+            row(self.first.mathml()),
+            row(self.second.mathml())
+        )
+    }
+}
+
+impl Mathml for Neg {
+    fn mathml(&self) -> String {
+        row(format!(
+            "<mo>{}</mo>{}",
+            Symbols::Subtraction.as_mathml(),
+            group(&self.value, Precedence::Prefix)
+        ))
+    }
+}
+
+impl Mathml for Exp {
+    fn mathml(&self) -> String {
+        // A fraction still needs parentheses as a base
+        let base = if self.base.precedence() < Precedence::Atom {
+            fenced("(", self.base.mathml(), ")")
+        } else {
+            self.base.mathml()
+        };
+        format!("<msup>{}{}</msup>", row(base), row(self.exponent.mathml()))
+    }
+}
+
+impl Mathml for Root {
+    fn mathml(&self) -> String {
+        match &self.degree {
+            Expression::Constant(Value::Number(n)) if *n == 2.0 => {
+                format!("<msqrt>{}</msqrt>", self.radicand.mathml())
+            }
+            // <mroot> takes the radicand first, then the degree
+            degree => format!(
+                "<mroot>{}{}</mroot>",
+                row(self.radicand.mathml()),
+                row(degree.mathml())
+            ),
+        }
+    }
+}
+
+impl Mathml for Index {
+    fn mathml(&self) -> String {
+        format!(
+            "<msub>{}{}</msub>",
+            row(group(&self.base, Precedence::Atom)),
+            row(self.index.mathml())
+        )
+    }
+}
+
+impl Mathml for Series {
+    fn mathml(&self) -> String {
+        row(format!(
+            "<munderover><mo>{}</mo>{}{}</munderover>{}",
+            self.symbol.as_mathml(),
+            row(format!(
+                "{}<mo>=</mo>{}",
+                self.index.mathml(),
+                self.from.mathml()
+            )),
+            row(self.to.mathml()),
+            group(&self.body, Precedence::Product)
+        ))
+    }
+}
+
+impl Mathml for Compare {
+    fn mathml(&self) -> String {
+        row(self
+            .rest
+            .iter()
+            .fold(group(&self.first, Precedence::Sum), |acc, (symbol, e)| {
+                format!(
+                    "{}<mo>{}</mo>{}",
+                    acc,
+                    symbol.as_mathml(),
+                    group(e, Precedence::Sum)
+                )
+            }))
+    }
+}
+
+impl Mathml for Logic {
+    fn mathml(&self) -> String {
+        row(self
+            .terms
+            .iter()
+            .map(|t| group(t, self.precedence()))
+            .collect::<Vec<_>>()
+            .join(&format!("<mo>{}</mo>", self.symbol.as_mathml())))
+    }
+}
+
+impl Mathml for Not {
+    fn mathml(&self) -> String {
+        row(format!(
+            "<mo>{}</mo>{}",
+            Symbols::Not.as_mathml(),
+            group(&self.value, Precedence::Not)
+        ))
+    }
+}
+
+impl Mathml for Piecewise {
+    // A brace in front of a table, one row per case with the condition on the right
+    fn mathml(&self) -> String {
+        let mut rows = self
+            .cases
+            .iter()
+            .map(|(value, condition)| {
+                format!(
+                    "<mtr><mtd>{}</mtd><mtd><mtext>if&#xA0;</mtext>{}</mtd></mtr>",
+                    value.mathml(),
+                    condition.mathml()
+                )
+            })
+            .collect::<Vec<_>>();
+        if let Some(otherwise) = &self.otherwise {
+            rows.push(format!(
+                "<mtr><mtd>{}</mtd><mtd><mtext>otherwise</mtext></mtd></mtr>",
+                otherwise.mathml()
+            ));
+        }
+        row(format!(
+            "<mo>{{</mo><mtable columnalign=\"left left\">{}</mtable>",
+            rows.concat()
+        ))
+    }
+}
+
+impl Mathml for Definition {
+    fn mathml(&self) -> String {
+        match self {
+            Definition::Function(ident, vars, expression) => row(format!(
+                "{}<mo>&#x2061;</mo>{}<mo>=</mo>{}",
+                ident.mathml(),
+                fenced("(", separated(vars.iter().map(|v| v.mathml())), ")"),
+                expression.mathml()
+            )),
+            Definition::Constant(ident, expression) => row(format!(
+                "{}<mo>=</mo>{}",
+                ident.mathml(),
+                expression.mathml()
+            )),
+        }
+    }
+}
+
+impl Mathml for Statement {
+    fn mathml(&self) -> String {
+        match self {
+            Statement::Definition(definition) => definition.mathml(),
+            Statement::Expression(expression) => expression.mathml(),
+            Statement::Comment(comment) => {
+                format!("<mtext>{}</mtext>", html_escape(&comment.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{
+        ast::{complex::Complex, number::Number},
+        parser::math::{expression::pexp, statement::parse_statement},
+    };
+
+    fn mathml(input: &str) -> String {
+        let (rest, expression) = pexp(input).expect("should parse");
+        assert!(rest.is_empty(), "unparsed: {:?}", rest);
+        expression.mathml()
+    }
+
+    #[test]
+    fn mathml_fraction() {
+        assert_eq!(
+            mathml("(a + 1) / b"),
+            "<mfrac><mrow><mrow><mi>a</mi><mo>+</mo><mn>1</mn></mrow></mrow><mrow><mi>b</mi></mrow></mfrac>"
+        );
+        assert_eq!(mathml("0.25"), "<mfrac><mn>1</mn><mn>4</mn></mfrac>");
+    }
+
+    #[test]
+    fn mathml_roots() {
+        assert_eq!(mathml("sqrt(x)"), "<msqrt><mi>x</mi></msqrt>");
+        assert_eq!(
+            mathml("root(3, x)"),
+            "<mroot><mrow><mi>x</mi></mrow><mrow><mn>3</mn></mrow></mroot>"
+        );
+    }
+
+    #[test]
+    fn mathml_builtins() {
+        assert_eq!(
+            mathml("abs(x)"),
+            "<mrow><mo>|</mo><mi>x</mi><mo>|</mo></mrow>"
+        );
+        assert_eq!(mathml("factorial(n)"), "<mrow><mi>n</mi><mo>!</mo></mrow>");
+        assert_eq!(
+            mathml("sum(T)"),
+            "<mrow><mo>&#x2211;</mo><mrow><mo>(</mo><mi>T</mi><mo>)</mo></mrow></mrow>"
+        );
+        assert_eq!(
+            mathml("atan(x)"),
+            "<mrow><mi mathvariant=\"normal\">arctan</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow>"
+        );
+    }
+
+    #[test]
+    fn mathml_complex() {
+        let z = Complex::new(Number::Integer(1), Number::Integer(-1), 'j');
+        assert_eq!(
+            Value::from(z).mathml(),
+            "<mrow><mn>1</mn><mo>\u{2212}</mo><mi>j</mi></mrow>"
+        );
+        assert_eq!(
+            mathml("conj(z)"),
+            "<mover><mrow><mi>z</mi></mrow><mo>&#x203E;</mo></mover>"
+        );
+    }
+
+    #[test]
+    fn mathml_series() {
+        assert_eq!(
+            mathml("sum(i, 1, n, i)"),
+            "<mrow><munderover><mo>\u{2211}</mo><mrow><mi>i</mi><mo>=</mo><mn>1</mn></mrow><mrow><mi>n</mi></mrow></munderover><mi>i</mi></mrow>"
+        );
+    }
+
+    #[test]
+    fn mathml_piecewise() {
+        assert_eq!(
+            mathml("{ 1 if x < 0; x otherwise }"),
+            "<mrow><mo>{</mo><mtable columnalign=\"left left\"><mtr><mtd><mn>1</mn></mtd><mtd><mtext>if&#xA0;</mtext><mrow><mi>x</mi><mo>&lt;</mo><mn>0</mn></mrow></mtd></mtr><mtr><mtd><mi>x</mi></mtd><mtd><mtext>otherwise</mtext></mtd></mtr></mtable></mrow>"
+        );
+        assert_eq!(
+            mathml("not x ≥ 1"),
+            "<mrow><mo>\u{00AC}</mo><mrow><mi>x</mi><mo>\u{2265}</mo><mn>1</mn></mrow></mrow>"
+        );
+    }
+
+    #[test]
+    fn mathml_scripts() {
+        assert_eq!(
+            mathml("x^2"),
+            "<msup><mrow><mi>x</mi></mrow><mrow><mn>2</mn></mrow></msup>"
+        );
+        assert!(mathml("(a - b)^2").starts_with("<msup><mrow><mrow><mo>(</mo>"));
+        assert!(mathml("x_(i+1)").starts_with("<msub><mrow><mi>x</mi></mrow>"));
+    }
+
+    #[test]
+    fn mathml_identifiers() {
+        let (_, statement) = parse_statement("theta = 2x").unwrap();
+        assert!(statement.mathml().starts_with("<mrow><mi>θ</mi><mo>=</mo>"));
+        assert_eq!(
+            mathml("mean(x)"),
+            "<mrow><mi mathvariant=\"normal\">mean</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow>"
+        );
+    }
+
+    #[test]
+    fn mathml_statement() {
+        let (_, statement) = parse_statement("f(x) = -x").unwrap();
+        let math = math(&statement);
+        assert!(math.starts_with("<math display=\"block\">"));
+        assert!(math.contains("<mo>=</mo><mrow><mo>\u{2212}</mo><mi>x</mi></mrow>"));
+    }
+}
@@ -34,5 +34,7 @@
 * */
 
 pub mod html;
+pub mod mathml;
+pub mod plot;
 
 use crate::ast::Statement;
@@ -0,0 +1,400 @@
+use crate::{
+    ast::{
+        Definition,
+        base::{Ident, Value},
+        expressions::Expression,
+    },
+    eval::{Binding, Environment, EvalError},
+    writer::display::html::html_escape,
+};
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 400.0;
+// Room for the tick labels on the left and below, and the legend above
+const LEFT: f64 = 56.0;
+const RIGHT: f64 = 16.0;
+const TOP: f64 = 32.0;
+const BOTTOM: f64 = 40.0;
+const SAMPLES: usize = 400;
+
+// Tailwind 400 shades, readable on the page's dark background
+const COLORS: [&str; 6] = [
+    "#60a5fa", "#f87171", "#34d399", "#fbbf24", "#a78bfa", "#f472b6",
+];
+
+// One sampled curve, None where the function has no (finite) value
+struct Series {
+    label: String,
+    points: Vec<(f64, Option<f64>)>,
+}
+
+fn number(value: Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => Some(n.to_f64()).filter(|v| v.is_finite()),
+        _ => None,
+    }
+}
+
+// plot(f, x, ..) plots f(x), anything else is an expression in x
+fn series(expression: &Expression, x: &Ident, env: &Environment) -> Expression {
+    match expression {
+        Expression::VariableRef(ident)
+            if matches!(
+                env.lookup(&ident.inner),
+                Some(Binding::Definition(_, Definition::Function(..)))
+            ) =>
+        {
+            Expression::FunctionCall(ident.clone(), vec![Expression::VariableRef(x.clone())])
+        }
+        expression => expression.clone(),
+    }
+}
+
+// Points where the expression fails are gaps, failing everywhere (an undefined name) is an error
+fn sample(
+    expression: &Expression,
+    x: &Ident,
+    a: f64,
+    b: f64,
+    env: &Environment,
+) -> Result<Series, EvalError> {
+    let expression = series(expression, x, env);
+    let mut first_error = None;
+    let points = (0..=SAMPLES)
+        .map(|i| {
+            let t = a + (b - a) * i as f64 / SAMPLES as f64;
+            let mut scope = env.child();
+            scope.bind(x, Value::from(t));
+            match scope.evaluate(&expression) {
+                Ok(value) => (t, number(value)),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                    (t, None)
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    if let Some(error) = first_error
+        && points.iter().all(|(_, y)| y.is_none())
+    {
+        return Err(error);
+    }
+    Ok(Series {
+        label: expression.to_string(),
+        points,
+    })
+}
+
+// Vertical range of the curves, the outer 2% of samples are left out so poles don't flatten the plot
+fn range(series: &[Series]) -> (f64, f64) {
+    let mut ys = series
+        .iter()
+        .flat_map(|s| s.points.iter().filter_map(|(_, y)| *y))
+        .collect::<Vec<_>>();
+    if ys.is_empty() {
+        return (-1.0, 1.0);
+    }
+    ys.sort_by(f64::total_cmp);
+    let cut = ys.len() / 50;
+    let (low, high) = (ys[cut], ys[ys.len() - 1 - cut]);
+    // Flat relative to the size of the values, padded by the same scale
+    let scale = low.abs().max(high.abs());
+    if high - low <= scale * f64::EPSILON * 16.0 {
+        let pad = if scale > 0.0 { scale * 0.1 } else { 1.0 };
+        return (low - pad, high + pad);
+    }
+    let pad = (high - low) * 0.05;
+    (low - pad, high + pad)
+}
+
+// Round step (1, 2 or 5 times a power of ten) giving about `count` ticks
+fn step(low: f64, high: f64, count: f64) -> f64 {
+    let raw = (high - low) / count;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .find(|m| m * magnitude >= raw)
+        .unwrap_or(10.0);
+    step * magnitude
+}
+
+pub fn ticks(low: f64, high: f64) -> Vec<f64> {
+    let step = step(low, high, 6.0);
+    let first = (low / step).ceil() as i64;
+    let last = (high / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+fn label(value: f64, low: f64, high: f64) -> String {
+    let step = step(low, high, 6.0);
+    // -0 (or rounding noise around it) would show up next to the origin
+    let value = if value.abs() < step * 1e-6 {
+        0.0
+    } else {
+        value
+    };
+    // Huge values would run off the left margin
+    if step >= 1e6 {
+        return format!("{:e}", value);
+    }
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    format!("{:.*}", decimals, value)
+}
+
+// Path data for a series, split where it has no value, leaves the view or jumps
+// by more than the visible height (a pole, like tan at pi/2)
+fn path(
+    series: &Series,
+    to_x: impl Fn(f64) -> f64,
+    to_y: impl Fn(f64) -> f64,
+    (low, high): (f64, f64),
+) -> String {
+    let span = high - low;
+    let mut d = Vec::new();
+    let mut previous: Option<f64> = None;
+
+    for (x, y) in &series.points {
+        let y = y.filter(|y| *y >= low - span && *y <= high + span);
+        match (previous, y) {
+            (Some(p), Some(y)) if (y - p).abs() <= span => {
+                d.push(format!("L{:.1} {:.1}", to_x(*x), to_y(y)))
+            }
+            (_, Some(y)) => d.push(format!("M{:.1} {:.1}", to_x(*x), to_y(y))),
+            (_, None) => {}
+        }
+        previous = y;
+    }
+    d.join(" ")
+}
+
+// Inline SVG line chart of each series over a <= x <= b
+pub fn svg(
+    expression: &Expression,
+    x: &Ident,
+    a: &Expression,
+    b: &Expression,
+    env: &Environment,
+) -> Result<String, EvalError> {
+    let bound = |e: &Expression| -> Result<f64, EvalError> {
+        number(env.evaluate(e)?).ok_or_else(|| {
+            EvalError::Type(format!("plot bound `{}` is not a number", e.to_string()))
+        })
+    };
+    let (a, b) = (bound(a)?, bound(b)?);
+    if a >= b {
+        return Err(EvalError::Domain(format!(
+            "plot range {} to {} is empty",
+            a, b
+        )));
+    }
+
+    let curves = match expression {
+        Expression::List(items) => items.iter().collect(),
+        expression => vec![expression],
+    };
+    let series = curves
+        .into_iter()
+        .map(|curve| sample(curve, x, a, b, env))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (low, high) = range(&series);
+    let to_x = |v: f64| LEFT + (v - a) / (b - a) * (WIDTH - LEFT - RIGHT);
+    let to_y = |v: f64| HEIGHT - BOTTOM - (v - low) / (high - low) * (HEIGHT - TOP - BOTTOM);
+
+    let mut out = vec![format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"100%\" role=\"img\" font-family=\"monospace\" font-size=\"11\">",
+        w = WIDTH,
+        h = HEIGHT
+    )];
+
+    // Grid lines and tick labels
+    for t in ticks(a, b) {
+        out.push(format!(
+            "<line x1=\"{x:.1}\" y1=\"{}\" x2=\"{x:.1}\" y2=\"{}\" stroke=\"#374151\"/><text x=\"{x:.1}\" y=\"{}\" fill=\"#9ca3af\" text-anchor=\"middle\">{}</text>",
+            TOP,
+            HEIGHT - BOTTOM,
+            HEIGHT - BOTTOM + 16.0,
+            label(t, a, b),
+            x = to_x(t)
+        ));
+    }
+    for t in ticks(low, high) {
+        out.push(format!(
+            "<line x1=\"{}\" y1=\"{y:.1}\" x2=\"{}\" y2=\"{y:.1}\" stroke=\"#374151\"/><text x=\"{}\" y=\"{y:.1}\" fill=\"#9ca3af\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>",
+            LEFT,
+            WIDTH - RIGHT,
+            LEFT - 6.0,
+            label(t, low, high),
+            y = to_y(t)
+        ));
+    }
+
+    // Axes through the origin when it's in view, the frame otherwise
+    let x_axis = if low <= 0.0 && 0.0 <= high {
+        to_y(0.0)
+    } else {
+        HEIGHT - BOTTOM
+    };
+    let y_axis = if a <= 0.0 && 0.0 <= b {
+        to_x(0.0)
+    } else {
+        LEFT
+    };
+    out.push(format!(
+        "<line x1=\"{}\" y1=\"{x_axis:.1}\" x2=\"{}\" y2=\"{x_axis:.1}\" stroke=\"#9ca3af\"/><line x1=\"{y_axis:.1}\" y1=\"{}\" x2=\"{y_axis:.1}\" y2=\"{}\" stroke=\"#9ca3af\"/>",
+        LEFT,
+        WIDTH - RIGHT,
+        TOP,
+        HEIGHT - BOTTOM
+    ));
+    out.push(format!(
+        "<text x=\"{}\" y=\"{}\" fill=\"#d1d5db\" text-anchor=\"end\">{}</text>",
+        WIDTH - RIGHT,
+        HEIGHT - 6.0,
+        html_escape(&x.inner)
+    ));
+
+    // Curves and their legend, the nested <svg> clips the curves to the plot area
+    out.push(format!(
+        "<svg x=\"{x}\" y=\"{y}\" width=\"{w}\" height=\"{h}\" viewBox=\"{x} {y} {w} {h}\">",
+        x = LEFT,
+        y = TOP,
+        w = WIDTH - LEFT - RIGHT,
+        h = HEIGHT - TOP - BOTTOM
+    ));
+    for (i, s) in series.iter().enumerate() {
+        out.push(format!(
+            "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>",
+            path(s, to_x, to_y, (low, high)),
+            COLORS[i % COLORS.len()]
+        ));
+    }
+    out.push("</svg>".to_string());
+    for (i, s) in series.iter().enumerate() {
+        let x = LEFT + 8.0 + i as f64 * 120.0;
+        out.push(format!(
+            "<line x1=\"{x:.1}\" y1=\"14\" x2=\"{:.1}\" y2=\"14\" stroke=\"{}\" stroke-width=\"2\"/><text x=\"{:.1}\" y=\"14\" fill=\"#d1d5db\" dominant-baseline=\"middle\">{}</text>",
+            x + 16.0,
+            COLORS[i % COLORS.len()],
+            x + 22.0,
+            html_escape(&s.label)
+        ));
+    }
+
+    out.push("</svg>".to_string());
+    Ok(out.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{
+        ast::Statement,
+        parser::math::{expression::pexp, statement::parse_statement},
+    };
+
+    fn definition(input: &str) -> Definition {
+        match parse_statement(input).unwrap().1 {
+            Statement::Definition(definition) => definition,
+            _ => panic!("expected a definition"),
+        }
+    }
+
+    fn expression(input: &str) -> Expression {
+        pexp(input).unwrap().1
+    }
+
+    #[test]
+    fn test_ticks() {
+        assert_eq!(ticks(0.0, 10.0), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert_eq!(ticks(-1.0, 1.0).len(), 5);
+        assert_eq!(label(0.5, 0.0, 1.0), "0.5");
+    }
+
+    #[test]
+    fn test_plot_breaks_at_poles_and_gaps() {
+        let f = definition("f(x) = 1 / x");
+        let g = definition("g(x) = sqrt(x)");
+        let mut env = Environment::new();
+        env.define(&f);
+        env.define(&g);
+        let x = Ident::from("x".to_string());
+
+        // 1/x is split at 0 (a new M after the first one)
+        let pole = sample(&expression("f"), &x, -1.0, 1.0, &env).unwrap();
+        assert_eq!(pole.label, "f(x)");
+        let d = path(&pole, |v| v, |v| v, range(std::slice::from_ref(&pole)));
+        assert_eq!(d.matches('M').count(), 2);
+
+        // sqrt has no value below 0, the path starts at 0
+        let gap = sample(&expression("g(x)"), &x, -1.0, 1.0, &env).unwrap();
+        assert!(gap.points[..SAMPLES / 2].iter().all(|(_, y)| y.is_none()));
+        assert!(
+            gap.points[SAMPLES / 2 + 1..]
+                .iter()
+                .all(|(_, y)| y.is_some())
+        );
+
+        // Nothing to plot at all is the error of the first sample
+        assert_eq!(
+            sample(&expression("h(x)"), &x, -1.0, 1.0, &env).err(),
+            Some(EvalError::UnknownFunction("h".to_string()))
+        );
+        assert_eq!(
+            sample(&expression("h + x"), &x, -1.0, 1.0, &env).err(),
+            Some(EvalError::UnboundVariable("h".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_plot_svg() {
+        let f = definition("f(x) = x^2");
+        let mut env = Environment::new();
+        env.define(&f);
+        let svg = svg(
+            &expression("[f(x), 2x]"),
+            &Ident::from("x".to_string()),
+            &expression("0"),
+            &expression("2"),
+            &env,
+        )
+        .unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<path").count(), 2);
+        assert!(svg.contains(">f(x)</text>"));
+
+        let error = super::svg(
+            &expression("f"),
+            &Ident::from("x".to_string()),
+            &expression("1"),
+            &expression("0"),
+            &env,
+        );
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn test_plot_extreme_ranges() {
+        let env = Environment::new();
+        let x = Ident::from("x".to_string());
+        let plot = |f: &str, a: &str, b: &str| {
+            super::svg(&expression(f), &x, &expression(a), &expression(b), &env)
+        };
+
+        // exp(200) overflows f32 but not f64
+        let svg = plot("exp(x)", "0", "200").unwrap();
+        assert!(!svg.contains("NaN") && !svg.contains("inf"));
+
+        // Bounds that are one apart at 1e8 are still a range
+        let svg = plot("x", "100000000", "100000001").unwrap();
+        assert!(svg.contains(">100000000.0</text>"));
+
+        // A tiny range gets its own ticks, not a flat -1 to 1
+        let svg = plot("x", "0", "0.0000000001").unwrap();
+        assert!(svg.contains(">0.00000000010</text>"));
+        let series = sample(&expression("x"), &x, 0.0, 1e-10, &env).unwrap();
+        let (low, high) = range(std::slice::from_ref(&series));
+        assert!(low > -1e-9 && high < 1e-9);
+    }
+}
@@ -0,0 +1,622 @@
+use crate::{
+    ast::{
+        Comment, Definition, Statement,
+        base::{Ident, Value},
+        expressions::Expression,
+        module::Metadata,
+        opperations::{
+            Add, Compare, Div, Exp, Index, Logic, Multi, Neg, Not, Opperation, Piecewise,
+            Precedence, Root, Series, Sub,
+        },
+        system_func::System,
+    },
+    builtins,
+    calculus::simplify,
+    parser::markdown::{Alignment, Inline, ListItem, MarkdownElement, math_statement, parse_prose},
+    symbols::{GREEK, Symbols},
+};
+
+// Render as LaTeX math (without the surrounding $ or \[ \])
+pub trait Latex {
+    fn latex(&self) -> String;
+}
+
+// Fractions, roots and subscripts are already grouped visually
+fn precedence(expression: &Expression) -> Precedence {
+    match expression {
+        Expression::Opperations(opperation) if opperation.ty() == "div" => Precedence::Atom,
+        expression => expression.precedence(),
+    }
+}
+
+// LaTeX form of an operand, in parentheses when it binds looser than `min`
+fn group(expression: &Expression, min: Precedence) -> String {
+    if precedence(expression) < min {
+        format!("\\left({}\\right)", expression.latex())
+    } else {
+        expression.latex()
+    }
+}
+
+// Plain text inside a document (outside of math mode)
+pub fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".to_string(),
+            '~' => "\\textasciitilde{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{}", c),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+impl Latex for Ident {
+    fn latex(&self) -> String {
+        if GREEK.contains(&self.inner.as_str()) {
+            format!("\\{}", self.inner)
+        } else if self.inner.chars().count() > 1 {
+            format!("\\mathrm{{{}}}", self.inner)
+        } else {
+            self.inner.clone()
+        }
+    }
+}
+
+impl Latex for Value {
+    fn latex(&self) -> String {
+        match self {
+            Value::Char(_) => format!("\\text{{{}}}", escape(&self.to_string())),
+            Value::Number(n) => match n.fraction() {
+                Some((n, d)) if n < 0 => format!("-\\frac{{{}}}{{{}}}", -n, d),
+                Some((n, d)) => format!("\\frac{{{}}}{{{}}}", n, d),
+                None => n.to_string(),
+            },
+            Value::Complex(z) => z.render(
+                |n| Value::Number(n).latex(),
+                &z.unit.to_string(),
+                " + ",
+                " - ",
+            ),
+            Value::Bool(b) => format!("\\text{{{}}}", b),
+            Value::List(values) => format!(
+                "\\left[{}\\right]",
+                values
+                    .iter()
+                    .map(|v| v.latex())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+fn arguments(args: &[Expression]) -> String {
+    format!(
+        "\\left({}\\right)",
+        args.iter()
+            .map(|a| a.latex())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+// \sin\left(x\right), |x|, \lfloor x \rfloor and n!
+fn builtin(name: &str, args: &[Expression]) -> Option<String> {
+    let builtin = builtins::lookup(name)?;
+    Some(match (name, args) {
+        ("abs", [u]) => format!("\\left|{}\\right|", u.latex()),
+        ("floor", [u]) => format!("\\left\\lfloor {} \\right\\rfloor", u.latex()),
+        ("ceil", [u]) => format!("\\left\\lceil {} \\right\\rceil", u.latex()),
+        ("factorial", [u]) => format!("{}!", group(u, Precedence::Atom)),
+        ("conj", [u]) => format!("\\overline{{{}}}", u.latex()),
+        ("mean", _) => format!("\\operatorname{{mean}}{}", arguments(args)),
+        _ if !builtin.latex.is_empty() => format!("{}{}", builtin.latex, arguments(args)),
+        _ => return None,
+    })
+}
+
+impl Latex for Expression {
+    fn latex(&self) -> String {
+        match self {
+            Expression::Opperations(opperation) => opperation.latex(),
+            Expression::List(items) => format!(
+                "\\left[{}\\right]",
+                items
+                    .iter()
+                    .map(|i| i.latex())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Expression::FunctionCall(ident, args) => builtin(&ident.inner, args)
+                .unwrap_or_else(|| format!("{}{}", ident.latex(), arguments(args))),
+            Expression::System(system) => system.latex(),
+            Expression::VariableRef(ident) => ident.latex(),
+            Expression::Constant(value) => value.latex(),
+            Expression::Empty => String::new(),
+        }
+    }
+}
+
+impl Latex for System {
+    fn latex(&self) -> String {
+        match self {
+            System::Print(expression) => expression.latex(),
+            System::Simplify(expression) => simplify(expression).latex(),
+            System::Diff(expression, x) => format!(
+                "\\frac{{d}}{{d{}}} {}",
+                x.latex(),
+                group(expression, Precedence::Atom)
+            ),
+            System::Import(_)
+            | System::Frame(_)
+            | System::Data(..)
+            | System::Json(..)
+            | System::Plot(..) => {
+                format!("\\texttt{{{}}}", escape(&self.to_string()))
+            }
+        }
+    }
+}
+
+impl Latex for Add {
+    fn latex(&self) -> String {
+        self.terms
+            .iter()
+            .map(|t| group(t, Precedence::Sum))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", Symbols::Addition.as_latex()))
+    }
+}
+
+impl Latex for Sub {
+    fn latex(&self) -> String {
+        format!(
+            "{} {} {}",
+            group(&self.first, Precedence::Sum),
+            Symbols::Subtraction.as_latex(),
+            group(&self.second, Precedence::Product)
+        )
+    }
+}
+
+impl Latex for Multi {
+    fn latex(&self) -> String {
+        self.terms
+            .iter()
+            .map(|t| group(t, Precedence::Product))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", Symbols::Multiplication.as_latex()))
+    }
+}
+
+impl Latex for Div {
+    fn latex(&self) -> String {
+        format!(
+            "\\frac{{{}}}{{{}}}",
+            self.first.latex(),
+            self.second.latex()
+        )
+    }
+}
+
+impl Latex for Neg {
+    fn latex(&self) -> String {
+        format!(
+            "{}{}",
+            Symbols::Subtraction.as_latex(),
+            group(&self.value, Precedence::Prefix)
+        )
+    }
+}
+
+impl Latex for Exp {
+    fn latex(&self) -> String {
+        // A fraction still needs parentheses as a base
+        let base = if self.base.precedence() < Precedence::Atom {
+            format!("\\left({}\\right)", self.base.latex())
+        } else {
+            self.base.latex()
+        };
+        format!("{}^{{{}}}", base, self.exponent.latex())
+    }
+}
+
+impl Latex for Root {
+    fn latex(&self) -> String {
+        match &self.degree {
+            Expression::Constant(Value::Number(n)) if *n == 2.0 => {
+                format!("\\sqrt{{{}}}", self.radicand.latex())
+            }
+            degree => format!("\\sqrt[{}]{{{}}}", degree.latex(), self.radicand.latex()),
+        }
+    }
+}
+
+impl Latex for Index {
+    fn latex(&self) -> String {
+        format!(
+            "{}_{{{}}}",
+            group(&self.base, Precedence::Atom),
+            self.index.latex()
+        )
+    }
+}
+
+impl Latex for Series {
+    fn latex(&self) -> String {
+        format!(
+            "{}_{{{} = {}}}^{{{}}} {}",
+            self.symbol.as_latex(),
+            self.index.latex(),
+            self.from.latex(),
+            self.to.latex(),
+            group(&self.body, Precedence::Product)
+        )
+    }
+}
+
+impl Latex for Compare {
+    fn latex(&self) -> String {
+        self.rest
+            .iter()
+            .fold(group(&self.first, Precedence::Sum), |acc, (symbol, e)| {
+                format!(
+                    "{} {} {}",
+                    acc,
+                    symbol.as_latex(),
+                    group(e, Precedence::Sum)
+                )
+            })
+    }
+}
+
+impl Latex for Logic {
+    fn latex(&self) -> String {
+        self.terms
+            .iter()
+            .map(|t| group(t, self.precedence()))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", self.symbol.as_latex()))
+    }
+}
+
+impl Latex for Not {
+    fn latex(&self) -> String {
+        format!(
+            "{} {}",
+            Symbols::Not.as_latex(),
+            group(&self.value, Precedence::Not)
+        )
+    }
+}
+
+impl Latex for Piecewise {
+    // \begin{cases} x & \text{if } x > 0 \\ 0 & \text{otherwise} \end{cases}
+    fn latex(&self) -> String {
+        let mut rows = self
+            .cases
+            .iter()
+            .map(|(value, condition)| {
+                format!("{} & \\text{{if }} {}", value.latex(), condition.latex())
+            })
+            .collect::<Vec<_>>();
+        if let Some(otherwise) = &self.otherwise {
+            rows.push(format!("{} & \\text{{otherwise}}", otherwise.latex()));
+        }
+        format!("\\begin{{cases}} {} \\end{{cases}}", rows.join(" \\\\ "))
+    }
+}
+
+impl Latex for Definition {
+    fn latex(&self) -> String {
+        match self {
+            Definition::Function(ident, vars, expression) => {
+                let vars = vars.iter().map(|v| v.latex()).collect::<Vec<_>>();
+                format!(
+                    "{}\\left({}\\right) = {}",
+                    ident.latex(),
+                    vars.join(", "),
+                    expression.latex()
+                )
+            }
+            Definition::Constant(ident, expression) => {
+                format!("{} = {}", ident.latex(), expression.latex())
+            }
+        }
+    }
+}
+
+impl Latex for Statement {
+    fn latex(&self) -> String {
+        match self {
+            Statement::Definition(definition) => definition.latex(),
+            Statement::Expression(expression) => expression.latex(),
+            Statement::Comment(comment) => format!("\\text{{{}}}", escape(&comment.to_string())),
+        }
+    }
+}
+
+impl Latex for Inline {
+    fn latex(&self) -> String {
+        match self {
+            Inline::Text(text) => escape(text),
+            Inline::Bold(text) => format!("\\textbf{{{}}}", escape(text)),
+            Inline::Italic(text) => format!("\\emph{{{}}}", escape(text)),
+            Inline::Code(text) => format!("\\texttt{{{}}}", escape(text)),
+            Inline::Link(text, url) => format!("\\href{{{}}}{{{}}}", escape(url), escape(text)),
+            Inline::Image(_, src) => format!("\\includegraphics[width=\\linewidth]{{{}}}", src),
+            Inline::Math(text) => match math_statement(text) {
+                Some(statement) => format!("${}$", statement.latex()),
+                None => format!("\\texttt{{{}}}", escape(text)),
+            },
+        }
+    }
+}
+
+fn text(inlines: &[Inline]) -> String {
+    inlines.iter().map(|i| i.latex()).collect()
+}
+
+// Nested lists are environments inside their item
+fn items(environment: &str, items: &[ListItem]) -> String {
+    let items = items
+        .iter()
+        .map(|item| {
+            let children = item
+                .children
+                .iter()
+                .map(|child| format!("\n{}", child.latex()))
+                .collect::<String>();
+            format!("  \\item {}{}", text(&item.text), children)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "\\begin{{{}}}\n{}\n\\end{{{}}}",
+        environment, items, environment
+    )
+}
+
+// Prose (outside of math mode)
+impl Latex for MarkdownElement {
+    fn latex(&self) -> String {
+        match self {
+            MarkdownElement::Heading(level, text) => {
+                let command = match level {
+                    1 => "section",
+                    2 => "subsection",
+                    3 => "subsubsection",
+                    _ => "paragraph",
+                };
+                format!("\\{}*{{{}}}", command, escape(text))
+            }
+            MarkdownElement::Paragraph(inlines) => text(inlines),
+            MarkdownElement::CodeBlock(_, code) => {
+                format!("\\begin{{verbatim}}\n{}\\end{{verbatim}}", code)
+            }
+            MarkdownElement::UnorderedList(list) => items("itemize", list),
+            MarkdownElement::OrderedList(list) => items("enumerate", list),
+            MarkdownElement::Blockquote(inlines) => {
+                format!("\\begin{{quote}}\n{}\n\\end{{quote}}", text(inlines))
+            }
+            MarkdownElement::Table(alignments, header, rows) => {
+                let columns = alignments
+                    .iter()
+                    .map(|alignment| match alignment {
+                        Alignment::Center => 'c',
+                        Alignment::Right => 'r',
+                        Alignment::Left | Alignment::None => 'l',
+                    })
+                    .collect::<String>();
+                let row = |cells: &[Vec<Inline>]| {
+                    cells
+                        .iter()
+                        .map(|cell| text(cell))
+                        .collect::<Vec<_>>()
+                        .join(" & ")
+                        + " \\\\"
+                };
+                let rows = rows.iter().map(|r| row(r)).collect::<Vec<_>>().join("\n");
+                format!(
+                    "\\begin{{tabular}}{{{}}}\n{}\n\\hline\n{}\n\\end{{tabular}}",
+                    columns,
+                    row(header),
+                    rows
+                )
+            }
+            MarkdownElement::HorizontalRule => "\\noindent\\rule{\\textwidth}{0.4pt}".to_string(),
+            MarkdownElement::LineBreak => String::new(),
+        }
+    }
+}
+
+// Standalone .tex document, prose comments become paragraphs and statements display math
+pub fn document(metadata: &Metadata, statements: &[Statement]) -> String {
+    let mut out = vec![
+        "\\documentclass{article}".to_string(),
+        "\\usepackage{amsmath}".to_string(),
+        "\\usepackage{graphicx}".to_string(),
+        "\\usepackage{hyperref}".to_string(),
+        String::new(),
+    ];
+
+    if let Some(title) = &metadata.title {
+        out.push(format!("\\title{{{}}}", escape(title)));
+    }
+    match (&metadata.author, &metadata.author_email) {
+        (Some(author), Some(email)) => out.push(format!(
+            "\\author{{{} \\\\ \\texttt{{{}}}}}",
+            escape(author),
+            escape(email)
+        )),
+        (Some(author), None) => out.push(format!("\\author{{{}}}", escape(author))),
+        (None, Some(email)) => out.push(format!("\\author{{\\texttt{{{}}}}}", escape(email))),
+        (None, None) => (),
+    }
+    out.push("\\date{}".to_string());
+    out.push(String::new());
+    out.push("\\begin{document}".to_string());
+    if metadata.title.is_some() {
+        out.push("\\maketitle".to_string());
+    }
+    out.push(String::new());
+
+    for statement in statements {
+        match statement {
+            Statement::Comment(Comment::Multi(text)) => {
+                for element in parse_prose(text) {
+                    out.push(element.latex());
+                    out.push(String::new());
+                }
+            }
+            Statement::Comment(Comment::Single(text)) => out.push(format!("% {}", text.trim())),
+            statement => out.push(format!("\\[ {} \\]", statement.latex())),
+        }
+    }
+
+    out.push(String::new());
+    out.push("\\end{document}".to_string());
+    out.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{
+        ast::{complex::Complex, number::Number},
+        parser::math::{expression::pexp, statement::parse_statement},
+    };
+
+    fn latex(input: &str) -> String {
+        let (rest, expression) = pexp(input).expect("should parse");
+        assert!(rest.is_empty(), "unparsed: {:?}", rest);
+        expression.latex()
+    }
+
+    #[test]
+    fn latex_fraction_and_product() {
+        assert_eq!(latex("(a + 1) / 2b"), "\\frac{a + 1}{2} \\cdot b");
+        assert_eq!(latex("2(a + 2)"), "2 \\cdot \\left(a + 2\\right)");
+    }
+
+    #[test]
+    fn latex_exact_fractions() {
+        let fraction = |n, d| Expression::Constant(Value::from(Number::Rational(n, d)));
+        assert_eq!(fraction(1, 3).latex(), "\\frac{1}{3}");
+        assert_eq!(fraction(-2, 3).latex(), "-\\frac{2}{3}");
+        assert_eq!(fraction(1, 4).latex(), "\\frac{1}{4}");
+    }
+
+    #[test]
+    fn latex_complex() {
+        let z = |re, im| Value::from(Complex::new(re, im, 'i')).latex();
+        assert_eq!(z(Number::Integer(3), Number::Integer(-4)), "3 - 4i");
+        assert_eq!(
+            z(Number::Integer(0), Number::Rational(1, 3)),
+            "\\frac{1}{3}i"
+        );
+        assert_eq!(
+            latex("conj(z) + re(z)"),
+            "\\overline{z} + \\Re\\left(z\\right)"
+        );
+    }
+
+    #[test]
+    fn latex_roots() {
+        assert_eq!(latex("sqrt(x + 1)"), "\\sqrt{x + 1}");
+        assert_eq!(latex("root(3, x)"), "\\sqrt[3]{x}");
+    }
+
+    #[test]
+    fn latex_powers_and_subscripts() {
+        assert_eq!(latex("x^(n + 1)"), "x^{n + 1}");
+        assert_eq!(latex("(x + 1)^2"), "\\left(x + 1\\right)^{2}");
+        assert_eq!(latex("(a/b)^2"), "\\left(\\frac{a}{b}\\right)^{2}");
+        assert_eq!(latex("x_(i+1)"), "x_{i + 1}");
+    }
+
+    #[test]
+    fn latex_parentheses_only_where_needed() {
+        assert_eq!(latex("a - (b + c)"), "a - \\left(b + c\\right)");
+        assert_eq!(latex("a + (b * c)"), "a + b \\cdot c");
+        assert_eq!(latex("-x^2"), "-x^{2}");
+        assert_eq!(latex("-(x + 1)"), "-\\left(x + 1\\right)");
+    }
+
+    #[test]
+    fn latex_builtins() {
+        assert_eq!(latex("sin(x)"), "\\sin\\left(x\\right)");
+        assert_eq!(latex("asin(x)"), "\\arcsin\\left(x\\right)");
+        assert_eq!(latex("abs(x - 1)"), "\\left|x - 1\\right|");
+        assert_eq!(latex("floor(x)"), "\\left\\lfloor x \\right\\rfloor");
+        assert_eq!(latex("factorial(n + 1)"), "\\left(n + 1\\right)!");
+        assert_eq!(latex("max(a, 2pi)"), "\\max\\left(a, 2 \\cdot \\pi\\right)");
+        assert_eq!(latex("sum(T)"), "\\sum\\left(T\\right)");
+        assert_eq!(latex("mean(T)"), "\\operatorname{mean}\\left(T\\right)");
+    }
+
+    #[test]
+    fn latex_series() {
+        assert_eq!(latex("sum(i, 1, n, i^2)"), "\\sum_{i = 1}^{n} i^{2}");
+        assert_eq!(
+            latex("prod(k, 1, n, k + 1)"),
+            "\\prod_{k = 1}^{n} \\left(k + 1\\right)"
+        );
+    }
+
+    #[test]
+    fn latex_piecewise() {
+        assert_eq!(
+            latex("{ x if x > 0; 0 otherwise }"),
+            "\\begin{cases} x & \\text{if } x > 0 \\\\ 0 & \\text{otherwise} \\end{cases}"
+        );
+        assert_eq!(
+            latex("0 < x <= 1 and not x = 1/2"),
+            "0 < x \\leq 1 \\land \\lnot x = \\frac{1}{2}"
+        );
+        assert_eq!(latex("(x != 1) + 1"), "\\left(x \\neq 1\\right) + 1");
+    }
+
+    #[test]
+    fn latex_definitions() {
+        let (_, statement) = parse_statement("f(x, y) = x^2 / y").unwrap();
+        assert_eq!(statement.latex(), "f\\left(x, y\\right) = \\frac{x^{2}}{y}");
+        let (_, statement) = parse_statement("theta = 2x").unwrap();
+        assert_eq!(statement.latex(), "\\theta = 2 \\cdot x");
+    }
+
+    #[test]
+    fn latex_simplify_shows_result() {
+        assert_eq!(latex("simplify(2(a + 2) + 2)"), "2 \\cdot a + 6");
+    }
+
+    #[test]
+    fn latex_derivative() {
+        assert_eq!(
+            latex("diff(x^2 + 1, x)"),
+            "\\frac{d}{dx} \\left(x^{2} + 1\\right)"
+        );
+    }
+
+    #[test]
+    fn latex_document() {
+        let metadata = Metadata {
+            title: Some("Notes & Sums".to_string()),
+            author: Some("piderking".to_string()),
+            ..Default::default()
+        };
+        let (_, prose) = parse_statement("/* ## Intro\n**bold** 100% */").unwrap();
+        let (_, statement) = parse_statement("f(x) = x").unwrap();
+        let document = document(&metadata, &[prose, statement]);
+
+        assert!(document.starts_with("\\documentclass{article}"));
+        assert!(document.contains("\\title{Notes \\& Sums}"));
+        assert!(document.contains("\\author{piderking}"));
+        assert!(document.contains("\\subsection*{Intro}"));
+        assert!(document.contains("\\textbf{bold} 100\\%"));
+        assert!(document.contains("\\[ f\\left(x\\right) = x \\]"));
+        assert!(document.trim_end().ends_with("\\end{document}"));
+    }
+}
@@ -1,2 +1,5 @@
+pub mod latex;
+pub mod python;
 pub mod text;
+pub mod typst;
 pub mod display;
\ No newline at end of file
@@ -0,0 +1,753 @@
+use crate::{
+    ast::{
+        Comment, Definition, Statement,
+        base::{Ident, Value},
+        expressions::Expression,
+        number::Number,
+        opperations::{
+            Add, Compare, Div, Exp, Index, Logic, Multi, Neg, Not, Opperation, Piecewise,
+            Precedence, Root, Series, Sub,
+        },
+        system_func::System,
+    },
+    builtins,
+    calculus::{diff_in, simplify},
+    eval::{Binding, Environment},
+    loader::Document,
+    symbols::Symbols,
+};
+
+// Module providing roots and built-in functions in the generated code
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Library {
+    Math,
+    Numpy,
+}
+
+impl Library {
+    fn import(&self) -> &'static str {
+        match self {
+            Library::Math => "import math",
+            Library::Numpy => "import numpy as np",
+        }
+    }
+
+    fn prefix(&self) -> &'static str {
+        match self {
+            Library::Math => "math",
+            Library::Numpy => "np",
+        }
+    }
+}
+
+// What cmath has of math, for complex mode without numpy
+const CMATH: [&str; 12] = [
+    "sqrt", "exp", "log", "sin", "cos", "tan", "asin", "acos", "atan", "pi", "e", "nan",
+];
+
+// What the generated code can refer to, the definitions decide call vs implicit product
+pub struct Target<'a> {
+    pub library: Library,
+    pub env: Environment<'a>,
+}
+
+impl Target<'_> {
+    // Complex mode without numpy, math functions would fail on complex numbers
+    fn cmath(&self) -> bool {
+        self.library == Library::Math && self.env.imaginary().is_some()
+    }
+
+    // cmath lacks floor, gcd, factorial and prod, so math stays imported beside it
+    fn import(&self) -> String {
+        if self.cmath() {
+            "import cmath\nimport math".to_string()
+        } else {
+            self.library.import().to_string()
+        }
+    }
+
+    fn function(&self, name: &str) -> String {
+        if self.cmath() && CMATH.contains(&name) {
+            format!("cmath.{}", name)
+        } else {
+            format!("{}.{}", self.library.prefix(), name)
+        }
+    }
+
+    // A built-in's Python call, math.sin is cmath.sin in complex mode
+    fn builtin(&self, call: &str) -> String {
+        match call.strip_prefix("math.") {
+            Some(name) => self.function(name),
+            None => call.to_string(),
+        }
+    }
+
+    fn is_function(&self, name: &str) -> bool {
+        matches!(
+            self.env.lookup(name),
+            Some(Binding::Definition(_, Definition::Function(..)))
+        )
+    }
+}
+
+// Render as a Python expression (or statement for definitions)
+pub trait Python {
+    fn python(&self, target: &Target) -> String;
+}
+
+pub(crate) const KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+// Python form of an operand, in parentheses when it binds looser than `min`
+fn group(expression: &Expression, min: Precedence, target: &Target) -> String {
+    if expression.precedence() < min {
+        format!("({})", expression.python(target))
+    } else {
+        expression.python(target)
+    }
+}
+
+fn separated<'e>(items: impl Iterator<Item = &'e Expression>, target: &Target) -> String {
+    items
+        .map(|i| i.python(target))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Python for Ident {
+    // Names that are Python keywords (lambda) get a trailing underscore
+    fn python(&self, _: &Target) -> String {
+        if KEYWORDS.contains(&self.inner.as_str()) {
+            format!("{}_", self.inner)
+        } else {
+            self.inner.clone()
+        }
+    }
+}
+
+impl Python for Value {
+    fn python(&self, target: &Target) -> String {
+        match self {
+            Value::Char(_) => format!("{:?}", self.to_string()),
+            Value::Number(n) => match n.fraction() {
+                Some((n, d)) => format!("{} / {}", n, d),
+                None => n.to_string(),
+            },
+            Value::Complex(z) => format!(
+                "complex({}, {})",
+                Value::Number(z.re.clone()).python(target),
+                Value::Number(z.im.clone()).python(target)
+            ),
+            Value::Bool(b) => if *b { "True" } else { "False" }.to_string(),
+            Value::List(values) => {
+                let values = values
+                    .iter()
+                    .map(|v| v.python(target))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match target.library {
+                    Library::Math => format!("[{}]", values),
+                    Library::Numpy => format!("np.array([{}])", values),
+                }
+            }
+        }
+    }
+}
+
+impl Python for Expression {
+    fn python(&self, target: &Target) -> String {
+        match self {
+            Expression::Opperations(opperation) => opperation.python(target),
+            Expression::List(items) => match target.library {
+                Library::Math => format!("[{}]", separated(items.iter(), target)),
+                Library::Numpy => format!("np.array([{}])", separated(items.iter(), target)),
+            },
+            Expression::FunctionCall(ident, args) => {
+                let name = ident.inner.as_str();
+                if target.is_function(name) {
+                    format!(
+                        "{}({})",
+                        ident.python(target),
+                        separated(args.iter(), target)
+                    )
+                } else if let Some(builtin) = builtins::lookup(name)
+                    && target.env.lookup(name).is_none()
+                {
+                    match (target.library, args.as_slice()) {
+                        // Complex numbers and the mean without numpy
+                        (Library::Math, [z]) if builtin.math.is_empty() => {
+                            let z = group(z, Precedence::Atom, target);
+                            match name {
+                                "mean" => format!("(sum({}) / len({}))", z, z),
+                                "conj" => format!("{}.conjugate()", z),
+                                "arg" if target.cmath() => format!("cmath.phase({})", z),
+                                "arg" => format!("math.atan2({}.imag, {}.real)", z, z),
+                                "re" => format!("{}.real", z),
+                                _ => format!("{}.imag", z),
+                            }
+                        }
+                        (Library::Math, _) => format!(
+                            "{}({})",
+                            target.builtin(builtin.math),
+                            separated(args.iter(), target)
+                        ),
+                        // numpy has no factorial
+                        (Library::Numpy, _) if builtin.numpy.is_empty() => format!(
+                            "np.prod(np.arange(1, {} + 1))",
+                            separated(args.iter(), target)
+                        ),
+                        (Library::Numpy, _) => format!(
+                            "{}({})",
+                            builtin.numpy,
+                            separated(args.iter(), target)
+                        ),
+                    }
+                } else if args.len() == 1 {
+                    // x(y+1) when x isn't a function is implicit multiplication
+                    format!(
+                        "{} * {}",
+                        Expression::VariableRef(ident.clone()).python(target),
+                        group(&args[0], Precedence::Product, target)
+                    )
+                } else {
+                    format!(
+                        "{}({})",
+                        ident.python(target),
+                        separated(args.iter(), target)
+                    )
+                }
+            }
+            Expression::System(system) => system.python(target),
+            // pi and e come from the library unless the document defines them
+            Expression::VariableRef(ident)
+                if builtins::constant(&ident.inner).is_some()
+                    && target.env.lookup(&ident.inner).is_none() =>
+            {
+                target.function(&ident.inner)
+            }
+            // The imaginary unit in complex mode
+            Expression::VariableRef(ident)
+                if target.env.imaginary().is_some_and(|unit| ident.inner == unit.to_string())
+                    && target.env.lookup(&ident.inner).is_none() =>
+            {
+                "1j".to_string()
+            }
+            Expression::VariableRef(ident) => ident.python(target),
+            Expression::Constant(value) => value.python(target),
+            Expression::Empty => "0".to_string(),
+        }
+    }
+}
+
+impl Python for System {
+    fn python(&self, target: &Target) -> String {
+        match self {
+            System::Print(expression) => expression.python(target),
+            System::Simplify(expression) => simplify(expression).python(target),
+            // Derivatives are taken here, one of an unknown function stays a diff(..) call
+            System::Diff(expression, x) => match diff_in(expression, x, &target.env) {
+                Ok(derivative) => simplify(&derivative).python(target),
+                Err(_) => format!("diff({}, {:?})", expression.python(target), x.inner),
+            },
+            // The module doesn't read the file, the data is written out
+            System::Data(..) | System::Json(..) => match target
+                .env
+                .evaluate(&Expression::System(Box::new(self.clone())))
+            {
+                Ok(value) => value.python(target),
+                Err(error) => format!("None  # {}", error),
+            },
+            System::Import(_) | System::Frame(_) | System::Plot(..) => format!("None  # {}", self.to_string()),
+        }
+    }
+}
+
+impl Python for Add {
+    fn python(&self, target: &Target) -> String {
+        self.terms
+            .iter()
+            .map(|t| group(t, Precedence::Sum, target))
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+}
+
+impl Python for Sub {
+    fn python(&self, target: &Target) -> String {
+        format!(
+            "{} - {}",
+            group(&self.first, Precedence::Sum, target),
+            group(&self.second, Precedence::Product, target)
+        )
+    }
+}
+
+impl Python for Multi {
+    fn python(&self, target: &Target) -> String {
+        self.terms
+            .iter()
+            .map(|t| group(t, Precedence::Product, target))
+            .collect::<Vec<_>>()
+            .join(" * ")
+    }
+}
+
+impl Python for Div {
+    // Unlike the math writers, a / b / c needs the right operand grouped
+    fn python(&self, target: &Target) -> String {
+        format!(
+            "{} / {}",
+            group(&self.first, Precedence::Product, target),
+            group(&self.second, Precedence::Prefix, target)
+        )
+    }
+}
+
+impl Python for Neg {
+    fn python(&self, target: &Target) -> String {
+        format!("-{}", group(&self.value, Precedence::Prefix, target))
+    }
+}
+
+impl Python for Exp {
+    fn python(&self, target: &Target) -> String {
+        format!(
+            "{} ** {}",
+            group(&self.base, Precedence::Atom, target),
+            group(&self.exponent, Precedence::Power, target)
+        )
+    }
+}
+
+impl Python for Root {
+    fn python(&self, target: &Target) -> String {
+        match &self.degree {
+            Expression::Constant(Value::Number(n)) if *n == 2.0 => {
+                format!(
+                    "{}({})",
+                    target.function("sqrt"),
+                    self.radicand.python(target)
+                )
+            }
+            // cmath has no cbrt, ** takes the principal root
+            Expression::Constant(Value::Number(n)) if *n == 3.0 && !target.cmath() => {
+                format!(
+                    "{}({})",
+                    target.function("cbrt"),
+                    self.radicand.python(target)
+                )
+            }
+            degree => format!(
+                "{} ** (1 / {})",
+                group(&self.radicand, Precedence::Atom, target),
+                group(degree, Precedence::Prefix, target)
+            ),
+        }
+    }
+}
+
+impl Python for Index {
+    // Subscripts start at 1, Python lists at 0
+    fn python(&self, target: &Target) -> String {
+        let index = match &self.index {
+            Expression::Constant(Value::Number(n)) => (n.clone() - Number::Integer(1)).to_string(),
+            index => format!("int({}) - 1", index.python(target)),
+        };
+        format!("{}[{}]", group(&self.base, Precedence::Atom, target), index)
+    }
+}
+
+impl Python for Series {
+    // A generator over range(from, to + 1), bounds that aren't literals go through int()
+    fn python(&self, target: &Target) -> String {
+        let bound = |expression: &Expression| match expression {
+            Expression::Constant(Value::Number(n)) => n.to_string(),
+            expression => format!("int({})", expression.python(target)),
+        };
+        let generator = format!(
+            "{} for {} in range({}, {} + 1)",
+            self.body.python(target),
+            self.index.python(target),
+            bound(&self.from),
+            bound(&self.to)
+        );
+        match (&self.symbol, target.library) {
+            (Symbols::Product, Library::Math) => format!("math.prod({})", generator),
+            // np.prod doesn't take a generator
+            (Symbols::Product, Library::Numpy) => format!("np.prod([{}])", generator),
+            _ => format!("sum({})", generator),
+        }
+    }
+}
+
+impl Python for Compare {
+    // Python chains comparisons itself, with numpy arrays each link is and-ed
+    fn python(&self, target: &Target) -> String {
+        let operator = |symbol: &Symbols| match symbol {
+            Symbols::Equal => "==",
+            symbol => symbol.as_str(),
+        };
+        let mut left = &self.first;
+        let mut links = Vec::new();
+        for (symbol, right) in &self.rest {
+            links.push(format!(
+                "{} {} {}",
+                group(left, Precedence::Sum, target),
+                operator(symbol),
+                group(right, Precedence::Sum, target)
+            ));
+            left = right;
+        }
+        match target.library {
+            Library::Numpy if links.len() > 1 => links
+                .iter()
+                .map(|link| format!("({})", link))
+                .collect::<Vec<_>>()
+                .join(" & "),
+            _ => self.rest.iter().fold(
+                group(&self.first, Precedence::Sum, target),
+                |acc, (symbol, e)| {
+                    format!(
+                        "{} {} {}",
+                        acc,
+                        operator(symbol),
+                        group(e, Precedence::Sum, target)
+                    )
+                },
+            ),
+        }
+    }
+}
+
+impl Python for Logic {
+    // numpy arrays take & and |, which bind tighter than comparisons
+    fn python(&self, target: &Target) -> String {
+        let (operator, min) = match (target.library, &self.symbol) {
+            (Library::Math, symbol) => (symbol.as_str(), self.precedence()),
+            (Library::Numpy, Symbols::Or) => ("|", Precedence::Atom),
+            (Library::Numpy, _) => ("&", Precedence::Atom),
+        };
+        self.terms
+            .iter()
+            .map(|t| group(t, min, target))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", operator))
+    }
+}
+
+impl Python for Not {
+    fn python(&self, target: &Target) -> String {
+        match target.library {
+            Library::Math => format!("not {}", group(&self.value, Precedence::Not, target)),
+            Library::Numpy => format!("np.logical_not({})", self.value.python(target)),
+        }
+    }
+}
+
+impl Python for Piecewise {
+    // A conditional expression (np.select for arrays), nan where no case holds
+    fn python(&self, target: &Target) -> String {
+        let otherwise = match &self.otherwise {
+            Some(otherwise) => otherwise.python(target),
+            None => target.function("nan"),
+        };
+        match target.library {
+            Library::Math => format!(
+                "({})",
+                self.cases
+                    .iter()
+                    .rev()
+                    .fold(otherwise, |acc, (value, condition)| {
+                        format!(
+                            "{} if {} else {}",
+                            value.python(target),
+                            condition.python(target),
+                            acc
+                        )
+                    })
+            ),
+            Library::Numpy => format!(
+                "np.select([{}], [{}], {})",
+                separated(self.cases.iter().map(|(_, condition)| condition), target),
+                separated(self.cases.iter().map(|(value, _)| value), target),
+                otherwise
+            ),
+        }
+    }
+}
+
+impl Python for Definition {
+    fn python(&self, target: &Target) -> String {
+        match self {
+            Definition::Function(ident, vars, expression) => {
+                // Parameters shadow the library constants (f(e) = e^2)
+                let mut body = Target {
+                    library: target.library,
+                    env: target.env.child(),
+                };
+                for var in vars {
+                    body.env.bind(var, Value::from(0));
+                }
+                format!(
+                    "def {}({}):\n    return {}",
+                    ident.python(target),
+                    vars.iter()
+                        .map(|v| v.python(target))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    expression.python(&body)
+                )
+            }
+            Definition::Constant(ident, expression) => {
+                format!("{} = {}", ident.python(target), expression.python(target))
+            }
+        }
+    }
+}
+
+fn docstring(document: &Document) -> Option<String> {
+    let metadata = &document.metadata;
+    let mut lines = Vec::new();
+    if let Some(title) = &metadata.title {
+        lines.push(title.clone());
+    }
+    let byline = [&metadata.author, &metadata.author_email]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+    if !byline.is_empty() {
+        lines.push(byline.join(", "));
+    }
+    if let Some(url) = &metadata.url {
+        lines.push(url.clone());
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    let text = lines
+        .join("\n\n")
+        .replace('\\', "\\\\")
+        .replace("\"\"\"", "\\\"\\\"\\\"");
+    Some(format!("\"\"\"{}\n\"\"\"", text))
+}
+
+fn is_function(definition: &Definition) -> bool {
+    matches!(definition, Definition::Function(..))
+}
+
+// Importable .py module, imported definitions first, comments kept and bare expressions dropped
+pub fn document(document: &Document, library: Library) -> String {
+    let target = Target {
+        library,
+        env: document.environment(),
+    };
+    let mut out = Vec::new();
+
+    if let Some(docstring) = docstring(document) {
+        out.push(docstring);
+        out.push(String::new());
+    }
+    out.push(target.import());
+
+    // Each definition with the comments above it, functions are set apart by two blank lines
+    let mut blocks = Vec::new();
+    let mut comments = Vec::new();
+    for definition in &document.imports {
+        blocks.push((vec![definition.python(&target)], is_function(definition)));
+    }
+    for statement in &document.statements {
+        match statement {
+            Statement::Definition(definition) => {
+                comments.push(definition.python(&target));
+                blocks.push((std::mem::take(&mut comments), is_function(definition)));
+            }
+            Statement::Comment(Comment::Single(text)) => {
+                comments.push(format!("# {}", text.trim()))
+            }
+            Statement::Comment(Comment::Multi(text)) => comments.extend(
+                text.trim()
+                    .lines()
+                    .map(|line| format!("# {}", line).trim_end().to_string()),
+            ),
+            Statement::Expression(_) => {}
+        }
+    }
+    if !comments.is_empty() {
+        blocks.push((comments, false));
+    }
+
+    let mut previous = None;
+    for (lines, function) in blocks {
+        let blank = match previous {
+            _ if function => 2,
+            Some(true) => 2,
+            None => 1,
+            Some(false) => 0,
+        };
+        out.extend(std::iter::repeat_n(String::new(), blank));
+        out.extend(lines);
+        previous = Some(function);
+    }
+
+    out.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{
+        ast::module::ParseMode,
+        parser::math::{expression::pexp, module::parse_module, statement::parse_statement},
+    };
+    use rustpython_parser::{Mode, parse};
+
+    fn python(input: &str) -> String {
+        let (rest, expression) = pexp(input).expect("should parse");
+        assert!(rest.is_empty(), "unparsed: {:?}", rest);
+        expression.python(&Target {
+            library: Library::Math,
+            env: Environment::new(),
+        })
+    }
+
+    fn module(source: &str, library: Library) -> String {
+        let (_, module) = parse_module(source, true, ParseMode::Frame).expect("should parse");
+        let (metadata, statements) = module.into_parts();
+        let source = document(
+            &Document {
+                metadata,
+                statements,
+                imports: Vec::new(),
+                sources: Vec::new(),
+            },
+            library,
+        );
+        assert!(
+            parse(&source, Mode::Module, "<test>").is_ok(),
+            "invalid python:\n{}",
+            source
+        );
+        source
+    }
+
+    #[test]
+    fn python_opperations() {
+        assert_eq!(python("(a + 1) / b"), "(a + 1) / b");
+        assert_eq!(python("a / (b * c)"), "a / (b * c)");
+        assert_eq!(python("-x^2"), "-x ** 2");
+        assert_eq!(python("(a - b)^(n + 1)"), "(a - b) ** (n + 1)");
+    }
+
+    #[test]
+    fn python_roots_and_builtins() {
+        assert_eq!(python("sqrt(x)"), "math.sqrt(x)");
+        assert_eq!(python("root(3, x)"), "math.cbrt(x)");
+        assert_eq!(python("root(n, x + 1)"), "(x + 1) ** (1 / n)");
+        assert_eq!(python("ln(x) + sin(x)"), "math.log(x) + math.sin(x)");
+    }
+
+    #[test]
+    fn python_builtins_and_constants() {
+        assert_eq!(python("asin(x) + abs(x)"), "math.asin(x) + abs(x)");
+        assert_eq!(python("max(a, b) * pi"), "max(a, b) * math.pi");
+        assert_eq!(python("e(x + 1)"), "math.e * (x + 1)");
+        assert_eq!(python("sum(T) + mean(T)"), "sum(T) + (sum(T) / len(T))");
+        let source = module("T = [1, 2]\nm = sum(T) * mean(T)\n", Library::Numpy);
+        assert!(source.contains("m = np.sum(T) * np.mean(T)\n"));
+        let source = module("f(e) = e^2 + factorial(e)\n", Library::Numpy);
+        assert!(source.contains("return e ** 2 + np.prod(np.arange(1, e + 1))"));
+    }
+
+    #[test]
+    fn python_series() {
+        assert_eq!(python("sum(i, 1, n, i^2)"), "sum(i ** 2 for i in range(1, int(n) + 1))");
+        assert_eq!(python("prod(k, 1, 3, k)"), "math.prod(k for k in range(1, 3 + 1))");
+    }
+
+    #[test]
+    fn python_index_and_implicit_product() {
+        assert_eq!(python("x_1"), "x[0]");
+        assert_eq!(python("x_(i+1)"), "x[int(i + 1) - 1]");
+        assert_eq!(python("a(b + 1)"), "a * (b + 1)");
+    }
+
+    #[test]
+    fn python_keywords() {
+        let (_, statement) = parse_statement("lambda = 2").unwrap();
+        let Statement::Definition(definition) = statement else {
+            panic!("expected a definition")
+        };
+        let target = Target {
+            library: Library::Math,
+            env: Environment::new(),
+        };
+        assert_eq!(definition.python(&target), "lambda_ = 2");
+    }
+
+    #[test]
+    fn python_module() {
+        let source = module(
+            "/*title: Notes;author: piderking*/\n// Squares\nf(x) = x^2 - 1\ng(x) = f(x) / 2 + diff(f(x), x)\nk = [1, 2]\nf(3)\n",
+            Library::Math,
+        );
+        assert!(source.starts_with("\"\"\"Notes\n\npiderking\n\"\"\"\n\nimport math\n"));
+        assert!(
+            source.contains("import math\n\n\n# Squares\ndef f(x):\n    return x ** 2 - 1\n\n\n")
+        );
+        assert!(source.contains("def g(x):\n    return f(x) / 2 + 2 * x\n\n\nk = [1, 2]\n"));
+        // Top level expressions are not part of the module
+        assert!(!source.contains("f(3)"));
+    }
+
+    #[test]
+    fn python_complex() {
+        let source = module(
+            "/*imaginary: j*/\nz = 3 + 4j\nm = abs(z) + conj(z) + re(z) + arg(z)\n",
+            Library::Math,
+        );
+        assert!(source.contains("z = 3 + 4 * 1j\n"));
+        assert!(source.contains("m = abs(z) + z.conjugate() + z.real + cmath.phase(z)\n"));
+        // cmath in place of math, it takes complex arguments
+        let source = module(
+            "/*imaginary: i*/\nz = sqrt(x) + root(3, x) + ln(x) * pi + floor(x)\n",
+            Library::Math,
+        );
+        assert!(source.starts_with("import cmath\nimport math\n"));
+        assert!(source.contains(
+            "z = cmath.sqrt(x) + x ** (1 / 3) + cmath.log(x) * cmath.pi + math.floor(x)\n"
+        ));
+        let source = module("/*imaginary: i*/\nm = conj(2i) + im(x)\n", Library::Numpy);
+        assert!(source.contains("m = np.conj(2 * 1j) + np.imag(x)\n"));
+    }
+
+    #[test]
+    fn python_conditions() {
+        assert_eq!(
+            python("0 < x <= 1 and not x = 2"),
+            "0 < x <= 1 and not x == 2"
+        );
+        assert_eq!(
+            python("{ x if x > 0; -x if x < 0 } * 2"),
+            "(x if x > 0 else -x if x < 0 else math.nan) * 2"
+        );
+        let source = module(
+            "f(x) = { x if 0 < x < 1 and x != 0.5; 0 otherwise }\n",
+            Library::Numpy,
+        );
+        assert!(source.contains("return np.select([((0 < x) & (x < 1)) & (x != 1 / 2)], [x], 0)"));
+    }
+
+    #[test]
+    fn python_module_numpy() {
+        let source = module("f(x) = sqrt(x) + exp(x)\nk = [1, 2]\n", Library::Numpy);
+        assert!(source.starts_with("import numpy as np\n"));
+        assert!(source.contains("return np.sqrt(x) + np.exp(x)"));
+        assert!(source.contains("k = np.array([1, 2])"));
+    }
+}
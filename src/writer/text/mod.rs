@@ -1,7 +1,8 @@
 use std::{char, fmt::format};
 
 use crate::ast::{
-    Comment, Definition, Statement, base::{Ident, Value}, expressions::Expression
+    Comment, Definition, Statement, base::{Ident, Value}, expressions::Expression,
+    system_func::System,
 };
 
 // For Parsing the Files into our AST
@@ -21,6 +22,16 @@ impl ToString for Value {
         match self {
             Value::Char(n) => format!("'{}'", char::from_u32(*n as u32).unwrap() ),
             Value::Number(n) => n.to_string(),
+            Value::Complex(z) => z.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::List(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
         }
     }
 }
@@ -47,7 +58,34 @@ impl ToString for Expression {
                     .join(",")
             ),
             Expression::Empty => stringify!().to_string(),
-            Expression::System(system) => todo!(),
+            Expression::System(system) => system.to_string(),
+        }
+    }
+}
+
+impl ToString for System {
+    fn to_string(&self) -> String {
+        match self {
+            System::Print(expression) => format!("print({})", expression.to_string()),
+            System::Simplify(expression) => format!("simplify({})", expression.to_string()),
+            System::Import(path) => format!("import(\"{}\")", path),
+            System::Frame(path) => format!("frame(\"{}\")", path),
+            System::Data(path, None) => format!("data(\"{}\")", path),
+            System::Plot(expression, x, a, b) => format!(
+                "plot({}, {}, {}, {})",
+                expression.to_string(),
+                x.to_string(),
+                a.to_string(),
+                b.to_string()
+            ),
+            System::Json(path, None) => format!("json(\"{}\")", path),
+            System::Json(path, Some(field)) => format!("json(\"{}\", \"{}\")", path, field),
+            System::Data(path, Some(column)) => {
+                format!("data(\"{}\").{}", path, column.to_string())
+            }
+            System::Diff(expression, x) => {
+                format!("diff({}, {})", expression.to_string(), x.to_string())
+            }
         }
     }
 }
@@ -108,7 +146,7 @@ mod tests {
     fn constant_stringify() {
         assert_eq!(
             "10".to_string(),
-            Expression::Constant(Value::Number(10.0)).to_string()
+            Expression::Constant(Value::from(10)).to_string()
         )
     }
 
@@ -0,0 +1,577 @@
+use crate::{
+    ast::{
+        Comment, Definition, Statement,
+        base::{Ident, Value},
+        expressions::Expression,
+        module::{Metadata, Module},
+        opperations::{
+            Add, Compare, Div, Exp, Index, Logic, Multi, Neg, Not, Opperation, Piecewise,
+            Precedence, Root, Series, Sub,
+        },
+        system_func::System,
+    },
+    builtins,
+    calculus::simplify,
+    parser::markdown::{Alignment, Inline, ListItem, MarkdownElement, math_statement, parse_prose},
+    symbols::{GREEK, Symbols},
+};
+
+// Render as Typst math (without the surrounding $ $), except for markdown which is markup
+pub trait Typst {
+    fn typst(&self) -> String;
+}
+
+// Fractions, roots and attachments are already grouped visually
+fn precedence(expression: &Expression) -> Precedence {
+    match expression {
+        Expression::Opperations(opperation) if opperation.ty() == "div" => Precedence::Atom,
+        expression => expression.precedence(),
+    }
+}
+
+// Typst form of an operand, in parentheses when it binds looser than `min`
+fn group(expression: &Expression, min: Precedence) -> String {
+    if precedence(expression) < min {
+        format!("({})", expression.typst())
+    } else {
+        expression.typst()
+    }
+}
+
+// Markup text, characters with a meaning in Typst are escaped
+pub fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' | '*' | '_' | '`' | '$' | '#' | '@' | '<' | '>' | '[' | ']' | '=' | '~' => {
+                format!("\\{}", c)
+            }
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+// Text inside math ("...")
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Typst for Ident {
+    fn typst(&self) -> String {
+        // Multi letter names would be read as symbols (or fail), greek letters are symbols
+        if self.inner.chars().count() > 1 && !GREEK.contains(&self.inner.as_str()) {
+            quoted(&self.inner)
+        } else {
+            self.inner.clone()
+        }
+    }
+}
+
+impl Typst for Value {
+    fn typst(&self) -> String {
+        match self {
+            Value::Char(_) => quoted(&self.to_string()),
+            Value::Number(n) => match n.fraction() {
+                Some((n, d)) if n < 0 => format!("-frac({}, {})", -n, d),
+                Some((n, d)) => format!("frac({}, {})", n, d),
+                None => n.to_string(),
+            },
+            Value::Complex(z) => z.render(
+                |n| Value::Number(n).typst(),
+                &z.unit.to_string(),
+                " + ",
+                " - ",
+            ),
+            Value::Bool(b) => quoted(&b.to_string()),
+            Value::List(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(|v| v.typst())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl Typst for Expression {
+    fn typst(&self) -> String {
+        match self {
+            Expression::Opperations(opperation) => opperation.typst(),
+            Expression::List(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(|i| i.typst())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Expression::FunctionCall(ident, args) => {
+                // Typst knows the built-ins (sin, arcsin, abs, floor), n! and conj are written out
+                match (ident.inner.as_str(), args.as_slice()) {
+                    ("factorial", [u]) => return format!("{}!", group(u, Precedence::Atom)),
+                    ("conj", [u]) => return format!("overline({})", u.typst()),
+                    _ => {}
+                }
+                // Typst has no mean, it's written like any other long name
+                let name = if let Some(builtin) = builtins::lookup(&ident.inner)
+                    && builtin.name != "mean"
+                {
+                    match builtin.latex.strip_prefix('\\') {
+                        Some(name) => name.to_string(),
+                        None => builtin.name.to_string(),
+                    }
+                } else if ident.inner.chars().count() > 1 {
+                    format!("op({})", quoted(&ident.inner))
+                } else {
+                    ident.typst()
+                };
+                format!(
+                    "{}({})",
+                    name,
+                    args.iter()
+                        .map(|a| a.typst())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            Expression::System(system) => system.typst(),
+            Expression::VariableRef(ident) => ident.typst(),
+            Expression::Constant(value) => value.typst(),
+            Expression::Empty => String::new(),
+        }
+    }
+}
+
+impl Typst for System {
+    fn typst(&self) -> String {
+        match self {
+            System::Print(expression) => expression.typst(),
+            System::Simplify(expression) => simplify(expression).typst(),
+            System::Diff(expression, x) => format!(
+                "frac(d, d {}) {}",
+                x.typst(),
+                group(expression, Precedence::Atom)
+            ),
+            System::Import(_)
+            | System::Frame(_)
+            | System::Data(..)
+            | System::Json(..)
+            | System::Plot(..) => quoted(&self.to_string()),
+        }
+    }
+}
+
+impl Typst for Add {
+    fn typst(&self) -> String {
+        self.terms
+            .iter()
+            .map(|t| group(t, Precedence::Sum))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", Symbols::Addition.as_typst()))
+    }
+}
+
+impl Typst for Sub {
+    fn typst(&self) -> String {
+        format!(
+            "{} {} {}",
+            group(&self.first, Precedence::Sum),
+            Symbols::Subtraction.as_typst(),
+            group(&self.second, Precedence::Product)
+        )
+    }
+}
+
+impl Typst for Multi {
+    fn typst(&self) -> String {
+        self.terms
+            .iter()
+            .map(|t| group(t, Precedence::Product))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", Symbols::Multiplication.as_typst()))
+    }
+}
+
+impl Typst for Div {
+    fn typst(&self) -> String {
+        format!("frac({}, {})", self.first.typst(), self.second.typst())
+    }
+}
+
+impl Typst for Neg {
+    fn typst(&self) -> String {
+        format!(
+            "{}{}",
+            Symbols::Subtraction.as_typst(),
+            group(&self.value, Precedence::Prefix)
+        )
+    }
+}
+
+impl Typst for Exp {
+    // Typst drops the parentheses around an attachment: x^(n+1)
+    fn typst(&self) -> String {
+        let base = if self.base.precedence() < Precedence::Atom {
+            format!("({})", self.base.typst())
+        } else {
+            self.base.typst()
+        };
+        format!("{}^({})", base, self.exponent.typst())
+    }
+}
+
+impl Typst for Root {
+    fn typst(&self) -> String {
+        match &self.degree {
+            Expression::Constant(Value::Number(n)) if *n == 2.0 => {
+                format!("sqrt({})", self.radicand.typst())
+            }
+            degree => format!("root({}, {})", degree.typst(), self.radicand.typst()),
+        }
+    }
+}
+
+impl Typst for Index {
+    fn typst(&self) -> String {
+        format!(
+            "{}_({})",
+            group(&self.base, Precedence::Atom),
+            self.index.typst()
+        )
+    }
+}
+
+impl Typst for Series {
+    fn typst(&self) -> String {
+        format!(
+            "{}_({} = {})^({}) {}",
+            self.symbol.as_typst(),
+            self.index.typst(),
+            self.from.typst(),
+            self.to.typst(),
+            group(&self.body, Precedence::Product)
+        )
+    }
+}
+
+impl Typst for Compare {
+    fn typst(&self) -> String {
+        self.rest
+            .iter()
+            .fold(group(&self.first, Precedence::Sum), |acc, (symbol, e)| {
+                format!(
+                    "{} {} {}",
+                    acc,
+                    symbol.as_typst(),
+                    group(e, Precedence::Sum)
+                )
+            })
+    }
+}
+
+impl Typst for Logic {
+    fn typst(&self) -> String {
+        self.terms
+            .iter()
+            .map(|t| group(t, self.precedence()))
+            .collect::<Vec<_>>()
+            .join(&format!(" {} ", self.symbol.as_typst()))
+    }
+}
+
+impl Typst for Not {
+    fn typst(&self) -> String {
+        format!(
+            "{} {}",
+            Symbols::Not.as_typst(),
+            group(&self.value, Precedence::Not)
+        )
+    }
+}
+
+impl Typst for Piecewise {
+    // cases(x & "if " x > 0, 0 & "otherwise")
+    fn typst(&self) -> String {
+        let mut rows = self
+            .cases
+            .iter()
+            .map(|(value, condition)| format!("{} & \"if \" {}", value.typst(), condition.typst()))
+            .collect::<Vec<_>>();
+        if let Some(otherwise) = &self.otherwise {
+            rows.push(format!("{} & \"otherwise\"", otherwise.typst()));
+        }
+        format!("cases({})", rows.join(", "))
+    }
+}
+
+impl Typst for Definition {
+    fn typst(&self) -> String {
+        match self {
+            Definition::Function(ident, vars, expression) => {
+                let vars = vars.iter().map(|v| v.typst()).collect::<Vec<_>>();
+                format!(
+                    "{}({}) = {}",
+                    ident.typst(),
+                    vars.join(", "),
+                    expression.typst()
+                )
+            }
+            Definition::Constant(ident, expression) => {
+                format!("{} = {}", ident.typst(), expression.typst())
+            }
+        }
+    }
+}
+
+impl Typst for Statement {
+    fn typst(&self) -> String {
+        match self {
+            Statement::Definition(definition) => definition.typst(),
+            Statement::Expression(expression) => expression.typst(),
+            Statement::Comment(comment) => quoted(&comment.to_string()),
+        }
+    }
+}
+
+impl Typst for Inline {
+    fn typst(&self) -> String {
+        match self {
+            Inline::Text(text) => escape(text),
+            Inline::Bold(text) => format!("*{}*", escape(text)),
+            Inline::Italic(text) => format!("_{}_", escape(text)),
+            Inline::Code(text) => format!("`{}`", text),
+            Inline::Link(text, url) => format!("#link({})[{}]", quoted(url), escape(text)),
+            Inline::Image(alt, src) => {
+                format!("#box(image({}, alt: {}))", quoted(src), quoted(alt))
+            }
+            Inline::Math(text) => match math_statement(text) {
+                Some(statement) => format!("${}$", statement.typst()),
+                None => format!("`{}`", text),
+            },
+        }
+    }
+}
+
+fn text(inlines: &[Inline]) -> String {
+    inlines.iter().map(|i| i.typst()).collect()
+}
+
+// Nested lists are indented under their item
+fn list(marker: &str, items: &[ListItem]) -> String {
+    items
+        .iter()
+        .map(|item| {
+            let children = item
+                .children
+                .iter()
+                .flat_map(|child| {
+                    child
+                        .typst()
+                        .lines()
+                        .map(|line| format!("\n  {}", line))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<String>();
+            format!("{} {}{}", marker, text(&item.text), children)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Typst for MarkdownElement {
+    fn typst(&self) -> String {
+        match self {
+            MarkdownElement::Heading(level, text) => {
+                format!("{} {}", "=".repeat(*level as usize), escape(text))
+            }
+            MarkdownElement::Paragraph(inlines) => text(inlines),
+            MarkdownElement::CodeBlock(lang, code) => format!("```{}\n{}```", lang, code),
+            MarkdownElement::UnorderedList(items) => list("-", items),
+            MarkdownElement::OrderedList(items) => list("+", items),
+            MarkdownElement::Blockquote(inlines) => {
+                format!("#quote(block: true)[{}]", text(inlines))
+            }
+            MarkdownElement::Table(alignments, header, rows) => {
+                let align = alignments
+                    .iter()
+                    .map(|alignment| match alignment {
+                        Alignment::Left => "left",
+                        Alignment::Center => "center",
+                        Alignment::Right => "right",
+                        Alignment::None => "auto",
+                    })
+                    .collect::<Vec<_>>();
+                let cells = |row: &[Vec<Inline>]| {
+                    row.iter()
+                        .map(|cell| format!("[{}]", text(cell)))
+                        .collect::<Vec<_>>()
+                };
+                let mut arguments = vec![
+                    format!("columns: {}", alignments.len()),
+                    format!("align: ({},)", align.join(", ")),
+                    format!("table.header({})", cells(header).join(", ")),
+                ];
+                arguments.extend(rows.iter().flat_map(|row| cells(row)));
+                format!("#table({})", arguments.join(", "))
+            }
+            MarkdownElement::HorizontalRule => "#line(length: 100%)".to_string(),
+            MarkdownElement::LineBreak => String::new(),
+        }
+    }
+}
+
+// Standalone .typ document, prose comments become markup and statements block equations
+pub fn document(metadata: &Metadata, statements: &[Statement]) -> String {
+    let mut out = Vec::new();
+
+    let mut settings = Vec::new();
+    if let Some(title) = &metadata.title {
+        settings.push(format!("title: {}", quoted(title)));
+    }
+    if let Some(author) = &metadata.author {
+        settings.push(format!("author: {}", quoted(author)));
+    }
+    if !settings.is_empty() {
+        out.push(format!("#set document({})", settings.join(", ")));
+        out.push(String::new());
+    }
+
+    if let Some(title) = &metadata.title {
+        out.push(format!("#align(center, text(17pt)[*{}*])", escape(title)));
+    }
+    let byline = [&metadata.author, &metadata.author_email]
+        .into_iter()
+        .flatten()
+        .map(|s| escape(s))
+        .collect::<Vec<_>>();
+    if !byline.is_empty() {
+        out.push(format!("#align(center)[{}]", byline.join(" \\ ")));
+    }
+    if metadata.title.is_some() || !byline.is_empty() {
+        out.push(String::new());
+    }
+
+    for statement in statements {
+        match statement {
+            Statement::Comment(Comment::Multi(text)) => {
+                for element in parse_prose(text) {
+                    out.push(element.typst());
+                    out.push(String::new());
+                }
+            }
+            Statement::Comment(Comment::Single(text)) => out.push(format!("// {}", text.trim())),
+            statement => out.push(format!("$ {} $", statement.typst())),
+        }
+    }
+
+    out.join("\n") + "\n"
+}
+
+impl Typst for Module {
+    fn typst(&self) -> String {
+        document(self.metadata(), self.statements())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::{
+        ast::module::ParseMode,
+        parser::math::{expression::pexp, module::parse_module, statement::parse_statement},
+    };
+
+    fn typst(input: &str) -> String {
+        let (rest, expression) = pexp(input).expect("should parse");
+        assert!(rest.is_empty(), "unparsed: {:?}", rest);
+        expression.typst()
+    }
+
+    #[test]
+    fn typst_fraction_and_product() {
+        assert_eq!(typst("(a + 1) / b"), "frac(a + 1, b)");
+        assert_eq!(typst("2(a + 2)"), "2 dot (a + 2)");
+        // Exact numbers are fractions even when they were written as decimals
+        assert_eq!(typst("0.25"), "frac(1, 4)");
+    }
+
+    #[test]
+    fn typst_roots() {
+        assert_eq!(typst("sqrt(x)"), "sqrt(x)");
+        assert_eq!(typst("root(n, x)"), "root(n, x)");
+    }
+
+    #[test]
+    fn typst_builtins() {
+        assert_eq!(typst("acos(x)"), "arccos(x)");
+        assert_eq!(typst("abs(x) + ceil(x)"), "abs(x) + ceil(x)");
+        assert_eq!(typst("factorial(n)"), "n!");
+        assert_eq!(typst("foo(x)"), "op(\"foo\")(x)");
+    }
+
+    #[test]
+    fn typst_series() {
+        assert_eq!(typst("Σ(i, 1, n, i^2)"), "sum_(i = 1)^(n) i^(2)");
+        assert_eq!(typst("prod(k, 0, 2, k + 1)"), "product_(k = 0)^(2) (k + 1)");
+    }
+
+    #[test]
+    fn typst_piecewise() {
+        assert_eq!(
+            typst("{ x if x >= 0 or x = -1; 0 otherwise }"),
+            "cases(x & \"if \" x >= 0 or x = -1, 0 & \"otherwise\")"
+        );
+    }
+
+    #[test]
+    fn typst_attachments() {
+        assert_eq!(typst("x_(i+1)"), "x_(i + 1)");
+        assert_eq!(typst("x^2"), "x^(2)");
+        assert_eq!(typst("(a - b)^2"), "(a - b)^(2)");
+        assert_eq!(typst("-x^2"), "-x^(2)");
+    }
+
+    #[test]
+    fn typst_function_calls() {
+        assert_eq!(typst("f(x, y)"), "f(x, y)");
+        assert_eq!(typst("mean(x)"), "op(\"mean\")(x)");
+        assert_eq!(typst("sum(T)"), "sum(T)");
+    }
+
+    #[test]
+    fn typst_definition() {
+        let (_, statement) = parse_statement("f(x) = x^2 - 1").unwrap();
+        assert_eq!(statement.typst(), "f(x) = x^(2) - 1");
+    }
+
+    #[test]
+    fn typst_markdown() {
+        let (_, statement) =
+            parse_statement("/* # Intro\nSome **bold** [link](a.html) */").unwrap();
+        let Statement::Comment(comment) = statement else {
+            panic!("expected a comment")
+        };
+        let markup = parse_prose(&comment.to_string())
+            .iter()
+            .map(|e| e.typst())
+            .collect::<Vec<_>>();
+        assert_eq!(markup[0], "= Intro");
+        assert_eq!(markup[1], "Some *bold* #link(\"a.html\")[link]");
+    }
+
+    #[test]
+    fn typst_module_document() {
+        let (_, module) = parse_module(
+            "/*title: Notes;author: piderking*/\nf(x) = x\n",
+            true,
+            ParseMode::Frame,
+        )
+        .unwrap();
+        let document = module.typst();
+
+        assert!(document.starts_with("#set document(title: \"Notes\", author: \"piderking\")"));
+        assert!(document.contains("$ f(x) = x $"));
+    }
+}
@@ -0,0 +1,18 @@
+"""Formulas shared with the notebooks"""
+import math
+
+scale = 3
+
+
+def square(x):
+    return x ** 2
+
+
+def area(r):
+    """Scaled square"""
+    s = square(r)
+    return scale * s
+
+
+if __name__ == "__main__":
+    print(area(2))